@@ -0,0 +1,135 @@
+//! A tiny WebDAV server for trying out WebDAV sync without a real one.
+//!
+//! It serves one folder and understands just the requests Focus Hub sends:
+//! PROPFIND, GET, PUT and MKCOL.
+//!
+//!     cargo run --example webdav_server -- <folder> [port] [user:password]
+//!
+//! Then point File → Sync Through WebDAV at `http://localhost:<port>/focushub/`.
+//! Two copies of Focus Hub in different folders can sync through it.
+
+use base64::Engine;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let Some(root) = args.next().map(PathBuf::from) else {
+        eprintln!("usage: webdav_server <folder> [port] [user:password]");
+        return;
+    };
+    let port = args.next().unwrap_or_else(|| "8080".to_owned());
+    let auth = args.next().map(|credentials| {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+        format!("Basic {encoded}")
+    });
+
+    fs::create_dir_all(&root).expect("could not create the folder");
+    let server = Server::http(format!("127.0.0.1:{port}")).expect("could not listen");
+    println!("Serving {} on http://127.0.0.1:{port}/", root.display());
+    serve(&server, &root, auth.as_deref());
+}
+
+/// Answers requests for `root` until the server is dropped. The app's tests
+/// run against this too.
+pub fn serve(server: &Server, root: &Path, auth: Option<&str>) {
+    for request in server.incoming_requests() {
+        let authorized = auth.is_none_or(|auth| {
+            request
+                .headers()
+                .iter()
+                .any(|h| h.field.equiv("Authorization") && h.value.as_str() == auth)
+        });
+        let method = request.method().to_string();
+        let url = request.url().to_owned();
+        let result = if authorized {
+            handle(request, root)
+        } else {
+            let challenge = header("WWW-Authenticate", "Basic realm=\"focushub\"");
+            request.respond(Response::empty(401).with_header(challenge))
+        };
+        if let Err(e) = result {
+            eprintln!("{method} {url}: {e}");
+        }
+    }
+}
+
+fn handle(mut request: Request, root: &Path) -> std::io::Result<()> {
+    // Only plain names are expected, so anything that could leave the root is refused.
+    let relative = request.url().trim_start_matches('/').to_owned();
+    if relative.split('/').any(|part| part == "..") {
+        return request.respond(Response::empty(403));
+    }
+    let path = root.join(&relative);
+    match request.method() {
+        Method::Get => match fs::read(&path) {
+            Ok(bytes) => request.respond(Response::from_data(bytes)),
+            Err(_) => request.respond(Response::empty(404)),
+        },
+        Method::Put => {
+            let mut body = Vec::new();
+            request.as_reader().read_to_end(&mut body)?;
+            fs::write(&path, body)?;
+            request.respond(Response::empty(201))
+        }
+        Method::NonStandard(method) if method.as_str() == "MKCOL" => {
+            fs::create_dir_all(&path)?;
+            request.respond(Response::empty(201))
+        }
+        Method::NonStandard(method) if method.as_str() == "PROPFIND" => {
+            if !path.exists() {
+                return request.respond(Response::empty(404));
+            }
+            let xml = header("Content-Type", "application/xml; charset=utf-8");
+            request.respond(
+                Response::from_string(multistatus(&relative, &path)?)
+                    .with_status_code(207)
+                    .with_header(xml),
+            )
+        }
+        _ => request.respond(Response::empty(405)),
+    }
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field, value).expect("valid header")
+}
+
+// Lists `path` and, for a folder, the files directly inside it.
+fn multistatus(relative: &str, path: &Path) -> std::io::Result<String> {
+    let mut xml =
+        String::from(r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:">"#);
+    let mut add = |href: String, path: &Path| -> std::io::Result<()> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let etag = format!("\"{modified:x}-{:x}\"", metadata.len());
+        xml += &format!(
+            "<d:response><d:href>/{href}</d:href><d:propstat><d:prop><d:getetag>{etag}</d:getetag></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>"
+        );
+        Ok(())
+    };
+    add(relative.to_owned(), path)?;
+    if path.is_dir() {
+        let folder = relative.trim_end_matches('/');
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let href = if folder.is_empty() {
+                    name
+                } else {
+                    format!("{folder}/{name}")
+                };
+                add(href, &entry.path())?;
+            }
+        }
+    }
+    xml += "</d:multistatus>";
+    Ok(xml)
+}
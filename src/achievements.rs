@@ -0,0 +1,238 @@
+use crate::app_data::{AppData, Stats};
+use crate::streaks::StreakSummary;
+use chrono::{Days, Local, NaiveDate};
+
+/// What has to be true for an achievement to unlock. Each rule measures one
+/// number that has to reach `target`.
+pub enum Rule {
+    /// Total focused hours.
+    TotalHours(u32),
+    /// Completed pomodoros, ever.
+    TotalSessions(u32),
+    /// Completed pomodoros in a single calendar month.
+    SessionsInMonth(u32),
+    /// Longest streak, in days.
+    Streak(u32),
+    /// Tasks finished, ever.
+    TasksFinished(u32),
+    /// Days in a row on which every scheduled task was finished.
+    AllTasksDoneDays(u32),
+}
+
+pub struct Achievement {
+    /// Stable key used to remember the unlock; never change it once released.
+    pub id: &'static str,
+    pub icon: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub rule: Rule,
+}
+
+/// Every achievement, in gallery order. Adding one here is all it takes.
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "first_session",
+        icon: "🌱",
+        name: "First Step",
+        description: "Complete your first pomodoro.",
+        rule: Rule::TotalSessions(1),
+    },
+    Achievement {
+        id: "first_10_hours",
+        icon: "⏳",
+        name: "Ten Hours In",
+        description: "Focus for 10 hours in total.",
+        rule: Rule::TotalHours(10),
+    },
+    Achievement {
+        id: "hundred_hours",
+        icon: "🏛",
+        name: "Centurion",
+        description: "Focus for 100 hours in total.",
+        rule: Rule::TotalHours(100),
+    },
+    Achievement {
+        id: "streak_7",
+        icon: "🔥",
+        name: "On Fire",
+        description: "Keep a 7-day streak.",
+        rule: Rule::Streak(7),
+    },
+    Achievement {
+        id: "streak_30",
+        icon: "☄",
+        name: "Unstoppable",
+        description: "Keep a 30-day streak.",
+        rule: Rule::Streak(30),
+    },
+    Achievement {
+        id: "month_50_sessions",
+        icon: "📆",
+        name: "Busy Month",
+        description: "Complete 50 pomodoros in one month.",
+        rule: Rule::SessionsInMonth(50),
+    },
+    Achievement {
+        id: "tasks_100",
+        icon: "✅",
+        name: "Getting Things Done",
+        description: "Finish 100 tasks.",
+        rule: Rule::TasksFinished(100),
+    },
+    Achievement {
+        id: "clean_slate_5",
+        icon: "🧹",
+        name: "Clean Slate",
+        description: "Finish all of the day's tasks 5 days in a row.",
+        rule: Rule::AllTasksDoneDays(5),
+    },
+];
+
+impl Rule {
+    pub fn target(&self) -> u32 {
+        match *self {
+            Rule::TotalHours(n)
+            | Rule::TotalSessions(n)
+            | Rule::SessionsInMonth(n)
+            | Rule::Streak(n)
+            | Rule::TasksFinished(n)
+            | Rule::AllTasksDoneDays(n) => n,
+        }
+    }
+
+    /// The rule's current measurement.
+    pub fn measure(&self, data: &AppData, stats: &Stats, streak: &StreakSummary) -> u32 {
+        match self {
+            Rule::TotalHours(_) => (stats.daily_study_seconds.values().sum::<u64>() / 3600) as u32,
+            Rule::TotalSessions(_) => stats.daily_sessions.values().sum(),
+            Rule::SessionsInMonth(_) => stats.monthly_sessions.values().max().copied().unwrap_or(0),
+            Rule::Streak(_) => streak.longest,
+            Rule::TasksFinished(_) => data.all_tasks().filter(|t| t.is_done()).count() as u32,
+            Rule::AllTasksDoneDays(_) => longest_clean_run(data),
+        }
+    }
+}
+
+// Longest run of consecutive days that had tasks and finished all of them.
+fn longest_clean_run(data: &AppData) -> u32 {
+    let mut clean: Vec<NaiveDate> = data
+        .todos_by_date
+        .iter()
+        .filter(|(_, todos)| !todos.is_empty() && todos.iter().all(|t| t.is_done()))
+        .map(|(date, _)| *date)
+        .collect();
+    clean.sort();
+
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for date in clean {
+        run = if previous.is_some_and(|p| p + Days::new(1) == date) {
+            run + 1
+        } else {
+            1
+        };
+        longest = longest.max(run);
+        previous = Some(date);
+    }
+    longest
+}
+
+/// Unlocks every achievement whose rule is now met and returns the new ones.
+pub fn update(
+    data: &mut AppData,
+    stats: &Stats,
+    streak: &StreakSummary,
+) -> Vec<&'static Achievement> {
+    let now = Local::now().naive_local();
+    let unlocked: Vec<_> = ACHIEVEMENTS
+        .iter()
+        .filter(|a| !data.achievements.contains_key(a.id))
+        .filter(|a| a.rule.measure(data, stats, streak) >= a.rule.target())
+        .collect();
+    for achievement in &unlocked {
+        data.achievements.insert(achievement.id.to_owned(), now);
+    }
+    unlocked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_data::{STATUS_DONE, TodoItem};
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    fn streak(longest: u32) -> StreakSummary {
+        StreakSummary {
+            current: 0,
+            longest,
+            freezes: 0,
+        }
+    }
+
+    fn unlocked_ids(
+        data: &mut AppData,
+        stats: &Stats,
+        streak: &StreakSummary,
+    ) -> Vec<&'static str> {
+        update(data, stats, streak).iter().map(|a| a.id).collect()
+    }
+
+    #[test]
+    fn achievements_unlock_at_their_threshold_and_only_once() {
+        let mut data = AppData::default();
+        let mut stats = Stats {
+            daily_sessions: [(day(1), 1)].into(),
+            daily_study_seconds: [(day(1), 10 * 3600 - 1)].into(),
+            ..Stats::default()
+        };
+        assert_eq!(
+            unlocked_ids(&mut data, &stats, &streak(6)),
+            ["first_session"]
+        );
+
+        stats.daily_study_seconds.insert(day(2), 1);
+        assert_eq!(
+            unlocked_ids(&mut data, &stats, &streak(7)),
+            ["first_10_hours", "streak_7"]
+        );
+        assert!(unlocked_ids(&mut data, &stats, &streak(7)).is_empty());
+        assert_eq!(data.achievements.len(), 3);
+    }
+
+    #[test]
+    fn a_clean_slate_needs_consecutive_days_with_everything_done() {
+        let mut data = AppData::default();
+        let done = || {
+            let mut todo = TodoItem::new("Read".to_owned(), None);
+            todo.status = STATUS_DONE;
+            todo
+        };
+        for d in [1, 2, 4, 5, 6] {
+            data.todos_by_date.insert(day(d), vec![done()]);
+        }
+        // An open task spoils the day; an empty list doesn't count.
+        data.todos_by_date
+            .get_mut(&day(5))
+            .unwrap()
+            .push(TodoItem::new("Write".to_owned(), None));
+        data.todos_by_date.insert(day(3), Vec::new());
+        assert_eq!(longest_clean_run(&data), 2);
+
+        data.todos_by_date.get_mut(&day(5)).unwrap().pop();
+        assert_eq!(longest_clean_run(&data), 3);
+    }
+
+    #[test]
+    fn monthly_sessions_count_the_best_month() {
+        let stats = Stats {
+            monthly_sessions: [("2025-2".to_owned(), 30), ("2025-3".to_owned(), 50)].into(),
+            ..Stats::default()
+        };
+        let rule = Rule::SessionsInMonth(50);
+        assert_eq!(rule.measure(&AppData::default(), &stats, &streak(0)), 50);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::app_data::{FocusSession, Stats};
+use chrono::{Datelike, Days, NaiveDate, TimeDelta, Timelike};
+
+/// Study seconds for every day from `from` to `to` inclusive, including days without any.
+pub fn daily_totals(stats: &Stats, from: NaiveDate, to: NaiveDate) -> Vec<(NaiveDate, u64)> {
+    from.iter_days()
+        .take_while(|d| *d <= to)
+        .map(|d| (d, stats.daily_study_seconds.get(&d).cloned().unwrap_or(0)))
+        .collect()
+}
+
+/// Study seconds per Monday-based week, keyed by the week's first day.
+/// Weeks at the edges only count the days inside the range.
+pub fn weekly_totals(stats: &Stats, from: NaiveDate, to: NaiveDate) -> Vec<(NaiveDate, u64)> {
+    let mut weeks: Vec<(NaiveDate, u64)> = Vec::new();
+    for (date, seconds) in daily_totals(stats, from, to) {
+        let monday = date - Days::new(date.weekday().num_days_from_monday() as u64);
+        match weeks.last_mut() {
+            Some((week, total)) if *week == monday => *total += seconds,
+            _ => weeks.push((monday, seconds)),
+        }
+    }
+    weeks
+}
+
+/// Mean daily study seconds over the `window` days ending on each day of the range.
+/// Days before `from` are included so the average is meaningful from the first day.
+pub fn rolling_average(
+    stats: &Stats,
+    from: NaiveDate,
+    to: NaiveDate,
+    window: u64,
+) -> Vec<(NaiveDate, f64)> {
+    let lead_in = from - Days::new(window.saturating_sub(1));
+    let daily = daily_totals(stats, lead_in, to);
+    daily
+        .windows(window as usize)
+        .map(|days| {
+            let total: u64 = days.iter().map(|(_, s)| s).sum();
+            (days[days.len() - 1].0, total as f64 / window as f64)
+        })
+        .collect()
+}
+
+/// Seconds focused in each hour of the day, from sessions that started in the range.
+/// Sessions crossing an hour boundary are split between the hours.
+pub fn hour_distribution(sessions: &[FocusSession], from: NaiveDate, to: NaiveDate) -> [u64; 24] {
+    let mut hours = [0; 24];
+    for session in sessions {
+        let date = session.start.date();
+        if date < from || date > to {
+            continue;
+        }
+        let mut time = session.start;
+        let mut remaining = session.seconds;
+        while remaining > 0 {
+            let into_hour = (time.minute() * 60 + time.second()) as u64;
+            let chunk = remaining.min(3600 - into_hour);
+            hours[time.hour() as usize] += chunk;
+            remaining -= chunk;
+            time += TimeDelta::seconds(chunk as i64);
+        }
+    }
+    hours
+}
+
+/// The entry with the most study time, if any time was studied at all.
+pub fn best(totals: &[(NaiveDate, u64)]) -> Option<(NaiveDate, u64)> {
+    totals
+        .iter()
+        .copied()
+        .filter(|(_, seconds)| *seconds > 0)
+        .max_by_key(|(_, seconds)| *seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    // 2025-03-02 is a Sunday, so these days span two weeks.
+    fn stats() -> Stats {
+        Stats {
+            daily_study_seconds: [(day(1), 600), (day(2), 1200), (day(3), 300), (day(5), 900)]
+                .into(),
+            ..Stats::default()
+        }
+    }
+
+    #[test]
+    fn totals_cover_every_day_and_split_at_mondays() {
+        let daily = daily_totals(&stats(), day(2), day(4));
+        assert_eq!(daily, [(day(2), 1200), (day(3), 300), (day(4), 0)]);
+        // The first week only counts the days inside the range.
+        let weekly = weekly_totals(&stats(), day(2), day(9));
+        assert_eq!(
+            weekly,
+            [
+                (NaiveDate::from_ymd_opt(2025, 2, 24).unwrap(), 1200),
+                (day(3), 1200)
+            ]
+        );
+        assert_eq!(best(&daily), Some((day(2), 1200)));
+        assert_eq!(best(&[(day(4), 0)]), None);
+    }
+
+    #[test]
+    fn the_rolling_average_looks_back_before_the_range() {
+        let averages = rolling_average(&stats(), day(2), day(3), 2);
+        assert_eq!(averages, [(day(2), 900.0), (day(3), 750.0)]);
+    }
+
+    #[test]
+    fn sessions_are_split_across_the_hours_they_span() {
+        let session = |d, h, m, seconds| FocusSession {
+            start: day(d).and_hms_opt(h, m, 0).unwrap(),
+            seconds,
+            task: None,
+            project: None,
+        };
+        let sessions = [
+            // 09:50 for 25 minutes: 10 minutes at 9, 15 at 10.
+            session(2, 9, 50, 1500),
+            // 23:30 for an hour runs into the next day's first hour.
+            session(2, 23, 30, 3600),
+            // Outside the range.
+            session(4, 9, 0, 1500),
+        ];
+        let hours = hour_distribution(&sessions, day(1), day(3));
+        assert_eq!(hours[9], 600);
+        assert_eq!(hours[10], 900);
+        assert_eq!(hours[23], 1800);
+        assert_eq!(hours[0], 1800);
+        assert_eq!(hours.iter().sum::<u64>(), 5100);
+    }
+}
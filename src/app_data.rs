@@ -1,59 +1,903 @@
-use chrono::NaiveDate;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs;
+use crate::profiles;
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-const DATA_FILE: &str = "focushub_data.json";
+/// Ids of the board columns that always exist. Tasks in the done column count as completed.
+pub const STATUS_TODO: u64 = 0;
+pub const STATUS_IN_PROGRESS: u64 = 1;
+pub const STATUS_DONE: u64 = 2;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TodoItem {
+    #[serde(default)]
+    pub id: u64,
     pub text: String,
-    pub completed: bool,
+    /// Id of the board column the task sits in.
+    #[serde(default)]
+    pub status: u64,
+    #[serde(default)]
+    pub project: Option<u64>,
+    /// Ids of tasks that must be finished before this one can be worked on.
+    #[serde(default)]
+    pub blocked_by: Vec<u64>,
+    /// The iCalendar UID of an imported task, used to recognise it on re-import.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    /// A todo.txt style priority, 'A' being the most important.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<char>,
+    /// The day the task was finished on, if it is done.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_on: Option<NaiveDate>,
+    // Superseded by `status`; only read to migrate data saved by older versions.
+    #[serde(default, rename = "completed", skip_serializing)]
+    legacy_completed: bool,
+}
+
+impl TodoItem {
+    pub fn new(text: String, project: Option<u64>) -> Self {
+        Self {
+            id: new_id(),
+            text,
+            status: STATUS_TODO,
+            project,
+            blocked_by: Vec::new(),
+            uid: None,
+            priority: None,
+            completed_on: None,
+            legacy_completed: false,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.status == STATUS_DONE
+    }
+
+    /// The `#tags` written in the task's text, without the leading `#`.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.text
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix('#'))
+            .filter(|tag| !tag.is_empty())
+    }
+}
+
+/// A column of the task board; each column is one possible task status.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BoardColumn {
+    pub id: u64,
+    pub name: String,
+}
+
+fn default_board_columns() -> Vec<BoardColumn> {
+    [
+        (STATUS_TODO, "To Do"),
+        (STATUS_IN_PROGRESS, "In Progress"),
+        (STATUS_DONE, "Done"),
+    ]
+    .into_iter()
+    .map(|(id, name)| BoardColumn {
+        id,
+        name: name.to_owned(),
+    })
+    .collect()
+}
+
+/// An undated backlog of tasks, e.g. "Thesis" or "Errands".
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub tasks: Vec<TodoItem>,
+}
+
+/// The list a task lives in: a calendar day or a project's undated backlog.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TaskList {
+    Date(NaiveDate),
+    Project(u64),
+}
+
+/// Identifies a task by the list it lives in and its position in that list.
+/// Used as the drag-and-drop payload between the to-do list and the calendar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaskRef {
+    pub list: TaskList,
+    pub index: usize,
+}
+
+/// An appointment imported from an external calendar.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub start: NaiveDateTime,
+    /// Exclusive end; all-day events end at midnight after their last day.
+    pub end: Option<NaiveDateTime>,
+    pub all_day: bool,
+}
+
+impl CalendarEvent {
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        let first = self.start.date();
+        let last = match self.end {
+            // An end at midnight doesn't reach into that day.
+            Some(end) if end > self.start => (end - TimeDelta::seconds(1)).date(),
+            _ => first,
+        };
+        first <= date && date <= last
+    }
+}
+
+/// A stretch of uninterrupted work time, optionally linked to the task that was focused.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FocusSession {
+    pub start: NaiveDateTime,
+    pub seconds: u64,
+    #[serde(default)]
+    pub task: Option<u64>,
+    #[serde(default)]
+    pub project: Option<u64>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct Stats {
     #[serde(default)]
     pub daily_study_seconds: HashMap<NaiveDate, u64>,
+    /// Completed pomodoros per day. Older files called these "streaks".
+    #[serde(default, alias = "daily_streaks")]
+    pub daily_sessions: HashMap<NaiveDate, u32>,
+    #[serde(default, alias = "monthly_streaks")]
+    pub monthly_sessions: HashMap<String, u32>,
+    #[serde(default)]
+    pub sessions: Vec<FocusSession>,
+    /// Streak freezes earned and not yet spent.
+    #[serde(default)]
+    pub streak_freezes: u32,
+    /// Missed days that a streak freeze was spent on.
+    #[serde(default)]
+    pub frozen_days: BTreeSet<NaiveDate>,
+    /// The last day whose streak outcome (freeze earned or spent) has been settled.
     #[serde(default)]
-    pub daily_streaks: HashMap<NaiveDate, u32>,
+    pub streak_checked_through: Option<NaiveDate>,
+    /// Whether the daily goal was met, for each day a goal was set.
     #[serde(default)]
-    pub monthly_streaks: HashMap<String, u32>,
+    pub daily_goal_results: BTreeMap<NaiveDate, bool>,
+    /// Whether the weekly goal was met, keyed by the Monday of each week.
+    #[serde(default)]
+    pub weekly_goal_results: BTreeMap<NaiveDate, bool>,
+    /// The last day whose goal outcome has been recorded.
+    #[serde(default)]
+    pub goals_checked_through: Option<NaiveDate>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl Stats {
+    /// Total focused seconds per task id, from the session log.
+    pub fn task_seconds(&self) -> HashMap<u64, u64> {
+        let mut totals = HashMap::new();
+        for session in &self.sessions {
+            if let Some(task) = session.task {
+                *totals.entry(task).or_insert(0) += session.seconds;
+            }
+        }
+        totals
+    }
+
+    /// Total focused seconds per project id, from the session log.
+    pub fn project_seconds(&self) -> HashMap<u64, u64> {
+        let mut totals = HashMap::new();
+        for session in &self.sessions {
+            if let Some(project) = session.project {
+                *totals.entry(project).or_insert(0) += session.seconds;
+            }
+        }
+        totals
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Reward {
+    /// Identifies the reward across devices when syncing.
+    #[serde(default)]
+    pub id: u64,
     pub name: String,
     pub completed: bool,
+    #[serde(default)]
+    pub cost: Cost,
+    /// The level needed before this reward can be redeemed; 0 for none.
+    #[serde(default)]
+    pub min_level: u32,
+    /// Repeatable rewards can be claimed again once their cooldown has passed.
+    #[serde(default)]
+    pub repeatable: bool,
+    #[serde(default)]
+    pub cooldown_minutes: u32,
+    /// When the reward was claimed, oldest first.
+    #[serde(default)]
+    pub claims: Vec<NaiveDateTime>,
+    /// Free-form group name; empty for uncategorized.
+    #[serde(default)]
+    pub category: String,
+    /// An emoji shown before the name.
+    #[serde(default)]
+    pub icon: String,
+    /// Path of a picture shown with the reward.
+    #[serde(default)]
+    pub image: Option<String>,
+}
+
+impl Reward {
+    pub fn new(name: String, cost: Cost) -> Self {
+        Self {
+            id: new_id(),
+            name,
+            completed: false,
+            cost,
+            min_level: 0,
+            repeatable: false,
+            cooldown_minutes: 0,
+            claims: Vec::new(),
+            category: String::new(),
+            icon: String::new(),
+            image: None,
+        }
+    }
+
+    /// How long until a repeatable reward can be claimed again, if it is cooling down.
+    pub fn cooldown_left(&self, now: NaiveDateTime) -> Option<TimeDelta> {
+        let last = *self.claims.last()?;
+        let left = last + TimeDelta::minutes(self.cooldown_minutes as i64) - now;
+        (left > TimeDelta::zero()).then_some(left)
+    }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+/// The price of a reward. Pomodoros are converted to points when redeemed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Cost {
+    Points(u32),
+    Pomodoros(u32),
+}
+
+impl Default for Cost {
+    fn default() -> Self {
+        Cost::Points(0)
+    }
+}
+
+/// One change to the points balance.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub time: NaiveDateTime,
+    /// Points earned (positive) or spent (negative).
+    pub amount: i64,
+    pub reason: String,
+}
+
+/// The points balance and every transaction that led to it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Wallet {
+    pub balance: i64,
+    pub ledger: Vec<Transaction>,
+}
+
+impl Wallet {
+    pub fn record(&mut self, transaction: Transaction) {
+        self.balance += transaction.amount;
+        self.ledger.push(transaction);
+    }
+
+    /// Takes back the most recent matching transaction, if it is still in the ledger.
+    pub fn revert(&mut self, transaction: &Transaction) {
+        if let Some(index) = self.ledger.iter().rposition(|t| t == transaction) {
+            self.balance -= self.ledger.remove(index).amount;
+        }
+    }
+}
+
+/// What a day needs for it to count towards a streak.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StreakGoal {
+    Minutes(u32),
+    Sessions(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GoalUnit {
+    Minutes,
+    Sessions,
+}
+
+/// Daily and weekly focus targets. A target of zero means no goal.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Goals {
+    pub unit: GoalUnit,
+    pub daily: u32,
+    /// Use `weekday_targets` instead of `daily`.
+    pub per_weekday: bool,
+    /// Daily targets, Monday first.
+    pub weekday_targets: [u32; 7],
+    pub weekly: u32,
+}
+
+impl Default for Goals {
+    fn default() -> Self {
+        Self {
+            unit: GoalUnit::Minutes,
+            daily: 0,
+            per_weekday: false,
+            weekday_targets: [0; 7],
+            weekly: 0,
+        }
+    }
+}
+
+/// How much XP each level takes: reaching level `n` needs
+/// `base * (n - 1) ^ exponent` XP in total.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelCurve {
+    pub base: u32,
+    pub exponent: f32,
+}
+
+impl Default for LevelCurve {
+    fn default() -> Self {
+        Self {
+            base: 60,
+            exponent: 1.5,
+        }
+    }
+}
+
+impl LevelCurve {
+    // Brings a hand-edited or synced curve into the ranges the settings menu
+    // allows; a zero base or exponent would make every level free.
+    fn deserialize_checked<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut curve = Self::deserialize(deserializer)?;
+        curve.base = curve.base.clamp(1, 10_000);
+        curve.exponent = if curve.exponent.is_finite() {
+            curve.exponent.clamp(1.0, 3.0)
+        } else {
+            Self::default().exponent
+        };
+        Ok(curve)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Move tasks onto today's list once the last task blocking them is finished.
+    pub surface_unblocked: bool,
+    /// Minutes of study a day needs for each successive shade of the calendar heatmap.
+    pub heatmap_thresholds: [u32; 4],
+    pub streak_goal: StreakGoal,
+    /// Weekdays, Monday first, that neither extend nor break a streak.
+    pub rest_days: [bool; 7],
+    pub goals: Goals,
+    #[serde(deserialize_with = "LevelCurve::deserialize_checked")]
+    pub level_curve: LevelCurve,
+    /// Open last week's review on the first launch of each week.
+    pub review_on_new_week: bool,
+    /// A todo.txt file kept in sync with the dated task lists.
+    pub todotxt_sync_path: Option<String>,
+    /// A shared folder, e.g. in Dropbox, used to sync with other devices.
+    pub sync_folder: Option<String>,
+    /// This device's name in the sync folder; made up when sync is first set up.
+    pub sync_device: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            surface_unblocked: false,
+            heatmap_thresholds: [15, 60, 120, 240],
+            streak_goal: StreakGoal::Minutes(25),
+            rest_days: [false; 7],
+            goals: Goals::default(),
+            level_curve: LevelCurve::default(),
+            review_on_new_week: false,
+            todotxt_sync_path: None,
+            sync_folder: None,
+            sync_device: String::new(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct AppData {
     pub todos_by_date: HashMap<NaiveDate, Vec<TodoItem>>,
     pub stats: Stats,
     pub rewards: Vec<Reward>,
     #[serde(default)]
     pub gif_path: Option<String>,
+    #[serde(default)]
+    pub projects: Vec<Project>,
+    #[serde(default = "default_board_columns")]
+    pub board_columns: Vec<BoardColumn>,
+    #[serde(default)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub wallet: Wallet,
+    /// When each achievement was unlocked, by achievement id.
+    #[serde(default)]
+    pub achievements: BTreeMap<String, NaiveDateTime>,
+    /// The Monday of the last week the automatic review was shown in.
+    #[serde(default)]
+    pub last_review_week: Option<NaiveDate>,
+    #[serde(default)]
+    pub events: Vec<CalendarEvent>,
+}
+
+impl Default for AppData {
+    fn default() -> Self {
+        Self {
+            todos_by_date: HashMap::new(),
+            stats: Stats::default(),
+            rewards: Vec::new(),
+            gif_path: None,
+            projects: Vec::new(),
+            board_columns: default_board_columns(),
+            settings: Settings::default(),
+            wallet: Wallet::default(),
+            achievements: BTreeMap::new(),
+            last_review_week: None,
+            events: Vec::new(),
+        }
+    }
 }
 
-fn get_data_path() -> Result<PathBuf, std::io::Error> {
+impl AppData {
+    pub fn tasks(&self, list: TaskList) -> &[TodoItem] {
+        match list {
+            TaskList::Date(date) => self.todos_by_date.get(&date).map(Vec::as_slice),
+            TaskList::Project(id) => self.project(id).map(|p| p.tasks.as_slice()),
+        }
+        .unwrap_or_default()
+    }
+
+    pub fn tasks_mut(&mut self, list: TaskList) -> Option<&mut Vec<TodoItem>> {
+        match list {
+            TaskList::Date(date) => Some(self.todos_by_date.entry(date).or_default()),
+            TaskList::Project(id) => self
+                .projects
+                .iter_mut()
+                .find(|p| p.id == id)
+                .map(|p| &mut p.tasks),
+        }
+    }
+
+    pub fn task_mut(&mut self, task: TaskRef) -> Option<&mut TodoItem> {
+        self.tasks_mut(task.list)
+            .and_then(|tasks| tasks.get_mut(task.index))
+    }
+
+    /// Every task, scheduled or in a project backlog.
+    pub fn all_tasks(&self) -> impl Iterator<Item = &TodoItem> {
+        let undated = self.projects.iter().flat_map(|p| p.tasks.iter());
+        self.todos_by_date.values().flatten().chain(undated)
+    }
+
+    /// Every task together with where it lives.
+    pub fn task_refs(&self) -> impl Iterator<Item = (TaskRef, &TodoItem)> {
+        let dated = self
+            .todos_by_date
+            .iter()
+            .map(|(date, tasks)| (TaskList::Date(*date), tasks));
+        let undated = self
+            .projects
+            .iter()
+            .map(|p| (TaskList::Project(p.id), &p.tasks));
+        dated.chain(undated).flat_map(|(list, tasks)| {
+            tasks
+                .iter()
+                .enumerate()
+                .map(move |(index, task)| (TaskRef { list, index }, task))
+        })
+    }
+
+    /// Imported events on `date`, earliest first.
+    pub fn events_on(&self, date: NaiveDate) -> Vec<&CalendarEvent> {
+        let mut events: Vec<_> = self.events.iter().filter(|e| e.occurs_on(date)).collect();
+        events.sort_by_key(|e| (!e.all_day, e.start));
+        events
+    }
+
+    pub fn task_by_id(&self, id: u64) -> Option<&TodoItem> {
+        self.all_tasks().find(|t| t.id == id)
+    }
+
+    /// The unfinished tasks that `task` is waiting on. Links to deleted tasks are ignored.
+    pub fn open_blockers(&self, task: &TodoItem) -> Vec<&TodoItem> {
+        task.blocked_by
+            .iter()
+            .filter_map(|&id| self.task_by_id(id))
+            .filter(|blocker| !blocker.is_done())
+            .collect()
+    }
+
+    /// Whether making `task` wait on `blocker` would close a dependency cycle,
+    /// i.e. `blocker` already (transitively) waits on `task`.
+    pub fn would_create_cycle(&self, task: u64, blocker: u64) -> bool {
+        let mut stack = vec![blocker];
+        let mut visited = HashSet::new();
+        while let Some(id) = stack.pop() {
+            if id == task {
+                return true;
+            }
+            if visited.insert(id)
+                && let Some(current) = self.task_by_id(id)
+            {
+                stack.extend(&current.blocked_by);
+            }
+        }
+        false
+    }
+
+    /// Tasks that become unblocked once `finished` is done: every task waiting
+    /// on it whose other blockers are already finished.
+    pub fn unblocked_by(&self, finished: u64) -> Vec<TaskRef> {
+        self.task_refs()
+            .filter(|(_, task)| !task.is_done() && task.blocked_by.contains(&finished))
+            .filter(|(_, task)| {
+                task.blocked_by
+                    .iter()
+                    .all(|&id| id == finished || self.task_by_id(id).is_none_or(TodoItem::is_done))
+            })
+            .map(|(task_ref, _)| task_ref)
+            .collect()
+    }
+
+    pub fn project(&self, id: u64) -> Option<&Project> {
+        self.projects.iter().find(|p| p.id == id)
+    }
+
+    /// Removes the task at `from` and inserts it at `to`, where `to.index` is
+    /// its position in the target list after the move.
+    /// Does nothing if either list no longer exists, so a stale undo entry
+    /// can't lose the task.
+    pub fn relocate_todo(&mut self, from: TaskRef, to: TaskRef) {
+        if self.tasks_mut(to.list).is_none() {
+            return;
+        }
+        let Some(source) = self.tasks_mut(from.list) else {
+            return;
+        };
+        if from.index >= source.len() {
+            return;
+        }
+        let item = source.remove(from.index);
+        if let Some(target) = self.tasks_mut(to.list) {
+            target.insert(to.index.min(target.len()), item);
+        }
+    }
+
+    pub fn column_name(&self, status: u64) -> Option<&str> {
+        self.board_columns
+            .iter()
+            .find(|c| c.id == status)
+            .map(|c| c.name.as_str())
+    }
+
+    /// Upgrades tasks saved by older versions, right after loading: those
+    /// without ids deserialize them as 0, and completion used to be a plain boolean.
+    ///
+    /// The ids given here only depend on where a task is and what it says, so
+    /// every device, and every run that doesn't save, upgrades the same file
+    /// to the same ids.
+    pub fn migrate_tasks(&mut self) {
+        let dated = self.todos_by_date.iter_mut().flat_map(|(date, tasks)| {
+            let list = date.to_string();
+            tasks
+                .iter_mut()
+                .enumerate()
+                .map(move |(i, t)| (format!("{list}/{i}"), t))
+        });
+        let undated = self.projects.iter_mut().flat_map(|p| {
+            let list = p.id.to_string();
+            p.tasks
+                .iter_mut()
+                .enumerate()
+                .map(move |(i, t)| (format!("project {list}/{i}"), t))
+        });
+        for (place, task) in dated.chain(undated) {
+            if task.id == 0 {
+                task.id = legacy_id(&format!("task {place} {}", task.text));
+            }
+            if std::mem::take(&mut task.legacy_completed) {
+                task.status = STATUS_DONE;
+            }
+        }
+        // Rewards saved before they had ids.
+        for (i, reward) in self.rewards.iter_mut().enumerate() {
+            if reward.id == 0 {
+                reward.id = legacy_id(&format!("reward {i} {}", reward.name));
+            }
+        }
+    }
+}
+
+/// Resolves where a task dragged from `from` ends up when dropped before
+/// `insert_at` in a list of `target_len` tasks, counted before the task is
+/// removed. Out-of-range insertion points append.
+pub fn drop_position(
+    from: TaskRef,
+    list: TaskList,
+    insert_at: usize,
+    target_len: usize,
+) -> TaskRef {
+    let mut index = insert_at.min(target_len);
+    if from.list == list && from.index < index {
+        index -= 1;
+    }
+    TaskRef { list, index }
+}
+
+/// Returns a fresh id for a task or project.
+///
+/// Ids are derived from the clock so that they stay unique across restarts.
+pub fn new_id() -> u64 {
+    static LAST: AtomicU64 = AtomicU64::new(0);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(1, |d| d.as_micros() as u64);
+    let mut last = LAST.load(Ordering::Relaxed);
+    loop {
+        let id = now.max(last + 1);
+        match LAST.compare_exchange_weak(last, id, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => return id,
+            Err(current) => last = current,
+        }
+    }
+}
+
+/// Ids from here up were made up by `legacy_id` rather than read off the clock.
+pub const FIRST_LEGACY_ID: u64 = 1 << 61;
+
+// An id for something saved before it had one, made from a description of
+// it with 64-bit FNV-1a. It lands between 2^61 and 2^62: far above the
+// clock-based ids of `new_id`, and still a valid SQLite integer.
+fn legacy_id(description: &str) -> u64 {
+    let hash = description
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    (hash >> 3) | FIRST_LEGACY_ID
+}
+
+/// The folder the executable is in.
+pub fn exe_dir() -> Result<PathBuf, std::io::Error> {
     let exe_path = std::env::current_exe()?;
-    let dir = exe_path
+    Ok(exe_path
         .parent()
         .unwrap_or(&PathBuf::from(""))
-        .to_path_buf();
-    Ok(dir.join(DATA_FILE))
+        .to_path_buf())
 }
 
-pub fn save(data: &AppData) -> Result<(), Box<dyn std::error::Error>> {
-    let path = get_data_path()?;
-    fs::write(path, serde_json::to_string_pretty(data)?)?;
-    Ok(())
+/// The path of a file kept with the active profile's data, like the data file.
+pub fn local_path(file_name: &str) -> Result<PathBuf, std::io::Error> {
+    Ok(profiles::dir(&profiles::active())?.join(file_name))
 }
 
-pub fn load() -> Result<AppData, Box<dyn std::error::Error>> {
-    let path = get_data_path()?;
-    let json_str = fs::read_to_string(path)?;
-    Ok(serde_json::from_str(&json_str)?)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY: &str = r#"{
+        "todos_by_date": {"2024-05-01": [
+            {"text": "Read", "completed": true},
+            {"text": "Read", "completed": false}
+        ]},
+        "stats": {},
+        "rewards": [{"name": "Coffee", "completed": false}]
+    }"#;
+
+    fn upgraded() -> AppData {
+        let mut data: AppData = serde_json::from_str(LEGACY).unwrap();
+        data.migrate_tasks();
+        data
+    }
+
+    #[test]
+    fn legacy_ids_are_the_same_on_every_upgrade() {
+        let (first, second) = (upgraded(), upgraded());
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let ids = |data: &AppData| -> Vec<u64> {
+            data.todos_by_date[&date].iter().map(|t| t.id).collect()
+        };
+        assert_eq!(ids(&first), ids(&second));
+        assert_ne!(ids(&first)[0], ids(&first)[1]);
+        assert_eq!(first.rewards[0].id, second.rewards[0].id);
+        assert!(first.todos_by_date[&date][0].is_done());
+        assert!(
+            ids(&first)
+                .iter()
+                .all(|&id| id > new_id() && id <= i64::MAX as u64)
+        );
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    fn at(list: TaskList, index: usize) -> TaskRef {
+        TaskRef { list, index }
+    }
+
+    fn texts(data: &AppData, list: TaskList) -> Vec<&str> {
+        data.tasks(list).iter().map(|t| t.text.as_str()).collect()
+    }
+
+    fn with_tasks(lists: &[(NaiveDate, &[&str])]) -> AppData {
+        let mut data = AppData::default();
+        for (date, tasks) in lists {
+            let tasks = tasks
+                .iter()
+                .map(|text| TodoItem::new((*text).to_owned(), None))
+                .collect();
+            data.todos_by_date.insert(*date, tasks);
+        }
+        data
+    }
+
+    #[test]
+    fn dropping_further_down_the_same_list_counts_the_gap_left_behind() {
+        let list = TaskList::Date(day(1));
+        // Dropped before the fourth of four tasks: it becomes the third.
+        assert_eq!(drop_position(at(list, 0), list, 3, 4), at(list, 2));
+        // Higher up, nothing shifts.
+        assert_eq!(drop_position(at(list, 3), list, 1, 4), at(list, 1));
+        // Past the end, it goes last.
+        assert_eq!(drop_position(at(list, 0), list, 9, 4), at(list, 3));
+        // In another list the source doesn't matter.
+        let other = TaskList::Date(day(2));
+        assert_eq!(drop_position(at(list, 0), other, 2, 2), at(other, 2));
+    }
+
+    #[test]
+    fn tasks_move_within_and_between_days() {
+        let (first, second) = (TaskList::Date(day(1)), TaskList::Date(day(2)));
+        let mut data = with_tasks(&[(day(1), &["a", "b", "c"]), (day(2), &["x"])]);
+
+        let to = drop_position(at(first, 0), first, 3, 3);
+        data.relocate_todo(at(first, 0), to);
+        assert_eq!(texts(&data, first), ["b", "c", "a"]);
+
+        data.relocate_todo(at(first, 1), at(second, 0));
+        assert_eq!(texts(&data, first), ["b", "a"]);
+        assert_eq!(texts(&data, second), ["c", "x"]);
+
+        // A day with no list yet gets one.
+        let third = TaskList::Date(day(3));
+        data.relocate_todo(at(second, 1), at(third, 5));
+        assert_eq!(texts(&data, third), ["x"]);
+    }
+
+    #[test]
+    fn a_move_to_a_missing_project_keeps_the_task() {
+        let list = TaskList::Date(day(1));
+        let mut data = with_tasks(&[(day(1), &["a"])]);
+        data.relocate_todo(at(list, 0), at(TaskList::Project(7), 0));
+        data.relocate_todo(at(list, 4), at(list, 0));
+        assert_eq!(texts(&data, list), ["a"]);
+    }
+
+    #[test]
+    fn backlog_tasks_are_found_and_can_be_scheduled() {
+        let mut data = with_tasks(&[(day(1), &["a"])]);
+        let mut project = Project {
+            id: new_id(),
+            name: "Thesis".to_owned(),
+            tasks: Vec::new(),
+        };
+        let project_id = project.id;
+        let task = TodoItem::new("Outline".to_owned(), Some(project_id));
+        let id = task.id;
+        project.tasks.push(task);
+        let backlog = TaskList::Project(project_id);
+        data.projects.push(project);
+
+        assert_eq!(
+            data.task_by_id(id).map(|t| t.text.as_str()),
+            Some("Outline")
+        );
+        assert_eq!(data.all_tasks().count(), 2);
+
+        data.relocate_todo(at(backlog, 0), at(TaskList::Date(day(1)), 1));
+        assert!(data.tasks(backlog).is_empty());
+        assert_eq!(texts(&data, TaskList::Date(day(1))), ["a", "Outline"]);
+        // It keeps its project when scheduled.
+        assert_eq!(data.task_by_id(id).unwrap().project, Some(project_id));
+    }
+
+    #[test]
+    fn focus_time_adds_up_per_project_and_task() {
+        let start = day(1).and_hms_opt(9, 0, 0).unwrap();
+        let session = |seconds, task, project| FocusSession {
+            start,
+            seconds,
+            task,
+            project,
+        };
+        let stats = Stats {
+            sessions: vec![
+                session(1500, Some(1), Some(10)),
+                session(600, Some(2), Some(10)),
+                session(300, None, Some(20)),
+                session(900, None, None),
+            ],
+            ..Stats::default()
+        };
+        assert_eq!(
+            stats.project_seconds(),
+            HashMap::from([(10, 2100), (20, 300)])
+        );
+        assert_eq!(stats.task_seconds(), HashMap::from([(1, 1500), (2, 600)]));
+    }
+
+    // Tasks on day 1, each waiting on the ones listed with it.
+    fn chain(blockers: &[&[usize]]) -> AppData {
+        let mut data = AppData::default();
+        let mut tasks: Vec<_> = (0..blockers.len())
+            .map(|i| TodoItem::new(i.to_string(), None))
+            .collect();
+        let ids: Vec<u64> = tasks.iter().map(|t| t.id).collect();
+        for (task, blockers) in tasks.iter_mut().zip(blockers) {
+            task.blocked_by = blockers.iter().map(|&i| ids[i]).collect();
+        }
+        data.todos_by_date.insert(day(1), tasks);
+        data
+    }
+
+    fn id(data: &AppData, index: usize) -> u64 {
+        data.todos_by_date[&day(1)][index].id
+    }
+
+    #[test]
+    fn dependency_cycles_are_found_through_every_step() {
+        // 2 waits on 1, which waits on 0.
+        let data = chain(&[&[], &[0], &[1]]);
+        assert!(data.would_create_cycle(id(&data, 0), id(&data, 2)));
+        assert!(data.would_create_cycle(id(&data, 0), id(&data, 0)));
+        assert!(!data.would_create_cycle(id(&data, 2), id(&data, 0)));
+        // Links to deleted tasks lead nowhere.
+        assert!(!data.would_create_cycle(id(&data, 0), 42));
+    }
+
+    #[test]
+    fn a_task_is_unblocked_once_its_last_blocker_is_done() {
+        // 2 waits on 0 and 1; 3 waits on 0 and a deleted task.
+        let mut data = chain(&[&[], &[], &[0, 1], &[0]]);
+        data.todos_by_date.get_mut(&day(1)).unwrap()[3]
+            .blocked_by
+            .push(42);
+        let list = TaskList::Date(day(1));
+        assert_eq!(data.unblocked_by(id(&data, 0)), [at(list, 3)]);
+        assert_eq!(data.open_blockers(&data.todos_by_date[&day(1)][2]).len(), 2);
+
+        data.todos_by_date.get_mut(&day(1)).unwrap()[1].status = STATUS_DONE;
+        assert_eq!(data.unblocked_by(id(&data, 0)), [at(list, 2), at(list, 3)]);
+        assert_eq!(data.open_blockers(&data.todos_by_date[&day(1)][2]).len(), 1);
+    }
+
+    #[test]
+    fn a_reward_cools_down_after_its_last_claim() {
+        let mut reward = Reward::new("Coffee".to_owned(), Cost::Points(10));
+        let claimed = day(1).and_hms_opt(9, 0, 0).unwrap();
+        reward.repeatable = true;
+        reward.cooldown_minutes = 90;
+        assert_eq!(reward.cooldown_left(claimed), None);
+
+        reward.claims.push(claimed);
+        let later = claimed + TimeDelta::minutes(30);
+        assert_eq!(reward.cooldown_left(later), Some(TimeDelta::minutes(60)));
+        assert_eq!(reward.cooldown_left(claimed + TimeDelta::minutes(90)), None);
+    }
 }
@@ -0,0 +1,491 @@
+//! Commands for scripts and terminals, working on the same data as the
+//! window without opening it.
+//!
+//! ```text
+//! focushub todo add "Read chapter 3" --date tomorrow
+//! focushub todo list
+//! focushub todo done 4821
+//! focushub stats --week
+//! focushub export --format csv
+//! ```
+
+use crate::app_data::{AppData, STATUS_DONE, STATUS_TODO, TaskList, TaskRef, TodoItem};
+use crate::export::{self, ExportFormat};
+use crate::history::{self, Command, History};
+use crate::storage::{self, Storage};
+use crate::ui::format_duration;
+use crate::{goals, points, profiles};
+use chrono::{Datelike, Days, Local, NaiveDate};
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::io;
+use std::path::PathBuf;
+
+// Read instead of asking, for scripts that work on an encrypted profile.
+const PASSPHRASE_VAR: &str = "FOCUSHUB_PASSPHRASE";
+
+#[derive(Parser)]
+#[command(
+    name = "focushub",
+    version,
+    about = "A focus timer with tasks, stats and rewards.",
+    after_help = "Without a command, the Focus Hub window opens.\n\
+        Tasks can't be added or finished while the window has the profile open.\n\
+        An encrypted profile's passphrase is read from FOCUSHUB_PASSPHRASE, or asked for."
+)]
+pub struct Cli {
+    /// Use this profile's data instead of the one opened last.
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Add, list and finish tasks.
+    #[command(subcommand)]
+    Todo(TodoCommand),
+
+    /// Show focus time and sessions per day, this week unless told otherwise.
+    Stats {
+        /// This week, from Monday.
+        #[arg(long, conflicts_with_all = ["month", "from", "to"])]
+        week: bool,
+        /// This month, from the 1st.
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        month: bool,
+        /// First day to show.
+        #[arg(long, value_parser = parse_date, requires = "to")]
+        from: Option<NaiveDate>,
+        /// Last day to show.
+        #[arg(long, value_parser = parse_date, requires = "from")]
+        to: Option<NaiveDate>,
+    },
+
+    /// Write tasks, focus sessions and daily totals to files.
+    Export {
+        #[arg(long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+        /// First day to export; everything if left out.
+        #[arg(long, value_parser = parse_date, requires = "to")]
+        from: Option<NaiveDate>,
+        /// Last day to export.
+        #[arg(long, value_parser = parse_date, requires = "from")]
+        to: Option<NaiveDate>,
+        /// Where to write, or - for standard output. CSV is split into
+        /// *_todos.csv, *_sessions.csv and *_daily.csv next to it.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TodoCommand {
+    /// Add a task and print its id.
+    Add {
+        text: String,
+        /// The day to schedule it on: today, tomorrow, yesterday or YYYY-MM-DD.
+        #[arg(long, value_parser = parse_date, default_value = "today")]
+        date: NaiveDate,
+        /// The name of the project it belongs to.
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// List the tasks of a day.
+    List {
+        /// Today, tomorrow, yesterday or YYYY-MM-DD.
+        #[arg(long, value_parser = parse_date, default_value = "today")]
+        date: NaiveDate,
+        /// Leave out finished tasks.
+        #[arg(long)]
+        open: bool,
+    },
+
+    /// Mark a task as done, earning its points.
+    Done {
+        /// The id shown by `todo list`; its last few digits are enough if no
+        /// other task ends with them.
+        id: String,
+    },
+}
+
+fn parse_date(text: &str) -> Result<NaiveDate, String> {
+    let today = Local::now().date_naive();
+    match text.to_lowercase().as_str() {
+        "today" => Ok(today),
+        "tomorrow" => Ok(today + Days::new(1)),
+        "yesterday" => Ok(today - Days::new(1)),
+        other => NaiveDate::parse_from_str(other, "%Y-%m-%d")
+            .map_err(|_| "use today, tomorrow, yesterday or YYYY-MM-DD".to_owned()),
+    }
+}
+
+/// On Windows the app is built as a GUI program, which has no console of its
+/// own, so output would go nowhere. This borrows the console of the shell it
+/// was started from, if there is one. The shell doesn't wait for GUI
+/// programs, so its prompt can come back before the output does.
+#[cfg(windows)]
+pub fn attach_console() {
+    use windows_sys::Win32::System::Console::{ATTACH_PARENT_PROCESS, AttachConsole};
+    // Fails harmlessly when started from Explorer.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+pub fn attach_console() {}
+
+/// Runs `command` on `profile`, or on the profile opened last, and returns
+/// the exit code.
+pub fn run(profile: Option<String>, command: CliCommand) -> i32 {
+    match execute(profile, command) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("focushub: {e}");
+            1
+        }
+    }
+}
+
+fn execute(profile: Option<String>, command: CliCommand) -> Result<(), Box<dyn Error>> {
+    let profile = match profile {
+        Some(name) if !profiles::exists(&name) => {
+            return Err(format!("there is no profile called {name}").into());
+        }
+        Some(name) => name,
+        None => profiles::last_used().unwrap_or_else(|| profiles::DEFAULT_PROFILE.to_owned()),
+    };
+    // Only the window changes which profile is preselected next time.
+    profiles::select(&profile);
+    let changes_data = matches!(
+        command,
+        CliCommand::Todo(TodoCommand::Add { .. } | TodoCommand::Done { .. })
+    );
+    // The window would overwrite the change when it saves.
+    if changes_data && !profiles::claim_active()? {
+        return Err(format!(
+            "Focus Hub has {profile} open; make the change there, or close it first"
+        )
+        .into());
+    }
+    let mut storage = storage::open()?;
+    if storage.is_locked() {
+        let passphrase = match std::env::var(PASSPHRASE_VAR) {
+            Ok(passphrase) => passphrase,
+            Err(_) => rpassword::prompt_password(format!("Passphrase for {profile}: "))?,
+        };
+        storage.unlock(&passphrase)?;
+    }
+
+    let today = Local::now().date_naive();
+    match command {
+        CliCommand::Todo(TodoCommand::Add {
+            text,
+            date,
+            project,
+        }) => add_todo(storage.as_mut(), text, date, project),
+        CliCommand::Todo(TodoCommand::List { date, open }) => {
+            list_todos(&load(storage.as_mut())?, date, open);
+            Ok(())
+        }
+        CliCommand::Todo(TodoCommand::Done { id }) => finish_todo(storage.as_mut(), &id),
+        CliCommand::Stats {
+            week: _,
+            month,
+            from,
+            to,
+        } => {
+            let (from, to) = match (from, to) {
+                (Some(from), Some(to)) => (from, to),
+                _ if month => (today.with_day(1).unwrap_or(today), today),
+                _ => (goals::week_start(today), today),
+            };
+            print_stats(storage.as_mut(), from, to)
+        }
+        CliCommand::Export {
+            format,
+            from,
+            to,
+            output,
+        } => export(&load(storage.as_mut())?, format, from.zip(to), output),
+    }
+}
+
+// Whether `e` only means the profile was never saved, so it starts out
+// empty, as it does in the window.
+fn never_saved(e: &(dyn Error + 'static)) -> bool {
+    e.downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::NotFound)
+}
+
+fn load(storage: &mut dyn Storage) -> Result<AppData, Box<dyn Error>> {
+    match storage.load() {
+        Err(e) if never_saved(e.as_ref()) => Ok(AppData::default()),
+        result => result,
+    }
+}
+
+// Applies `command` the way the window would, then saves.
+fn apply(
+    storage: &mut dyn Storage,
+    data: &mut AppData,
+    mut command: Command,
+) -> Result<(), Box<dyn Error>> {
+    if data.settings.surface_unblocked {
+        command = history::surface_unblocked(command, data, Local::now().date_naive());
+    }
+    command = points::award_for_tasks(command, data);
+    History::default().execute(command, data);
+    storage.save(data)
+}
+
+fn add_todo(
+    storage: &mut dyn Storage,
+    text: String,
+    date: NaiveDate,
+    project: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut data = load(storage)?;
+    let text = text.trim().to_owned();
+    if text.is_empty() {
+        return Err("the task has no text".into());
+    }
+    let project = match project {
+        Some(name) => Some(
+            data.projects
+                .iter()
+                .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+                .map(|p| p.id)
+                .ok_or_else(|| format!("there is no project called {name}"))?,
+        ),
+        None => None,
+    };
+
+    let item = TodoItem::new(text, project);
+    let id = item.id;
+    let list = TaskList::Date(date);
+    let task = TaskRef {
+        list,
+        index: data.tasks(list).len(),
+    };
+    apply(storage, &mut data, Command::AddTodo { task, item })?;
+    println!("Added {id} to {date}.");
+    Ok(())
+}
+
+fn list_todos(data: &AppData, date: NaiveDate, open: bool) {
+    let tasks: Vec<&TodoItem> = data
+        .tasks(TaskList::Date(date))
+        .iter()
+        .filter(|t| !open || !t.is_done())
+        .collect();
+    if tasks.is_empty() {
+        println!("No tasks on {date}.");
+        return;
+    }
+    for task in tasks {
+        let mark = if task.is_done() { "[x]" } else { "[ ]" };
+        let mut line = format!("{mark} {}  {}", task.id, task.text);
+        if let Some(project) = task.project.and_then(|id| data.project(id)) {
+            line += &format!("  @{}", project.name);
+        }
+        if task.status != STATUS_TODO
+            && task.status != STATUS_DONE
+            && let Some(column) = data.column_name(task.status)
+        {
+            line += &format!("  ({column})");
+        }
+        if !task.is_done() && !data.open_blockers(task).is_empty() {
+            line += "  (blocked)";
+        }
+        println!("{line}");
+    }
+}
+
+fn finish_todo(storage: &mut dyn Storage, id: &str) -> Result<(), Box<dyn Error>> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("{id} isn't a task id").into());
+    }
+    let mut data = load(storage)?;
+    let matches: Vec<(TaskRef, &TodoItem)> = data
+        .task_refs()
+        .filter(|(_, t)| t.id.to_string().ends_with(id))
+        .collect();
+    let (task, item) = match matches.as_slice() {
+        [found] => *found,
+        [] => return Err(format!("no task has the id {id}").into()),
+        _ => return Err(format!("several tasks end in {id}; give more digits").into()),
+    };
+    if item.is_done() {
+        println!("“{}” is already done.", item.text);
+        return Ok(());
+    }
+
+    let text = item.text.clone();
+    let command = Command::SetStatus {
+        task,
+        old: item.status,
+        new: STATUS_DONE,
+        old_completed_on: item.completed_on,
+    };
+    let balance = data.wallet.balance;
+    apply(storage, &mut data, command)?;
+    let earned = data.wallet.balance - balance;
+    if earned > 0 {
+        println!("Finished “{text}” and earned {earned} points.");
+    } else {
+        println!("Finished “{text}”.");
+    }
+    Ok(())
+}
+
+fn print_stats(
+    storage: &mut dyn Storage,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<(), Box<dyn Error>> {
+    if from > to {
+        return Err("--from must not be after --to".into());
+    }
+    // Only the days asked for are read, however long the history is.
+    let days = match storage.daily_totals(from, to) {
+        Err(e) if never_saved(e.as_ref()) => Default::default(),
+        days => days?,
+    };
+    let (mut seconds, mut sessions) = (0, 0);
+    for date in from.iter_days().take_while(|d| *d <= to) {
+        let totals = days.get(&date).copied().unwrap_or_default();
+        println!(
+            "{}  {:>8}  {:>3} sessions",
+            date.format("%a %Y-%m-%d"),
+            format_duration(totals.study_seconds),
+            totals.sessions
+        );
+        seconds += totals.study_seconds;
+        sessions += totals.sessions;
+    }
+    println!(
+        "{:<14}  {:>8}  {sessions:>3} sessions",
+        "Total",
+        format_duration(seconds)
+    );
+    Ok(())
+}
+
+fn export(
+    data: &AppData,
+    format: ExportFormat,
+    dates: Option<(NaiveDate, NaiveDate)>,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let Some((from, to)) = dates.or_else(|| export::all_time(data, &data.stats)) else {
+        return Err("there is nothing to export yet".into());
+    };
+    if from > to {
+        return Err("--from must not be after --to".into());
+    }
+    let export = export::collect(data, &data.stats, from, to);
+
+    let path = output
+        .unwrap_or_else(|| PathBuf::from(format!("focushub_{from}_{to}.{}", format.extension())));
+    if path.as_os_str() == "-" {
+        match format {
+            ExportFormat::Json => println!("{}", export.to_json()?),
+            ExportFormat::Markdown => print!("{}", export.to_markdown()),
+            ExportFormat::Csv => {
+                return Err("CSV is written as three files, so it needs a file name".into());
+            }
+        }
+        return Ok(());
+    }
+    export.write(format, &path)?;
+    if format == ExportFormat::Csv {
+        let stem = path.with_extension("");
+        let stem = stem.display();
+        println!("Wrote {stem}_todos.csv, {stem}_sessions.csv and {stem}_daily.csv.");
+    } else {
+        println!("Wrote {}.", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{File, OpenOptions};
+
+    const PROFILE: &str = "test-cli";
+
+    fn focushub(args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let cli = Cli::try_parse_from(["focushub", "--profile", PROFILE].iter().chain(args))?;
+        execute(cli.profile, cli.command.unwrap())
+    }
+
+    // What the window holds while it has the profile open.
+    fn open_window() -> File {
+        let path = profiles::dir(PROFILE).unwrap().join("focushub.lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .unwrap();
+        file.try_lock().unwrap();
+        file
+    }
+
+    #[test]
+    fn an_open_window_keeps_out_changes_but_not_reads() {
+        let _turn = profiles::test_profile(PROFILE);
+        focushub(&["todo", "add", "Read"]).unwrap();
+        // Let go of the claim the command took, as its process would on exit.
+        profiles::select(profiles::DEFAULT_PROFILE);
+        profiles::claim_active().unwrap();
+
+        let window = open_window();
+        let error = focushub(&["todo", "add", "Write"]).unwrap_err();
+        assert!(error.to_string().contains("has test-cli open"), "{error}");
+        assert!(focushub(&["todo", "done", "1"]).is_err());
+        focushub(&["todo", "list"]).unwrap();
+        focushub(&["stats"]).unwrap();
+
+        drop(window);
+        focushub(&["todo", "add", "Write"]).unwrap();
+        profiles::select(PROFILE);
+        let data = storage::open().unwrap().load().unwrap();
+        assert_eq!(data.todos_by_date.values().flatten().count(), 2);
+    }
+
+    #[test]
+    fn ids_listed_for_an_old_data_file_still_work_after_saving() {
+        let _turn = profiles::test_profile(PROFILE);
+        let day = Local::now().date_naive();
+        let legacy = format!(
+            r#"{{"todos_by_date": {{"{day}": [{{"text": "Read", "completed": false}}, {{"text": "Write", "completed": false}}]}}, "stats": {{}}, "rewards": []}}"#
+        );
+        std::fs::write(storage::json::JsonStorage::path().unwrap(), legacy).unwrap();
+        // What `todo list` shows, read before anything is saved.
+        let listed: Vec<String> = load(storage::open().unwrap().as_mut())
+            .unwrap()
+            .todos_by_date[&day]
+            .iter()
+            .map(|t| t.id.to_string())
+            .collect();
+
+        for id in &listed {
+            focushub(&["todo", "done", id]).unwrap();
+        }
+        profiles::select(PROFILE);
+        let data = storage::open().unwrap().load().unwrap();
+        assert!(data.todos_by_date[&day].iter().all(TodoItem::is_done));
+        let saved: Vec<String> = data.todos_by_date[&day]
+            .iter()
+            .map(|t| t.id.to_string())
+            .collect();
+        assert_eq!(saved, listed);
+    }
+}
@@ -0,0 +1,210 @@
+//! Passphrase encryption for the data file.
+//!
+//! The key is stretched from the passphrase with Argon2id and the data is
+//! sealed with XChaCha20-Poly1305. A sealed file is a small JSON envelope
+//! holding everything needed to open it again except the passphrase, so it
+//! is easy to tell apart from a plain data file and the KDF cost can be
+//! raised later without breaking older files.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, OsRng, Payload};
+use chacha20poly1305::{AeadCore, KeyInit, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+const FORMAT: &str = "focushub-encrypted";
+const VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    format: String,
+    version: u32,
+    kdf: KdfParams,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct KdfParams {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+}
+
+impl KdfParams {
+    // Argon2's recommended minimums, with a fresh salt.
+    fn new() -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+            salt: BASE64.encode(salt),
+        }
+    }
+
+    // Bound into every ciphertext, so the envelope's header can't be swapped.
+    fn associated_data(&self) -> Vec<u8> {
+        format!(
+            "{FORMAT}:{VERSION}:argon2id:{}:{}:{}:{}",
+            self.memory_kib, self.iterations, self.parallelism, self.salt
+        )
+        .into_bytes()
+    }
+}
+
+/// A key stretched from the passphrase. Kept in memory while the app runs so
+/// saving doesn't have to run the slow KDF again.
+#[derive(Clone)]
+pub struct Key {
+    params: KdfParams,
+    bytes: [u8; 32],
+}
+
+impl Key {
+    /// A key for `passphrase` with a new salt, for sealing from now on.
+    pub fn new(passphrase: &str) -> Result<Self, Box<dyn Error>> {
+        Self::derive(passphrase, KdfParams::new())
+    }
+
+    fn derive(passphrase: &str, params: KdfParams) -> Result<Self, Box<dyn Error>> {
+        let salt = BASE64.decode(&params.salt)?;
+        let argon = Argon2::new(
+            Algorithm::Argon2id,
+            Version::V0x13,
+            Params::new(
+                params.memory_kib,
+                params.iterations,
+                params.parallelism,
+                Some(32),
+            )?,
+        );
+        let mut bytes = [0u8; 32];
+        argon.hash_password_into(passphrase.as_bytes(), &salt, &mut bytes)?;
+        Ok(Self { params, bytes })
+    }
+
+    /// Whether `passphrase` is the one this key came from.
+    pub fn matches(&self, passphrase: &str) -> bool {
+        Self::derive(passphrase, self.params.clone()).is_ok_and(|key| key.bytes == self.bytes)
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&self.bytes.into())
+    }
+
+    /// Encrypts `plaintext` into an envelope, ready to be written to disk.
+    pub fn seal(&self, plaintext: &[u8]) -> Result<String, Box<dyn Error>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let aad = self.params.associated_data();
+        let ciphertext = self.cipher().encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &aad,
+            },
+        )?;
+        let envelope = Envelope {
+            format: FORMAT.to_owned(),
+            version: VERSION,
+            kdf: self.params.clone(),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        Ok(serde_json::to_string_pretty(&envelope)?)
+    }
+
+    /// Decrypts an envelope this key sealed.
+    pub fn unseal(&self, text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let envelope = parse(text)?;
+        if envelope.kdf != self.params {
+            return Err("the file was sealed with a different passphrase".into());
+        }
+        self.decrypt(&envelope)
+    }
+
+    fn decrypt(&self, envelope: &Envelope) -> Result<Vec<u8>, Box<dyn Error>> {
+        let nonce = BASE64.decode(&envelope.nonce)?;
+        if nonce.len() != 24 {
+            return Err("the file is damaged".into());
+        }
+        let ciphertext = BASE64.decode(&envelope.ciphertext)?;
+        let aad = envelope.kdf.associated_data();
+        self.cipher()
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| "wrong passphrase, or the file is damaged".into())
+    }
+}
+
+fn parse(text: &str) -> Result<Envelope, Box<dyn Error>> {
+    let envelope: Envelope = serde_json::from_str(text)?;
+    if envelope.format != FORMAT || envelope.version > VERSION {
+        return Err("not a file this version of Focus Hub can decrypt".into());
+    }
+    Ok(envelope)
+}
+
+/// Whether `text` is a sealed envelope rather than plain data.
+pub fn is_sealed(text: &str) -> bool {
+    parse(text).is_ok()
+}
+
+/// Decrypts an envelope with `passphrase`, returning the contents and the
+/// key for sealing them again.
+pub fn open(text: &str, passphrase: &str) -> Result<(Vec<u8>, Key), Box<dyn Error>> {
+    let envelope = parse(text)?;
+    let key = Key::derive(passphrase, envelope.kdf.clone())?;
+    let plaintext = key.decrypt(&envelope)?;
+    Ok((plaintext, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &[u8] = br#"{"todos_by_date":{}}"#;
+
+    #[test]
+    fn sealed_data_opens_with_its_passphrase() {
+        let key = Key::new("correct horse").unwrap();
+        let sealed = key.seal(DATA).unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!is_sealed(std::str::from_utf8(DATA).unwrap()));
+
+        let (plaintext, opened) = open(&sealed, "correct horse").unwrap();
+        assert_eq!(plaintext, DATA);
+        assert!(opened.matches("correct horse"));
+        assert_eq!(opened.unseal(&sealed).unwrap(), DATA);
+    }
+
+    #[test]
+    fn a_wrong_passphrase_fails() {
+        let key = Key::new("correct horse").unwrap();
+        let sealed = key.seal(DATA).unwrap();
+        assert!(open(&sealed, "battery staple").is_err());
+        assert!(!key.matches("battery staple"));
+        // The same passphrase with another salt is another key.
+        assert!(Key::new("correct horse").unwrap().unseal(&sealed).is_err());
+    }
+
+    #[test]
+    fn a_changed_header_fails_even_with_the_right_key() {
+        let key = Key::new("correct horse").unwrap();
+        let mut envelope = parse(&key.seal(DATA).unwrap()).unwrap();
+        assert_eq!(key.decrypt(&envelope).unwrap(), DATA);
+        envelope.kdf.iterations += 1;
+        assert!(key.decrypt(&envelope).is_err());
+    }
+}
@@ -0,0 +1,459 @@
+//! Writing todos, focus sessions and daily totals out of the app.
+//!
+//! # JSON export format
+//!
+//! The JSON export is a stable, versioned format meant for other tools.
+//! Fields are only ever added, never renamed or removed, within a version.
+//! Version 2 added the tasks of project backlogs, which have no date; in
+//! version 1 `date` was never null.
+//!
+//! ```text
+//! {
+//!   "format": "focushub-export",
+//!   "version": 2,
+//!   "from": "2025-01-01",            // first day included, YYYY-MM-DD
+//!   "to": "2025-01-31",              // last day included
+//!   "todos": [{
+//!     "date": "2025-01-02",          // the day the task is scheduled on,
+//!                                    // or null in a project's backlog
+//!     "id": 1735812345678901,
+//!     "text": "Read chapter 3 #reading",
+//!     "completed": true,
+//!     "status": "Done",              // name of the board column
+//!     "tags": ["reading"],
+//!     "project": "Thesis"            // or null
+//!   }],
+//!   "sessions": [{
+//!     "start": "2025-01-02T09:00:00", // local time
+//!     "seconds": 1500,
+//!     "task_id": 1735812345678901,    // or null
+//!     "task": "Read chapter 3 #reading", // or null
+//!     "project": "Thesis"             // or null
+//!   }],
+//!   "daily_totals": [{
+//!     "date": "2025-01-02",
+//!     "seconds": 3000,
+//!     "sessions": 2                  // completed pomodoros
+//!   }]
+//! }
+//! ```
+
+use crate::app_data::{AppData, Stats};
+use crate::ui::format_duration;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+pub const JSON_FORMAT: &str = "focushub-export";
+pub const JSON_VERSION: u32 = 2;
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Three files: todos, sessions and daily totals.
+    Csv,
+    Json,
+    #[value(alias = "md")]
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ExportedTodo {
+    /// `None` for a task in a project's backlog.
+    pub date: Option<NaiveDate>,
+    pub id: u64,
+    pub text: String,
+    pub completed: bool,
+    pub status: String,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ExportedSession {
+    pub start: NaiveDateTime,
+    pub seconds: u64,
+    pub task_id: Option<u64>,
+    pub task: Option<String>,
+    pub project: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DailyTotal {
+    pub date: NaiveDate,
+    pub seconds: u64,
+    pub sessions: u32,
+}
+
+/// Everything in a date range, flattened into export rows.
+#[derive(Serialize)]
+pub struct Export {
+    pub format: &'static str,
+    pub version: u32,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub todos: Vec<ExportedTodo>,
+    pub sessions: Vec<ExportedSession>,
+    pub daily_totals: Vec<DailyTotal>,
+}
+
+/// The first and last day with any tasks or study time, if there is one.
+pub fn all_time(data: &AppData, stats: &Stats) -> Option<(NaiveDate, NaiveDate)> {
+    let days = data
+        .todos_by_date
+        .keys()
+        .chain(stats.daily_study_seconds.keys())
+        .chain(stats.daily_sessions.keys());
+    let from = days.clone().min()?;
+    let to = days.max()?;
+    Some((*from, *to))
+}
+
+/// Collects everything between `from` and `to`. Project backlogs have no
+/// dates, so their tasks are always included, after the dated ones.
+pub fn collect(data: &AppData, stats: &Stats, from: NaiveDate, to: NaiveDate) -> Export {
+    let in_range = |date: NaiveDate| from <= date && date <= to;
+    let project_name = |id: Option<u64>| id.and_then(|id| data.project(id)).map(|p| p.name.clone());
+
+    let mut dates: Vec<NaiveDate> = data
+        .todos_by_date
+        .keys()
+        .copied()
+        .filter(|d| in_range(*d))
+        .collect();
+    dates.sort();
+    let dated = dates.iter().flat_map(|date| {
+        data.todos_by_date[date]
+            .iter()
+            .map(move |todo| (Some(*date), todo, todo.project))
+    });
+    let backlogs = data.projects.iter().flat_map(|project| {
+        project
+            .tasks
+            .iter()
+            .map(move |todo| (None, todo, Some(project.id)))
+    });
+    let todos = dated
+        .chain(backlogs)
+        .map(|(date, todo, project)| ExportedTodo {
+            date,
+            id: todo.id,
+            text: todo.text.clone(),
+            completed: todo.is_done(),
+            status: data.column_name(todo.status).unwrap_or_default().to_owned(),
+            tags: todo.tags().map(str::to_owned).collect(),
+            project: project_name(project),
+        })
+        .collect();
+
+    let sessions = stats
+        .sessions
+        .iter()
+        .filter(|s| in_range(s.start.date()))
+        .map(|s| ExportedSession {
+            start: s.start,
+            seconds: s.seconds,
+            task_id: s.task,
+            task: s
+                .task
+                .and_then(|id| data.task_by_id(id))
+                .map(|t| t.text.clone()),
+            project: project_name(s.project),
+        })
+        .collect();
+
+    let daily_totals = from
+        .iter_days()
+        .take_while(|d| *d <= to)
+        .filter_map(|date| {
+            let seconds = stats.daily_study_seconds.get(&date).copied().unwrap_or(0);
+            let sessions = stats.daily_sessions.get(&date).copied().unwrap_or(0);
+            (seconds > 0 || sessions > 0).then_some(DailyTotal {
+                date,
+                seconds,
+                sessions,
+            })
+        })
+        .collect();
+
+    Export {
+        format: JSON_FORMAT,
+        version: JSON_VERSION,
+        from,
+        to,
+        todos,
+        sessions,
+        daily_totals,
+    }
+}
+
+impl Export {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn todos_csv(&self) -> Result<String, csv::Error> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record([
+            "date",
+            "id",
+            "text",
+            "completed",
+            "status",
+            "tags",
+            "project",
+        ])?;
+        for todo in &self.todos {
+            writer.write_record([
+                todo.date.map(|d| d.to_string()).unwrap_or_default(),
+                todo.id.to_string(),
+                todo.text.clone(),
+                todo.completed.to_string(),
+                todo.status.clone(),
+                todo.tags.join(" "),
+                todo.project.clone().unwrap_or_default(),
+            ])?;
+        }
+        finish_csv(writer)
+    }
+
+    pub fn sessions_csv(&self) -> Result<String, csv::Error> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(["start", "seconds", "task_id", "task", "project"])?;
+        for session in &self.sessions {
+            writer.write_record([
+                session.start.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                session.seconds.to_string(),
+                session.task_id.map(|id| id.to_string()).unwrap_or_default(),
+                session.task.clone().unwrap_or_default(),
+                session.project.clone().unwrap_or_default(),
+            ])?;
+        }
+        finish_csv(writer)
+    }
+
+    pub fn daily_totals_csv(&self) -> Result<String, csv::Error> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(["date", "seconds", "sessions"])?;
+        for total in &self.daily_totals {
+            writer.write_record([
+                total.date.to_string(),
+                total.seconds.to_string(),
+                total.sessions.to_string(),
+            ])?;
+        }
+        finish_csv(writer)
+    }
+
+    /// A journal with one section per day that has tasks or study time,
+    /// followed by the project backlogs.
+    pub fn to_markdown(&self) -> String {
+        let mut days: Vec<NaiveDate> = self
+            .todos
+            .iter()
+            .filter_map(|t| t.date)
+            .chain(self.daily_totals.iter().map(|t| t.date))
+            .collect();
+        days.sort();
+        days.dedup();
+
+        let mut out = format!("# Focus Hub Journal: {} to {}\n", self.from, self.to);
+        for day in days {
+            out += &format!("\n## {}\n\n", day.format("%A, %B %-d, %Y"));
+            if let Some(total) = self.daily_totals.iter().find(|t| t.date == day) {
+                out += &format!(
+                    "Studied {} over {} sessions.\n\n",
+                    format_duration(total.seconds),
+                    total.sessions
+                );
+            }
+            for todo in self.todos.iter().filter(|t| t.date == Some(day)) {
+                let mark = if todo.completed { "x" } else { " " };
+                out += &format!("- [{mark}] {}\n", todo.text);
+            }
+            let sessions: Vec<_> = self
+                .sessions
+                .iter()
+                .filter(|s| s.start.date() == day)
+                .collect();
+            if !sessions.is_empty() {
+                out += "\nFocus sessions:\n\n";
+                for session in sessions {
+                    let what = session.task.as_deref().unwrap_or("Unassigned");
+                    out += &format!(
+                        "- {} for {}: {what}\n",
+                        session.start.format("%H:%M"),
+                        format_duration(session.seconds)
+                    );
+                }
+            }
+        }
+
+        let mut backlog = self.todos.iter().filter(|t| t.date.is_none()).peekable();
+        if backlog.peek().is_some() {
+            out += "\n## Project Backlogs\n\n";
+        }
+        for todo in backlog {
+            let mark = if todo.completed { "x" } else { " " };
+            let project = todo.project.as_deref().unwrap_or_default();
+            out += &format!("- [{mark}] {} ({project})\n", todo.text);
+        }
+        out
+    }
+
+    /// Writes the export to `path`. CSV exports are split into three files
+    /// named after `path`: `*_todos.csv`, `*_sessions.csv` and `*_daily.csv`.
+    pub fn write(
+        &self,
+        format: ExportFormat,
+        path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match format {
+            ExportFormat::Json => fs::write(path, self.to_json()?)?,
+            ExportFormat::Markdown => fs::write(path, self.to_markdown())?,
+            ExportFormat::Csv => {
+                let stem = path.with_extension("");
+                let stem = stem.to_string_lossy();
+                fs::write(format!("{stem}_todos.csv"), self.todos_csv()?)?;
+                fs::write(format!("{stem}_sessions.csv"), self.sessions_csv()?)?;
+                fs::write(format!("{stem}_daily.csv"), self.daily_totals_csv()?)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn finish_csv(writer: csv::Writer<Vec<u8>>) -> Result<String, csv::Error> {
+    let bytes = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_data::{FocusSession, Project, STATUS_DONE, TodoItem};
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    fn sample() -> (AppData, Stats) {
+        let mut data = AppData::default();
+        let mut read = TodoItem::new("Read, then \"summarise\" #reading".to_owned(), Some(7));
+        read.status = STATUS_DONE;
+        let read_id = read.id;
+        data.todos_by_date.insert(day(2), vec![read]);
+        data.todos_by_date
+            .insert(day(9), vec![TodoItem::new("Out of range".to_owned(), None)]);
+        data.projects.push(Project {
+            id: 7,
+            name: "Thesis".to_owned(),
+            tasks: vec![TodoItem::new("Outline".to_owned(), None)],
+        });
+        let stats = Stats {
+            daily_study_seconds: [(day(2), 1500), (day(9), 600)].into(),
+            daily_sessions: [(day(2), 1)].into(),
+            sessions: vec![FocusSession {
+                start: day(2).and_hms_opt(9, 5, 0).unwrap(),
+                seconds: 1500,
+                task: Some(read_id),
+                project: Some(7),
+            }],
+            ..Stats::default()
+        };
+        (data, stats)
+    }
+
+    #[test]
+    fn only_the_range_is_exported_along_with_the_backlogs() {
+        let (data, stats) = sample();
+        let export = collect(&data, &stats, day(1), day(3));
+        let todos: Vec<_> = export
+            .todos
+            .iter()
+            .map(|t| (t.date, t.text.as_str(), t.project.as_deref()))
+            .collect();
+        assert_eq!(
+            todos,
+            [
+                (
+                    Some(day(2)),
+                    "Read, then \"summarise\" #reading",
+                    Some("Thesis")
+                ),
+                (None, "Outline", Some("Thesis")),
+            ]
+        );
+        assert_eq!(export.sessions.len(), 1);
+        assert_eq!(export.daily_totals.len(), 1);
+        assert_eq!(all_time(&data, &stats), Some((day(2), day(9))));
+    }
+
+    #[test]
+    fn csv_quotes_text_and_leaves_missing_values_empty() {
+        let (data, stats) = sample();
+        let export = collect(&data, &stats, day(1), day(3));
+        let todos = export.todos_csv().unwrap();
+        let lines: Vec<_> = todos.lines().collect();
+        assert_eq!(lines[0], "date,id,text,completed,status,tags,project");
+        let read = &export.todos[0];
+        assert_eq!(
+            lines[1],
+            format!(
+                "2025-03-02,{},\"Read, then \"\"summarise\"\" #reading\",true,Done,reading,Thesis",
+                read.id
+            )
+        );
+        assert!(lines[2].starts_with(",") && lines[2].ends_with(",Outline,false,To Do,,Thesis"));
+
+        let sessions = export.sessions_csv().unwrap();
+        assert!(sessions.contains(&format!("2025-03-02T09:05:00,1500,{},", read.id)));
+        assert_eq!(
+            export.daily_totals_csv().unwrap(),
+            "date,seconds,sessions\n2025-03-02,1500,1\n"
+        );
+    }
+
+    #[test]
+    fn json_carries_the_format_and_version() {
+        let (data, stats) = sample();
+        let json: serde_json::Value =
+            serde_json::from_str(&collect(&data, &stats, day(1), day(3)).to_json().unwrap())
+                .unwrap();
+        assert_eq!(json["format"], JSON_FORMAT);
+        assert_eq!(json["version"], JSON_VERSION);
+        assert_eq!(json["todos"][0]["date"], "2025-03-02");
+        assert!(json["todos"][1]["date"].is_null());
+        assert_eq!(
+            json["sessions"][0]["task"],
+            "Read, then \"summarise\" #reading"
+        );
+    }
+
+    #[test]
+    fn markdown_is_a_journal_by_day() {
+        let (data, stats) = sample();
+        let markdown = collect(&data, &stats, day(1), day(3)).to_markdown();
+        assert_eq!(
+            markdown,
+            "# Focus Hub Journal: 2025-03-01 to 2025-03-03\n\
+             \n## Sunday, March 2, 2025\n\n\
+             Studied 0h 25m over 1 sessions.\n\n\
+             - [x] Read, then \"summarise\" #reading\n\
+             \nFocus sessions:\n\n\
+             - 09:05 for 0h 25m: Read, then \"summarise\" #reading\n\
+             \n## Project Backlogs\n\n\
+             - [ ] Outline (Thesis)\n"
+        );
+    }
+}
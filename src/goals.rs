@@ -0,0 +1,204 @@
+use crate::app_data::{GoalUnit, Goals, Stats};
+use chrono::{Datelike, Days, NaiveDate};
+
+pub enum GoalEvent {
+    DailyReached,
+    WeeklyReached,
+}
+
+/// How far along a goal is, in the goal's unit.
+pub struct GoalProgress {
+    pub done: u32,
+    pub target: u32,
+    pub unit: GoalUnit,
+}
+
+impl GoalProgress {
+    pub fn fraction(&self) -> f32 {
+        (self.done as f32 / self.target as f32).min(1.0)
+    }
+
+    pub fn met(&self) -> bool {
+        self.done >= self.target
+    }
+
+    pub fn describe(&self) -> String {
+        let unit = match self.unit {
+            GoalUnit::Minutes => "min",
+            GoalUnit::Sessions => "sessions",
+        };
+        format!("{} / {} {unit}", self.done, self.target)
+    }
+}
+
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday() as u64)
+}
+
+pub fn daily_target(goals: &Goals, date: NaiveDate) -> u32 {
+    if goals.per_weekday {
+        goals.weekday_targets[date.weekday().num_days_from_monday() as usize]
+    } else {
+        goals.daily
+    }
+}
+
+fn amount(stats: &Stats, unit: GoalUnit, date: NaiveDate) -> u32 {
+    match unit {
+        GoalUnit::Minutes => {
+            (stats.daily_study_seconds.get(&date).cloned().unwrap_or(0) / 60) as u32
+        }
+        GoalUnit::Sessions => stats.daily_sessions.get(&date).cloned().unwrap_or(0),
+    }
+}
+
+pub fn daily_progress(stats: &Stats, goals: &Goals, date: NaiveDate) -> Option<GoalProgress> {
+    let target = daily_target(goals, date);
+    (target > 0).then(|| GoalProgress {
+        done: amount(stats, goals.unit, date),
+        target,
+        unit: goals.unit,
+    })
+}
+
+/// Progress towards the weekly goal for the week containing `date`.
+pub fn weekly_progress(stats: &Stats, goals: &Goals, date: NaiveDate) -> Option<GoalProgress> {
+    if goals.weekly == 0 {
+        return None;
+    }
+    let monday = week_start(date);
+    let done = monday
+        .iter_days()
+        .take(7)
+        .map(|day| amount(stats, goals.unit, day))
+        .sum();
+    Some(GoalProgress {
+        done,
+        target: goals.weekly,
+        unit: goals.unit,
+    })
+}
+
+/// Records goals reached today as hits and settles every finished day and
+/// week since the last check. Returns the goals that were reached just now.
+pub fn update(stats: &mut Stats, goals: &Goals, today: NaiveDate) -> Vec<GoalEvent> {
+    let mut events = Vec::new();
+
+    // History starts from the first time goals were checked, not from the first study day.
+    let start = stats
+        .goals_checked_through
+        .map_or(today, |d| d + Days::new(1));
+    for date in start.iter_days().take_while(|d| *d < today) {
+        if let Some(progress) = daily_progress(stats, goals, date) {
+            stats
+                .daily_goal_results
+                .entry(date)
+                .or_insert(progress.met());
+        }
+        // Settle a week on its last day.
+        if date.weekday().num_days_from_monday() == 6
+            && let Some(progress) = weekly_progress(stats, goals, date)
+        {
+            stats
+                .weekly_goal_results
+                .entry(week_start(date))
+                .or_insert(progress.met());
+        }
+        stats.goals_checked_through = Some(date);
+    }
+    if stats.goals_checked_through.is_none() {
+        stats.goals_checked_through = Some(today - Days::new(1));
+    }
+
+    if let Some(progress) = daily_progress(stats, goals, today)
+        && progress.met()
+        && !stats.daily_goal_results.contains_key(&today)
+    {
+        stats.daily_goal_results.insert(today, true);
+        events.push(GoalEvent::DailyReached);
+    }
+    let monday = week_start(today);
+    if let Some(progress) = weekly_progress(stats, goals, today)
+        && progress.met()
+        && !stats.weekly_goal_results.contains_key(&monday)
+    {
+        stats.weekly_goal_results.insert(monday, true);
+        events.push(GoalEvent::WeeklyReached);
+    }
+    events
+}
+
+/// Hits and total recorded outcomes, counting only entries on or after `from`.
+pub fn hit_rate(
+    results: &std::collections::BTreeMap<NaiveDate, bool>,
+    from: NaiveDate,
+) -> (usize, usize) {
+    let recent: Vec<bool> = results.range(from..).map(|(_, hit)| *hit).collect();
+    (recent.iter().filter(|hit| **hit).count(), recent.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    fn goals(daily: u32, weekly: u32) -> Goals {
+        Goals {
+            daily,
+            weekly,
+            ..Goals::default()
+        }
+    }
+
+    fn study(stats: &mut Stats, d: u32, minutes: u64) {
+        stats.daily_study_seconds.insert(day(d), minutes * 60);
+    }
+
+    #[test]
+    fn reaching_a_goal_is_announced_once() {
+        let goals = goals(30, 0);
+        let mut stats = Stats::default();
+        study(&mut stats, 3, 20);
+        assert!(update(&mut stats, &goals, day(3)).is_empty());
+
+        study(&mut stats, 3, 40);
+        let events = update(&mut stats, &goals, day(3));
+        assert!(matches!(events[..], [GoalEvent::DailyReached]));
+        assert!(update(&mut stats, &goals, day(3)).is_empty());
+    }
+
+    #[test]
+    fn days_and_weeks_are_settled_when_they_end() {
+        // Tracking starts on Wednesday 2025-03-05.
+        let goals = goals(30, 150);
+        let mut stats = Stats::default();
+        update(&mut stats, &goals, day(5));
+        for (d, minutes) in [(3, 60), (5, 40), (6, 10), (8, 60), (10, 30)] {
+            study(&mut stats, d, minutes);
+        }
+
+        update(&mut stats, &goals, day(11));
+        assert_eq!(stats.goals_checked_through, Some(day(10)));
+        // Days before tracking started aren't judged.
+        assert!(!stats.daily_goal_results.contains_key(&day(4)));
+        let days: Vec<bool> = stats.daily_goal_results.values().copied().collect();
+        assert_eq!(days, [true, false, false, true, false, true]);
+        // The whole week counts, including Monday before tracking started.
+        assert!(stats.weekly_goal_results[&day(3)]);
+        assert!(!stats.weekly_goal_results.contains_key(&day(10)));
+        assert_eq!(hit_rate(&stats.daily_goal_results, day(8)), (2, 3));
+    }
+
+    #[test]
+    fn weekday_targets_replace_the_daily_one() {
+        let mut goals = goals(30, 0);
+        goals.per_weekday = true;
+        goals.weekday_targets[0] = 60;
+        // Monday uses its own target; Tuesday has none.
+        assert_eq!(daily_target(&goals, day(3)), 60);
+        assert!(daily_progress(&Stats::default(), &goals, day(4)).is_none());
+    }
+}
@@ -0,0 +1,726 @@
+use crate::app_data::{
+    AppData, BoardColumn, CalendarEvent, Cost, Project, Reward, STATUS_DONE, TaskList, TaskRef,
+    TodoItem, Transaction,
+};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use std::collections::HashMap;
+
+// Oldest commands are dropped once the undo stack grows past this.
+const MAX_HISTORY: usize = 200;
+
+/// A reversible edit to the user's tasks, projects or rewards.
+///
+/// The UI never mutates `AppData` directly; it emits commands which are run
+/// through [`History::execute`] so that every change can be undone.
+#[derive(Clone, Debug)]
+pub enum Command {
+    AddTodo {
+        task: TaskRef,
+        item: TodoItem,
+    },
+    DeleteTodo {
+        task: TaskRef,
+        item: TodoItem,
+    },
+    EditTodo {
+        task: TaskRef,
+        old: String,
+        new: String,
+    },
+    /// `old_completed_on` is the task's completion date before the change,
+    /// given back to it when an un-complete is undone.
+    SetStatus {
+        task: TaskRef,
+        old: u64,
+        new: u64,
+        old_completed_on: Option<NaiveDate>,
+    },
+    MoveTodo {
+        from: TaskRef,
+        to: TaskRef,
+    },
+    AddReward {
+        index: usize,
+        reward: Reward,
+    },
+    DeleteReward {
+        index: usize,
+        reward: Reward,
+    },
+    EditReward {
+        index: usize,
+        old: String,
+        new: String,
+    },
+    ToggleReward {
+        index: usize,
+    },
+    ClaimReward {
+        index: usize,
+        time: NaiveDateTime,
+    },
+    UnclaimReward {
+        index: usize,
+        time: NaiveDateTime,
+    },
+    MoveReward {
+        from: usize,
+        to: usize,
+    },
+    /// Replaces a reward's settings (category, icon, image, cooldown...).
+    UpdateReward {
+        index: usize,
+        old: Reward,
+        new: Reward,
+    },
+    SetRewardCost {
+        index: usize,
+        old: Cost,
+        new: Cost,
+    },
+    SetRewardLevel {
+        index: usize,
+        old: u32,
+        new: u32,
+    },
+    AddTransaction {
+        entry: Transaction,
+    },
+    RemoveTransaction {
+        entry: Transaction,
+    },
+    AddProject {
+        index: usize,
+        project: Project,
+    },
+    DeleteProject {
+        index: usize,
+        project: Project,
+    },
+    RenameProject {
+        index: usize,
+        old: String,
+        new: String,
+    },
+    AddDependency {
+        task: TaskRef,
+        blocker: u64,
+    },
+    RemoveDependency {
+        task: TaskRef,
+        blocker: u64,
+    },
+    AddColumn {
+        index: usize,
+        column: BoardColumn,
+    },
+    DeleteColumn {
+        index: usize,
+        column: BoardColumn,
+    },
+    RenameColumn {
+        index: usize,
+        old: String,
+        new: String,
+    },
+    AddEvent {
+        index: usize,
+        event: CalendarEvent,
+    },
+    DeleteEvent {
+        index: usize,
+        event: CalendarEvent,
+    },
+    UpdateEvent {
+        index: usize,
+        old: CalendarEvent,
+        new: CalendarEvent,
+    },
+    /// Swaps every dated task list at once, for imports and syncs that
+    /// reshuffle too much to express as individual edits.
+    ReplaceSchedule {
+        old: HashMap<NaiveDate, Vec<TodoItem>>,
+        new: HashMap<NaiveDate, Vec<TodoItem>>,
+    },
+    /// Swaps all projects at once, for changes merged in from another device.
+    ReplaceProjects {
+        old: Vec<Project>,
+        new: Vec<Project>,
+    },
+    /// Swaps all rewards at once, for changes merged in from another device.
+    ReplaceRewards {
+        old: Vec<Reward>,
+        new: Vec<Reward>,
+    },
+    /// Several commands that are applied, and undone, as one step.
+    Batch(Vec<Command>),
+}
+
+impl Command {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Command::AddTodo { .. } => "Add task",
+            Command::DeleteTodo { .. } => "Delete task",
+            Command::EditTodo { .. } => "Edit task",
+            Command::SetStatus { .. } => "Change task status",
+            Command::MoveTodo { from, to } if from.list == to.list => "Reorder task",
+            Command::MoveTodo { .. } => "Move task",
+            Command::AddReward { .. } => "Add reward",
+            Command::DeleteReward { .. } => "Delete reward",
+            Command::EditReward { .. } => "Edit reward",
+            Command::ToggleReward { .. } => "Toggle reward",
+            Command::ClaimReward { .. } => "Claim reward",
+            Command::UnclaimReward { .. } => "Unclaim reward",
+            Command::MoveReward { .. } => "Reorder reward",
+            Command::UpdateReward { .. } => "Edit reward settings",
+            Command::SetRewardCost { .. } => "Change reward cost",
+            Command::SetRewardLevel { .. } => "Change reward level",
+            Command::AddTransaction { entry } if entry.amount < 0 => "Spend points",
+            Command::AddTransaction { .. } => "Earn points",
+            Command::RemoveTransaction { .. } => "Take back points",
+            Command::AddProject { .. } => "Add project",
+            Command::DeleteProject { .. } => "Delete project",
+            Command::RenameProject { .. } => "Rename project",
+            Command::AddDependency { .. } => "Add dependency",
+            Command::RemoveDependency { .. } => "Remove dependency",
+            Command::AddColumn { .. } => "Add board column",
+            Command::DeleteColumn { .. } => "Delete board column",
+            Command::RenameColumn { .. } => "Rename board column",
+            Command::AddEvent { .. } => "Add event",
+            Command::DeleteEvent { .. } => "Delete event",
+            Command::UpdateEvent { .. } => "Update event",
+            Command::ReplaceSchedule { .. } => "Replace scheduled tasks",
+            Command::ReplaceProjects { .. } => "Replace projects",
+            Command::ReplaceRewards { .. } => "Replace rewards",
+            Command::Batch(commands) => commands.first().map_or("Batch", Command::describe),
+        }
+    }
+
+    fn apply(&self, data: &mut AppData) {
+        match self {
+            Command::AddTodo { task, item } => {
+                if let Some(todos) = data.tasks_mut(task.list) {
+                    todos.insert(task.index.min(todos.len()), item.clone());
+                }
+            }
+            Command::DeleteTodo { task, .. } => {
+                if let Some(todos) = data.tasks_mut(task.list)
+                    && task.index < todos.len()
+                {
+                    todos.remove(task.index);
+                }
+            }
+            Command::EditTodo { task, new, .. } => {
+                if let Some(todo) = data.task_mut(*task) {
+                    todo.text = new.clone();
+                }
+            }
+            Command::SetStatus {
+                task,
+                new,
+                old_completed_on,
+                ..
+            } => {
+                if let Some(todo) = data.task_mut(*task) {
+                    todo.status = *new;
+                    todo.completed_on = (*new == STATUS_DONE).then(|| {
+                        old_completed_on
+                            .or(todo.completed_on)
+                            .unwrap_or_else(|| Local::now().date_naive())
+                    });
+                }
+            }
+            Command::MoveTodo { from, to } => data.relocate_todo(*from, *to),
+            Command::AddReward { index, reward } => {
+                let index = (*index).min(data.rewards.len());
+                data.rewards.insert(index, reward.clone());
+            }
+            Command::DeleteReward { index, .. } => {
+                if *index < data.rewards.len() {
+                    data.rewards.remove(*index);
+                }
+            }
+            Command::EditReward { index, new, .. } => {
+                if let Some(reward) = data.rewards.get_mut(*index) {
+                    reward.name = new.clone();
+                }
+            }
+            Command::ToggleReward { index } => {
+                if let Some(reward) = data.rewards.get_mut(*index) {
+                    reward.completed = !reward.completed;
+                }
+            }
+            Command::ClaimReward { index, time } => {
+                if let Some(reward) = data.rewards.get_mut(*index) {
+                    reward.claims.push(*time);
+                    reward.completed = !reward.repeatable;
+                }
+            }
+            Command::UnclaimReward { index, time } => {
+                if let Some(reward) = data.rewards.get_mut(*index) {
+                    if let Some(i) = reward.claims.iter().rposition(|t| t == time) {
+                        reward.claims.remove(i);
+                    }
+                    reward.completed = !reward.repeatable && !reward.claims.is_empty();
+                }
+            }
+            Command::MoveReward { from, to } => {
+                if *from < data.rewards.len() {
+                    let reward = data.rewards.remove(*from);
+                    let to = (*to).min(data.rewards.len());
+                    data.rewards.insert(to, reward);
+                }
+            }
+            Command::UpdateReward { index, new, .. } => {
+                if let Some(reward) = data.rewards.get_mut(*index) {
+                    *reward = new.clone();
+                }
+            }
+            Command::SetRewardCost { index, new, .. } => {
+                if let Some(reward) = data.rewards.get_mut(*index) {
+                    reward.cost = *new;
+                }
+            }
+            Command::SetRewardLevel { index, new, .. } => {
+                if let Some(reward) = data.rewards.get_mut(*index) {
+                    reward.min_level = *new;
+                }
+            }
+            Command::AddTransaction { entry } => data.wallet.record(entry.clone()),
+            Command::RemoveTransaction { entry } => data.wallet.revert(entry),
+            Command::AddProject { index, project } => {
+                let index = (*index).min(data.projects.len());
+                data.projects.insert(index, project.clone());
+            }
+            Command::DeleteProject { index, .. } => {
+                if *index < data.projects.len() {
+                    data.projects.remove(*index);
+                }
+            }
+            Command::RenameProject { index, new, .. } => {
+                if let Some(project) = data.projects.get_mut(*index) {
+                    project.name = new.clone();
+                }
+            }
+            Command::AddDependency { task, blocker } => {
+                if let Some(todo) = data.task_mut(*task) {
+                    todo.blocked_by.push(*blocker);
+                }
+            }
+            Command::RemoveDependency { task, blocker } => {
+                if let Some(todo) = data.task_mut(*task) {
+                    todo.blocked_by.retain(|id| id != blocker);
+                }
+            }
+            Command::AddColumn { index, column } => {
+                let index = (*index).min(data.board_columns.len());
+                data.board_columns.insert(index, column.clone());
+            }
+            Command::DeleteColumn { index, .. } => {
+                if *index < data.board_columns.len() {
+                    data.board_columns.remove(*index);
+                }
+            }
+            Command::RenameColumn { index, new, .. } => {
+                if let Some(column) = data.board_columns.get_mut(*index) {
+                    column.name = new.clone();
+                }
+            }
+            Command::AddEvent { index, event } => {
+                let index = (*index).min(data.events.len());
+                data.events.insert(index, event.clone());
+            }
+            Command::DeleteEvent { index, .. } => {
+                if *index < data.events.len() {
+                    data.events.remove(*index);
+                }
+            }
+            Command::UpdateEvent { index, new, .. } => {
+                if let Some(event) = data.events.get_mut(*index) {
+                    *event = new.clone();
+                }
+            }
+            Command::ReplaceSchedule { new, .. } => data.todos_by_date = new.clone(),
+            Command::ReplaceProjects { new, .. } => data.projects = new.clone(),
+            Command::ReplaceRewards { new, .. } => data.rewards = new.clone(),
+            Command::Batch(commands) => {
+                for command in commands {
+                    command.apply(data);
+                }
+            }
+        }
+    }
+
+    fn inverse(&self) -> Command {
+        match self.clone() {
+            Command::AddTodo { task, item } => Command::DeleteTodo { task, item },
+            Command::DeleteTodo { task, item } => Command::AddTodo { task, item },
+            Command::EditTodo { task, old, new } => Command::EditTodo {
+                task,
+                old: new,
+                new: old,
+            },
+            Command::SetStatus {
+                task,
+                old,
+                new,
+                old_completed_on,
+            } => Command::SetStatus {
+                task,
+                old: new,
+                new: old,
+                old_completed_on,
+            },
+            Command::MoveTodo { from, to } => Command::MoveTodo { from: to, to: from },
+            Command::AddReward { index, reward } => Command::DeleteReward { index, reward },
+            Command::DeleteReward { index, reward } => Command::AddReward { index, reward },
+            Command::EditReward { index, old, new } => Command::EditReward {
+                index,
+                old: new,
+                new: old,
+            },
+            Command::ToggleReward { index } => Command::ToggleReward { index },
+            Command::ClaimReward { index, time } => Command::UnclaimReward { index, time },
+            Command::UnclaimReward { index, time } => Command::ClaimReward { index, time },
+            Command::MoveReward { from, to } => Command::MoveReward { from: to, to: from },
+            Command::UpdateReward { index, old, new } => Command::UpdateReward {
+                index,
+                old: new,
+                new: old,
+            },
+            Command::SetRewardCost { index, old, new } => Command::SetRewardCost {
+                index,
+                old: new,
+                new: old,
+            },
+            Command::SetRewardLevel { index, old, new } => Command::SetRewardLevel {
+                index,
+                old: new,
+                new: old,
+            },
+            Command::AddTransaction { entry } => Command::RemoveTransaction { entry },
+            Command::RemoveTransaction { entry } => Command::AddTransaction { entry },
+            Command::AddProject { index, project } => Command::DeleteProject { index, project },
+            Command::DeleteProject { index, project } => Command::AddProject { index, project },
+            Command::RenameProject { index, old, new } => Command::RenameProject {
+                index,
+                old: new,
+                new: old,
+            },
+            Command::AddDependency { task, blocker } => Command::RemoveDependency { task, blocker },
+            Command::RemoveDependency { task, blocker } => Command::AddDependency { task, blocker },
+            Command::AddColumn { index, column } => Command::DeleteColumn { index, column },
+            Command::DeleteColumn { index, column } => Command::AddColumn { index, column },
+            Command::RenameColumn { index, old, new } => Command::RenameColumn {
+                index,
+                old: new,
+                new: old,
+            },
+            Command::AddEvent { index, event } => Command::DeleteEvent { index, event },
+            Command::DeleteEvent { index, event } => Command::AddEvent { index, event },
+            Command::UpdateEvent { index, old, new } => Command::UpdateEvent {
+                index,
+                old: new,
+                new: old,
+            },
+            Command::ReplaceSchedule { old, new } => {
+                Command::ReplaceSchedule { old: new, new: old }
+            }
+            Command::ReplaceProjects { old, new } => {
+                Command::ReplaceProjects { old: new, new: old }
+            }
+            Command::ReplaceRewards { old, new } => Command::ReplaceRewards { old: new, new: old },
+            Command::Batch(commands) => {
+                Command::Batch(commands.iter().rev().map(Command::inverse).collect())
+            }
+        }
+    }
+}
+
+/// Extends a command that finishes a task so that the tasks it was the last
+/// blocker of are also moved onto `today`'s list, as a single undo step.
+pub fn surface_unblocked(command: Command, data: &AppData, today: NaiveDate) -> Command {
+    let Command::SetStatus {
+        task,
+        new: STATUS_DONE,
+        ..
+    } = &command
+    else {
+        return command;
+    };
+    let Some(finished) = data.tasks(task.list).get(task.index) else {
+        return command;
+    };
+
+    let today = TaskList::Date(today);
+    let mut unblocked: Vec<_> = data
+        .unblocked_by(finished.id)
+        .into_iter()
+        .filter(|t| t.list != today)
+        .collect();
+    if unblocked.is_empty() {
+        return command;
+    }
+
+    // Move later tasks first so the indices of earlier ones in the same list stay valid.
+    unblocked.sort_by_key(|t| std::cmp::Reverse(t.index));
+    let first_free = data.tasks(today).len();
+    let moves = (first_free..)
+        .zip(unblocked)
+        .map(|(index, from)| Command::MoveTodo {
+            from,
+            to: TaskRef { list: today, index },
+        });
+    let commands = std::iter::once(command).chain(moves).collect();
+    Command::Batch(commands)
+}
+
+/// Global undo/redo stacks for commands applied to `AppData`.
+#[derive(Default)]
+pub struct History {
+    undo_stack: Vec<Command>,
+    redo_stack: Vec<Command>,
+    /// Bumped by every execute, undo and redo, so callers can tell the data changed.
+    revision: u64,
+}
+
+impl History {
+    pub fn execute(&mut self, command: Command, data: &mut AppData) {
+        command.apply(data);
+        self.revision += 1;
+        self.undo_stack.push(command);
+        if self.undo_stack.len() > MAX_HISTORY {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    /// Applies a change made elsewhere, such as on another device. Nothing
+    /// before it can be undone any more, as undoing across it would undo the
+    /// other change as well.
+    pub fn apply_external(&mut self, command: Command, data: &mut AppData) {
+        command.apply(data);
+        self.revision += 1;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    pub fn undo(&mut self, data: &mut AppData) {
+        if let Some(command) = self.undo_stack.pop() {
+            command.inverse().apply(data);
+            self.revision += 1;
+            self.redo_stack.push(command);
+        }
+    }
+
+    pub fn redo(&mut self, data: &mut AppData) {
+        if let Some(command) = self.redo_stack.pop() {
+            command.apply(data);
+            self.revision += 1;
+            self.undo_stack.push(command);
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn undo_label(&self) -> Option<&'static str> {
+        self.undo_stack.last().map(Command::describe)
+    }
+
+    pub fn redo_label(&self) -> Option<&'static str> {
+        self.redo_stack.last().map(Command::describe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_data::STATUS_TODO;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    fn first(index: usize) -> TaskRef {
+        TaskRef {
+            list: TaskList::Date(day(1)),
+            index,
+        }
+    }
+
+    fn texts(data: &AppData) -> Vec<&str> {
+        data.tasks(TaskList::Date(day(1)))
+            .iter()
+            .map(|t| t.text.as_str())
+            .collect()
+    }
+
+    fn add(text: &str, index: usize) -> Command {
+        Command::AddTodo {
+            task: first(index),
+            item: TodoItem::new(text.to_owned(), None),
+        }
+    }
+
+    #[test]
+    fn edits_undo_and_redo_in_order() {
+        let mut data = AppData::default();
+        let mut history = History::default();
+        history.execute(add("Read", 0), &mut data);
+        history.execute(
+            Command::EditTodo {
+                task: first(0),
+                old: "Read".to_owned(),
+                new: "Read chapter 3".to_owned(),
+            },
+            &mut data,
+        );
+        assert_eq!(texts(&data), ["Read chapter 3"]);
+        assert_eq!(history.undo_label(), Some("Edit task"));
+
+        history.undo(&mut data);
+        assert_eq!(texts(&data), ["Read"]);
+        history.undo(&mut data);
+        assert!(texts(&data).is_empty());
+        assert_eq!(history.undo_label(), None);
+        // Nothing left to undo changes nothing.
+        history.undo(&mut data);
+
+        history.redo(&mut data);
+        history.redo(&mut data);
+        assert_eq!(texts(&data), ["Read chapter 3"]);
+        assert_eq!(history.redo_label(), None);
+        assert_eq!(history.revision(), 6);
+    }
+
+    #[test]
+    fn a_new_edit_drops_what_could_be_redone() {
+        let mut data = AppData::default();
+        let mut history = History::default();
+        history.execute(add("Read", 0), &mut data);
+        history.undo(&mut data);
+        assert_eq!(history.redo_label(), Some("Add task"));
+        history.execute(add("Write", 0), &mut data);
+        assert_eq!(history.redo_label(), None);
+        assert_eq!(texts(&data), ["Write"]);
+    }
+
+    #[test]
+    fn a_batch_undoes_as_one_step() {
+        let mut data = AppData::default();
+        let mut history = History::default();
+        history.execute(add("a", 0), &mut data);
+        let item = data.todos_by_date[&day(1)][0].clone();
+        let batch = Command::Batch(vec![
+            add("b", 1),
+            Command::MoveTodo {
+                from: first(0),
+                to: first(1),
+            },
+            Command::DeleteTodo {
+                task: first(1),
+                item,
+            },
+        ]);
+        history.execute(batch, &mut data);
+        assert_eq!(texts(&data), ["b"]);
+        history.undo(&mut data);
+        assert_eq!(texts(&data), ["a"]);
+        history.redo(&mut data);
+        assert_eq!(texts(&data), ["b"]);
+    }
+
+    #[test]
+    fn only_the_latest_edits_are_kept() {
+        let mut data = AppData::default();
+        let mut history = History::default();
+        for i in 0..MAX_HISTORY + 5 {
+            history.execute(add(&i.to_string(), i), &mut data);
+        }
+        for _ in 0..MAX_HISTORY + 5 {
+            history.undo(&mut data);
+        }
+        assert_eq!(texts(&data), ["0", "1", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn undoing_a_reopen_keeps_the_completion_date() {
+        let mut data = AppData::default();
+        let mut item = TodoItem::new("Read".to_owned(), None);
+        item.status = STATUS_DONE;
+        item.completed_on = Some(day(1));
+        data.todos_by_date.insert(day(1), vec![item]);
+        let task = TaskRef {
+            list: TaskList::Date(day(1)),
+            index: 0,
+        };
+
+        let mut history = History::default();
+        history.execute(
+            Command::SetStatus {
+                task,
+                old: STATUS_DONE,
+                new: STATUS_TODO,
+                old_completed_on: Some(day(1)),
+            },
+            &mut data,
+        );
+        assert_eq!(data.todos_by_date[&day(1)][0].completed_on, None);
+        history.undo(&mut data);
+        let todo = &data.todos_by_date[&day(1)][0];
+        assert!(todo.is_done());
+        assert_eq!(todo.completed_on, Some(day(1)));
+    }
+
+    #[test]
+    fn finishing_the_last_blocker_brings_its_tasks_to_today() {
+        let mut data = AppData::default();
+        let blocker = TodoItem::new("Outline".to_owned(), None);
+        let mut waiting = TodoItem::new("Draft".to_owned(), None);
+        waiting.blocked_by.push(blocker.id);
+        data.todos_by_date.insert(day(1), vec![blocker, waiting]);
+
+        let finish = Command::SetStatus {
+            task: first(0),
+            old: STATUS_TODO,
+            new: STATUS_DONE,
+            old_completed_on: None,
+        };
+        let command = surface_unblocked(finish, &data, day(2));
+        let mut history = History::default();
+        history.execute(command, &mut data);
+        assert_eq!(texts(&data), ["Outline"]);
+        assert_eq!(data.todos_by_date[&day(2)][0].text, "Draft");
+
+        // One undo reopens the blocker and moves the task back.
+        history.undo(&mut data);
+        assert_eq!(texts(&data), ["Outline", "Draft"]);
+        assert!(!data.todos_by_date[&day(1)][0].is_done());
+    }
+
+    #[test]
+    fn claims_stay_open_only_on_repeatable_rewards() {
+        use crate::app_data::Cost;
+
+        let mut data = AppData::default();
+        let mut coffee = Reward::new("Coffee".to_owned(), Cost::Points(10));
+        coffee.repeatable = true;
+        data.rewards = vec![coffee, Reward::new("Concert".to_owned(), Cost::Points(100))];
+        let time = day(1).and_hms_opt(9, 0, 0).unwrap();
+        let mut history = History::default();
+
+        for index in [0, 0, 1] {
+            history.execute(Command::ClaimReward { index, time }, &mut data);
+        }
+        assert_eq!(data.rewards[0].claims.len(), 2);
+        assert!(!data.rewards[0].completed);
+        assert!(data.rewards[1].completed);
+
+        history.undo(&mut data);
+        history.undo(&mut data);
+        assert_eq!(data.rewards[0].claims, [time]);
+        assert!(!data.rewards[1].completed);
+    }
+}
@@ -0,0 +1,457 @@
+//! Reading and writing iCalendar (.ics) files.
+//!
+//! Scheduled tasks are written as VTODOs and focus sessions as VEVENTs.
+//! Importing turns VTODOs into tasks and VEVENTs into calendar events; items
+//! are matched by UID so importing the same file twice changes nothing.
+
+use crate::app_data::{
+    AppData, CalendarEvent, STATUS_DONE, STATUS_TODO, Stats, TaskList, TaskRef, TodoItem,
+};
+use crate::history::Command;
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::collections::{HashMap, HashSet};
+
+const PRODUCT_ID: &str = "-//Focus Hub//Focus Hub//EN";
+// Suffix of the UIDs this app makes up for items that didn't come with one.
+const UID_DOMAIN: &str = "@focushub";
+// Content lines longer than this many bytes are folded onto continuation lines.
+const MAX_LINE: usize = 75;
+
+fn task_uid(task: &TodoItem) -> String {
+    task.uid
+        .clone()
+        .unwrap_or_else(|| format!("task-{}{UID_DOMAIN}", task.id))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+// Appends a content line, folding it so no line exceeds `MAX_LINE` bytes.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn format_date_time(time: NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Writes every scheduled task and every focus session as one calendar.
+pub fn export(data: &AppData, stats: &Stats) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, &format!("PRODID:{PRODUCT_ID}"));
+
+    let mut dates: Vec<&NaiveDate> = data.todos_by_date.keys().collect();
+    dates.sort();
+    for date in dates {
+        for task in &data.todos_by_date[date] {
+            push_line(&mut out, "BEGIN:VTODO");
+            push_line(&mut out, &format!("UID:{}", task_uid(task)));
+            push_line(&mut out, &format!("DTSTAMP:{stamp}"));
+            push_line(&mut out, &format!("SUMMARY:{}", escape(&task.text)));
+            push_line(
+                &mut out,
+                &format!("DUE;VALUE=DATE:{}", date.format("%Y%m%d")),
+            );
+            let status = if task.is_done() {
+                "COMPLETED"
+            } else {
+                "NEEDS-ACTION"
+            };
+            push_line(&mut out, &format!("STATUS:{status}"));
+            let tags: Vec<String> = task.tags().map(escape).collect();
+            if !tags.is_empty() {
+                push_line(&mut out, &format!("CATEGORIES:{}", tags.join(",")));
+            }
+            push_line(&mut out, "END:VTODO");
+        }
+    }
+
+    for session in &stats.sessions {
+        let task = session.task.and_then(|id| data.task_by_id(id));
+        let summary = match task {
+            Some(task) => format!("Focus: {}", task.text),
+            None => "Focus session".to_owned(),
+        };
+        let end = session.start + chrono::TimeDelta::seconds(session.seconds as i64);
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(
+            &mut out,
+            &format!(
+                "UID:session-{}{UID_DOMAIN}",
+                session.start.and_utc().timestamp()
+            ),
+        );
+        push_line(&mut out, &format!("DTSTAMP:{stamp}"));
+        push_line(
+            &mut out,
+            &format!("DTSTART:{}", format_date_time(session.start)),
+        );
+        push_line(&mut out, &format!("DTEND:{}", format_date_time(end)));
+        push_line(&mut out, &format!("SUMMARY:{}", escape(&summary)));
+        push_line(&mut out, "END:VEVENT");
+    }
+
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// A task read from a VTODO.
+pub struct IcsTodo {
+    pub uid: String,
+    pub summary: String,
+    pub date: Option<NaiveDate>,
+    pub completed: bool,
+}
+
+#[derive(Default)]
+pub struct Calendar {
+    pub todos: Vec<IcsTodo>,
+    pub events: Vec<CalendarEvent>,
+}
+
+// One property of a component: its name, parameters and raw value.
+struct Property {
+    name: String,
+    params: String,
+    value: String,
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    let (head, value) = line.split_once(':')?;
+    let (name, params) = head.split_once(';').unwrap_or((head, ""));
+    Some(Property {
+        name: name.to_ascii_uppercase(),
+        params: params.to_ascii_uppercase(),
+        value: value.to_owned(),
+    })
+}
+
+/// Parses a DATE or DATE-TIME value into local time, and whether it was a
+/// whole-day date. UTC times are converted; times with a TZID are taken as local.
+fn parse_time(property: &Property) -> Option<(NaiveDateTime, bool)> {
+    let value = property.value.trim();
+    if property.params.contains("VALUE=DATE") && !property.params.contains("VALUE=DATE-TIME")
+        || value.len() == 8
+    {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((date.and_hms_opt(0, 0, 0)?, true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        let local = Utc.from_utc_datetime(&time).with_timezone(&Local);
+        return Some((local.naive_local(), false));
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((time, false))
+}
+
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines
+}
+
+/// Reads the VTODOs and VEVENTs of a calendar, skipping ones without a UID
+/// or summary and focus sessions exported by this app.
+pub fn parse(text: &str) -> Calendar {
+    let mut calendar = Calendar::default();
+    let mut component: Option<(String, Vec<Property>)> = None;
+    for line in unfold(text) {
+        let Some(property) = parse_property(&line) else {
+            continue;
+        };
+        match (property.name.as_str(), &mut component) {
+            ("BEGIN", None) => {
+                let kind = property.value.trim().to_ascii_uppercase();
+                if kind == "VTODO" || kind == "VEVENT" {
+                    component = Some((kind, Vec::new()));
+                }
+            }
+            ("END", Some((kind, _))) if property.value.trim().eq_ignore_ascii_case(kind) => {
+                let (kind, properties) = component.take().unwrap();
+                read_component(&mut calendar, &kind, &properties);
+            }
+            (_, Some((_, properties))) => properties.push(property),
+            _ => {}
+        }
+    }
+    calendar
+}
+
+fn read_component(calendar: &mut Calendar, kind: &str, properties: &[Property]) {
+    let find = |name: &str| properties.iter().find(|p| p.name == name);
+    let (Some(uid), Some(summary)) = (find("UID"), find("SUMMARY")) else {
+        return;
+    };
+    let uid = uid.value.trim().to_owned();
+    let summary = unescape(summary.value.trim());
+
+    if kind == "VTODO" {
+        let date = find("DUE")
+            .or_else(|| find("DTSTART"))
+            .and_then(parse_time)
+            .map(|(time, _)| time.date());
+        let completed = find("STATUS").is_some_and(|p| p.value.trim() == "COMPLETED")
+            || find("COMPLETED").is_some();
+        calendar.todos.push(IcsTodo {
+            uid,
+            summary,
+            date,
+            completed,
+        });
+    } else {
+        if uid.starts_with("session-") && uid.ends_with(UID_DOMAIN) {
+            return;
+        }
+        let Some((start, all_day)) = find("DTSTART").and_then(parse_time) else {
+            return;
+        };
+        let end = find("DTEND").and_then(parse_time).map(|(end, _)| end);
+        calendar.events.push(CalendarEvent {
+            uid,
+            summary,
+            start,
+            end,
+            all_day,
+        });
+    }
+}
+
+/// Counts of what an import changed.
+#[derive(Default)]
+pub struct ImportSummary {
+    pub tasks_added: usize,
+    pub tasks_updated: usize,
+    pub events_added: usize,
+    pub events_updated: usize,
+}
+
+/// Turns a parsed calendar into the commands that bring it into `data`.
+/// Tasks without a date are scheduled on `today`.
+pub fn import_commands(
+    data: &AppData,
+    calendar: Calendar,
+    today: NaiveDate,
+) -> (Vec<Command>, ImportSummary) {
+    let mut commands = Vec::new();
+    let mut summary = ImportSummary::default();
+
+    let existing: HashMap<String, (TaskRef, &TodoItem)> = data
+        .task_refs()
+        .map(|(task, item)| (task_uid(item), (task, item)))
+        .collect();
+    // Tasks added so far per day, so each new task gets the next free index.
+    let mut added: HashMap<NaiveDate, usize> = HashMap::new();
+    // Only the first item with a given UID in the file is used.
+    let mut seen = HashSet::new();
+    for todo in calendar.todos {
+        if !seen.insert(todo.uid.clone()) {
+            continue;
+        }
+        let status = if todo.completed {
+            STATUS_DONE
+        } else {
+            STATUS_TODO
+        };
+        if let Some(&(task, item)) = existing.get(&todo.uid) {
+            let mut changed = false;
+            if item.text != todo.summary {
+                commands.push(Command::EditTodo {
+                    task,
+                    old: item.text.clone(),
+                    new: todo.summary.clone(),
+                });
+                changed = true;
+            }
+            if item.is_done() != todo.completed {
+                commands.push(Command::SetStatus {
+                    task,
+                    old: item.status,
+                    new: status,
+                    old_completed_on: item.completed_on,
+                });
+                changed = true;
+            }
+            if changed {
+                summary.tasks_updated += 1;
+            }
+            continue;
+        }
+
+        let date = todo.date.unwrap_or(today);
+        let list = TaskList::Date(date);
+        let count = added.entry(date).or_insert(0);
+        let mut item = TodoItem::new(todo.summary, None);
+        item.uid = Some(todo.uid);
+        item.status = status;
+        commands.push(Command::AddTodo {
+            task: TaskRef {
+                list,
+                index: data.tasks(list).len() + *count,
+            },
+            item,
+        });
+        *count += 1;
+        summary.tasks_added += 1;
+    }
+
+    let mut next_event = data.events.len();
+    for event in calendar.events {
+        if !seen.insert(event.uid.clone()) {
+            continue;
+        }
+        match data.events.iter().position(|e| e.uid == event.uid) {
+            Some(index) if data.events[index] != event => {
+                commands.push(Command::UpdateEvent {
+                    index,
+                    old: data.events[index].clone(),
+                    new: event,
+                });
+                summary.events_updated += 1;
+            }
+            Some(_) => {}
+            None => {
+                commands.push(Command::AddEvent {
+                    index: next_event,
+                    event,
+                });
+                next_event += 1;
+                summary.events_added += 1;
+            }
+        }
+    }
+
+    (commands, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::History;
+
+    #[test]
+    fn long_lines_fold_between_characters() {
+        let text = "SUMMARY:".to_owned() + &"Größenwahn 😀 ".repeat(12);
+        let mut out = String::new();
+        push_line(&mut out, &text);
+        let lines: Vec<&str> = out.split_terminator("\r\n").collect();
+        assert!(lines.len() > 2);
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE));
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+        assert_eq!(unfold(&out), [text]);
+    }
+
+    #[test]
+    fn unfolding_joins_continuations() {
+        let text = "BEGIN:VEVENT\r\nSUMMARY:Lunch with\r\n  Ana\r\n\tand Bo\r\nEND:VEVENT\r\n";
+        assert_eq!(
+            unfold(text),
+            ["BEGIN:VEVENT", "SUMMARY:Lunch with Anaand Bo", "END:VEVENT"]
+        );
+    }
+
+    #[test]
+    fn events_and_todos_are_read() {
+        let text = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\nUID:a@example.com\r\nSUMMARY:Dentist\\, then work\r\n\
+            DTSTART;VALUE=DATE:20250301\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:b@example.com\r\nSUMMARY:Call\r\n\
+            DTSTART;TZID=Europe/Berlin:20250301T093000\r\nDTEND;TZID=Europe/Berlin:20250301T100000\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:session-1740820000@focushub\r\nSUMMARY:Focus session\r\n\
+            DTSTART:20250301T080000\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nSUMMARY:No UID\r\nDTSTART:20250301T080000\r\nEND:VEVENT\r\n\
+            BEGIN:VTODO\r\nUID:c@example.com\r\nSUMMARY:Pay rent\r\nDUE;VALUE=DATE:20250305\r\n\
+            STATUS:COMPLETED\r\nEND:VTODO\r\n\
+            END:VCALENDAR\r\n";
+        let calendar = parse(text);
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        assert_eq!(
+            calendar.events,
+            [
+                CalendarEvent {
+                    uid: "a@example.com".to_owned(),
+                    summary: "Dentist, then work".to_owned(),
+                    start: day.and_hms_opt(0, 0, 0).unwrap(),
+                    end: None,
+                    all_day: true,
+                },
+                CalendarEvent {
+                    uid: "b@example.com".to_owned(),
+                    summary: "Call".to_owned(),
+                    start: day.and_hms_opt(9, 30, 0).unwrap(),
+                    end: day.and_hms_opt(10, 0, 0),
+                    all_day: false,
+                },
+            ]
+        );
+        let [todo] = &calendar.todos[..] else {
+            panic!("expected one task");
+        };
+        assert_eq!(todo.summary, "Pay rent");
+        assert_eq!(todo.date, NaiveDate::from_ymd_opt(2025, 3, 5));
+        assert!(todo.completed);
+    }
+
+    #[test]
+    fn exported_tasks_import_unchanged() {
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let mut data = AppData::default();
+        let long =
+            "Read “Über die Zeit”, chapter 3; then notes\nand a summary, ".repeat(3) + "done";
+        data.todos_by_date
+            .insert(day, vec![TodoItem::new(long.clone(), None)]);
+        let exported = export(&data, &Stats::default());
+
+        let calendar = parse(&exported);
+        assert_eq!(calendar.todos[0].summary, long);
+        assert_eq!(calendar.todos[0].date, Some(day));
+        let (commands, summary) = import_commands(&data, calendar, day);
+        assert!(commands.is_empty());
+        assert_eq!(summary.tasks_updated, 0);
+
+        // Into an empty profile, and then again, which changes nothing.
+        let mut other = AppData::default();
+        let (commands, _) = import_commands(&other, parse(&exported), day);
+        History::default().execute(Command::Batch(commands), &mut other);
+        assert_eq!(other.todos_by_date[&day][0].text, long);
+        assert!(import_commands(&other, parse(&exported), day).0.is_empty());
+    }
+}
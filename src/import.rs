@@ -0,0 +1,676 @@
+//! Importing tasks and focus history from other apps.
+//!
+//! Three formats are understood: CSV with a column mapping chosen by the
+//! user, Taskwarrior's `task export` JSON, and Markdown checklists grouped
+//! under date headings. An import is always previewed before it is applied.
+//! Anything the app already has (the same task text on the same day, a
+//! Taskwarrior task with a known UUID, or a session with the same start and
+//! length) is left out, so importing a file twice adds nothing.
+
+use crate::app_data::{AppData, FocusSession, STATUS_DONE, Stats, TaskList, TaskRef, TodoItem};
+use crate::history::Command;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    Csv,
+    Taskwarrior,
+    Markdown,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 3] = [
+        ImportFormat::Csv,
+        ImportFormat::Taskwarrior,
+        ImportFormat::Markdown,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ImportFormat::Csv => "CSV",
+            ImportFormat::Taskwarrior => "Taskwarrior JSON",
+            ImportFormat::Markdown => "Markdown checklist",
+        }
+    }
+
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            ImportFormat::Csv => &["csv"],
+            ImportFormat::Taskwarrior => &["json"],
+            ImportFormat::Markdown => &["md", "markdown", "txt"],
+        }
+    }
+}
+
+/// What the numbers in a CSV duration column count.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum DurationUnit {
+    #[default]
+    Minutes,
+    Seconds,
+}
+
+/// Which CSV column, by index, holds each field.
+///
+/// A row with text becomes a task; a row with a duration becomes a focus
+/// session, attributed to the row's task if it has one.
+#[derive(Clone, Default, PartialEq)]
+pub struct CsvMapping {
+    pub date: Option<usize>,
+    pub text: Option<usize>,
+    pub done: Option<usize>,
+    pub project: Option<usize>,
+    pub start: Option<usize>,
+    pub duration: Option<usize>,
+    pub unit: DurationUnit,
+}
+
+impl CsvMapping {
+    /// Picks columns by their header names, as exported by this and similar apps.
+    pub fn guess(headers: &[String]) -> Self {
+        let find = |names: &[&str]| {
+            headers
+                .iter()
+                .position(|h| names.contains(&h.trim().to_lowercase().as_str()))
+        };
+        let duration = find(&["minutes", "duration", "length"]);
+        let seconds = find(&["seconds", "duration_seconds"]);
+        Self {
+            date: find(&["date", "day", "due"]),
+            text: find(&["text", "task", "title", "description", "name", "todo"]),
+            done: find(&["completed", "done", "status", "finished"]),
+            project: find(&["project", "list", "category"]),
+            start: find(&["start", "started", "start_time", "begin"]),
+            duration: duration.or(seconds),
+            unit: if duration.is_none() && seconds.is_some() {
+                DurationUnit::Seconds
+            } else {
+                DurationUnit::Minutes
+            },
+        }
+    }
+}
+
+/// The header row of a CSV file.
+pub fn csv_headers(text: &str) -> Vec<String> {
+    csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .headers()
+        .map(|h| h.iter().map(str::to_owned).collect())
+        .unwrap_or_default()
+}
+
+pub struct ImportedTask {
+    pub date: NaiveDate,
+    pub item: TodoItem,
+}
+
+/// What an import would add, worked out without changing anything.
+#[derive(Default)]
+pub struct Preview {
+    pub tasks: Vec<ImportedTask>,
+    pub sessions: Vec<FocusSession>,
+    /// Tasks and sessions left out because the app already has them.
+    pub duplicates: usize,
+    /// Rows or lines that couldn't be read, and why.
+    pub problems: Vec<String>,
+}
+
+impl Preview {
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty() && self.sessions.is_empty()
+    }
+
+    pub fn session_seconds(&self) -> u64 {
+        self.sessions.iter().map(|s| s.seconds).sum()
+    }
+
+    /// The commands that add the imported tasks, so they can be undone. The
+    /// sessions can't; see [`Preview::add_sessions`].
+    pub fn task_commands(&self, data: &AppData) -> Vec<Command> {
+        // Tasks added so far per day, so each new task gets the next free index.
+        let mut added: HashMap<NaiveDate, usize> = HashMap::new();
+        self.tasks
+            .iter()
+            .map(|task| {
+                let list = TaskList::Date(task.date);
+                let count = added.entry(task.date).or_insert(0);
+                let index = data.tasks(list).len() + *count;
+                *count += 1;
+                Command::AddTodo {
+                    task: TaskRef { list, index },
+                    item: task.item.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Adds the imported sessions to the focus log and the daily totals. Each
+    /// one counts as a completed pomodoro. Stats are outside the undo history,
+    /// so this is for good.
+    pub fn add_sessions(&self, stats: &mut Stats) {
+        for session in &self.sessions {
+            let day = session.start.date();
+            let total = stats.daily_study_seconds.entry(day).or_insert(0);
+            *total = total.saturating_add(session.seconds);
+            *stats.daily_sessions.entry(day).or_insert(0) += 1;
+            let month_key = format!("{}-{}", day.year(), day.month());
+            *stats.monthly_sessions.entry(month_key).or_insert(0) += 1;
+            stats.sessions.push(session.clone());
+        }
+        stats.sessions.sort_by_key(|s| s.start);
+    }
+
+    // Returns the id of the task, or of the one already there with the same text.
+    fn push_task(&mut self, data: &AppData, date: NaiveDate, item: TodoItem) -> u64 {
+        let known = data
+            .todos_by_date
+            .get(&date)
+            .and_then(|tasks| tasks.iter().find(|t| t.text == item.text));
+        if let Some(known) = known {
+            self.duplicates += 1;
+            return known.id;
+        }
+        let id = item.id;
+        self.tasks.push(ImportedTask { date, item });
+        id
+    }
+
+    fn push_session(&mut self, stats: &Stats, session: FocusSession) {
+        let known = stats
+            .sessions
+            .iter()
+            .any(|s| s.start == session.start && s.seconds == session.seconds);
+        if known {
+            self.duplicates += 1;
+        } else {
+            self.sessions.push(session);
+        }
+    }
+}
+
+/// Reads `text` in the given format into a preview of what it would add.
+/// `mapping` is only used for CSV; tasks without a date go on `today`.
+pub fn preview(
+    format: ImportFormat,
+    text: &str,
+    mapping: &CsvMapping,
+    data: &AppData,
+    stats: &Stats,
+    today: NaiveDate,
+) -> Preview {
+    match format {
+        ImportFormat::Csv => preview_csv(text, mapping, data, stats, today),
+        ImportFormat::Taskwarrior => preview_taskwarrior(text, data, today),
+        ImportFormat::Markdown => preview_markdown(text, data, today),
+    }
+}
+
+const MAX_SESSION_SECONDS: u64 = 24 * 60 * 60;
+
+const DATE_TIME_FORMATS: [&str; 6] = [
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%m/%d/%Y %H:%M",
+    "%d.%m.%Y %H:%M",
+    "%Y%m%dT%H%M%S",
+];
+const DATE_FORMATS: [&str; 6] = [
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%m/%d/%Y",
+    "%d.%m.%Y",
+    "%A, %B %d, %Y",
+    "%B %d, %Y",
+];
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    DATE_FORMATS
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(text, f).ok())
+        .or_else(|| parse_date_time(text).map(|t| t.date()))
+}
+
+// Local date and time; values with an offset or a trailing Z are converted.
+fn parse_date_time(text: &str) -> Option<NaiveDateTime> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Local).naive_local());
+    }
+    if let Some(utc) = text.strip_suffix('Z')
+        && let Some(time) = DATE_TIME_FORMATS
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(utc, f).ok())
+    {
+        return Some(
+            Utc.from_utc_datetime(&time)
+                .with_timezone(&Local)
+                .naive_local(),
+        );
+    }
+    DATE_TIME_FORMATS
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(text, f).ok())
+}
+
+// Either "h:mm:ss" / "mm:ss", or a plain number of `unit`s. Anything longer
+// than a day is cut to one, so a bad cell can't overflow the daily totals.
+fn parse_duration(text: &str, unit: DurationUnit) -> Option<u64> {
+    let text = text.trim();
+    if text.contains(':') {
+        let seconds = text.split(':').try_fold(0u64, |total, part| {
+            total
+                .checked_mul(60)?
+                .checked_add(part.trim().parse().ok()?)
+        });
+        return seconds.map(|s| s.min(MAX_SESSION_SECONDS));
+    }
+    let amount: f64 = text.parse().ok()?;
+    let seconds = match unit {
+        DurationUnit::Minutes => amount * 60.0,
+        DurationUnit::Seconds => amount,
+    };
+    (seconds.is_finite() && seconds >= 0.0)
+        .then(|| (seconds.round() as u64).min(MAX_SESSION_SECONDS))
+}
+
+fn parse_done(text: &str) -> bool {
+    matches!(
+        text.trim().to_lowercase().as_str(),
+        "x" | "1" | "true" | "yes" | "y" | "done" | "completed" | "complete" | "✓" | "✔"
+    )
+}
+
+// Finds a project by name, or keeps the name as a #tag on the task.
+fn assign_project(data: &AppData, item: &mut TodoItem, name: &str) {
+    let name = name.trim();
+    if name.is_empty() {
+        return;
+    }
+    match data
+        .projects
+        .iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name))
+    {
+        Some(project) => item.project = Some(project.id),
+        None => {
+            let tag = name.split_whitespace().collect::<Vec<_>>().join("-");
+            item.text = format!("{} #{tag}", item.text);
+        }
+    }
+}
+
+fn preview_csv(
+    text: &str,
+    mapping: &CsvMapping,
+    data: &AppData,
+    stats: &Stats,
+    today: NaiveDate,
+) -> Preview {
+    let mut preview = Preview::default();
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    for (row, record) in reader.records().enumerate() {
+        // Row numbers as a spreadsheet would show them, after the header.
+        let row = row + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                preview.problems.push(format!("Row {row}: {e}"));
+                continue;
+            }
+        };
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|c| record.get(c))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+
+        let start = match field(mapping.start) {
+            Some(value) => match parse_date_time(value) {
+                Some(start) => Some(start),
+                None => {
+                    preview.problems.push(format!(
+                        "Row {row}: couldn't read the start time \"{value}\""
+                    ));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let date = match field(mapping.date) {
+            Some(value) => match parse_date(value) {
+                Some(date) => Some(date),
+                None => {
+                    preview
+                        .problems
+                        .push(format!("Row {row}: couldn't read the date \"{value}\""));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let seconds = match field(mapping.duration) {
+            Some(value) => match parse_duration(value, mapping.unit) {
+                Some(seconds) => seconds,
+                None => {
+                    preview
+                        .problems
+                        .push(format!("Row {row}: couldn't read the duration \"{value}\""));
+                    continue;
+                }
+            },
+            None => 0,
+        };
+
+        let mut task = None;
+        if let Some(text) = field(mapping.text) {
+            let mut item = TodoItem::new(text.to_owned(), None);
+            if field(mapping.done).is_some_and(parse_done) {
+                item.status = STATUS_DONE;
+            }
+            if let Some(project) = field(mapping.project) {
+                assign_project(data, &mut item, project);
+            }
+            if item.is_done() {
+                item.completed_on = date.or(start.map(|s| s.date()));
+            }
+            let project = item.project;
+            let day = date.or(start.map(|s| s.date())).unwrap_or(today);
+            task = Some((preview.push_task(data, day, item), project));
+        }
+
+        if seconds > 0 {
+            let Some(start) = start.or(date.and_then(|d| d.and_hms_opt(0, 0, 0))) else {
+                preview
+                    .problems
+                    .push(format!("Row {row}: a session needs a start time or a date"));
+                continue;
+            };
+            let session = FocusSession {
+                start,
+                seconds,
+                task: task.map(|(id, _)| id),
+                project: task.and_then(|(_, project)| project),
+            };
+            preview.push_session(stats, session);
+        }
+    }
+    preview
+}
+
+// Taskwarrior writes times as UTC "20250101T120000Z".
+fn taskwarrior_time(task: &Value, key: &str) -> Option<NaiveDateTime> {
+    task.get(key)?.as_str().and_then(parse_date_time)
+}
+
+fn preview_taskwarrior(text: &str, data: &AppData, today: NaiveDate) -> Preview {
+    let mut preview = Preview::default();
+    // `task export` writes a JSON array; older versions wrote one object per line.
+    let tasks: Vec<Value> = match serde_json::from_str::<Value>(text) {
+        Ok(Value::Array(tasks)) => tasks,
+        Ok(task) => vec![task],
+        Err(_) => text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(number, line)| match serde_json::from_str(line) {
+                Ok(task) => Some(task),
+                Err(e) => {
+                    preview.problems.push(format!("Line {}: {e}", number + 1));
+                    None
+                }
+            })
+            .collect(),
+    };
+    let known_uids: Vec<&str> = data.all_tasks().filter_map(|t| t.uid.as_deref()).collect();
+
+    for (number, task) in tasks.iter().enumerate() {
+        let status = task.get("status").and_then(Value::as_str).unwrap_or("");
+        // Deleted tasks are gone, and recurring ones are templates for other tasks.
+        if status == "deleted" || status == "recurring" {
+            continue;
+        }
+        let Some(description) = task.get("description").and_then(Value::as_str) else {
+            preview
+                .problems
+                .push(format!("Task {}: no description", number + 1));
+            continue;
+        };
+        let uid = task.get("uuid").and_then(Value::as_str);
+        if uid.is_some_and(|uid| known_uids.contains(&uid)) {
+            preview.duplicates += 1;
+            continue;
+        }
+
+        let mut item = TodoItem::new(description.to_owned(), None);
+        item.uid = uid.map(str::to_owned);
+        item.priority = match task.get("priority").and_then(Value::as_str) {
+            Some("H") => Some('A'),
+            Some("M") => Some('B'),
+            Some("L") => Some('C'),
+            _ => None,
+        };
+        if let Some(tags) = task.get("tags").and_then(Value::as_array) {
+            for tag in tags.iter().filter_map(Value::as_str) {
+                item.text = format!("{} #{tag}", item.text);
+            }
+        }
+        if let Some(project) = task.get("project").and_then(Value::as_str) {
+            assign_project(data, &mut item, project);
+        }
+        if status == "completed" {
+            item.status = STATUS_DONE;
+            item.completed_on = taskwarrior_time(task, "end").map(|t| t.date());
+        }
+        let date = ["due", "scheduled", "entry"]
+            .iter()
+            .find_map(|key| taskwarrior_time(task, key))
+            .map_or(today, |t| t.date());
+        preview.push_task(data, date, item);
+    }
+    preview
+}
+
+// The date named by a heading, either the whole heading or an ISO date in it.
+fn heading_date(heading: &str) -> Option<NaiveDate> {
+    parse_date(heading).or_else(|| {
+        heading
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ':')
+            .find_map(|word| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok())
+    })
+}
+
+fn preview_markdown(text: &str, data: &AppData, today: NaiveDate) -> Preview {
+    let mut preview = Preview::default();
+    // Tasks above the first dated heading go on today's list.
+    let mut date = today;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(heading) = line.strip_prefix('#') {
+            // Headings without a date, like "### Errands", keep the current day.
+            if let Some(day) = heading_date(heading.trim_start_matches('#').trim()) {
+                date = day;
+            }
+            continue;
+        }
+        let Some(item) = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .or_else(|| line.strip_prefix("+ "))
+        else {
+            continue;
+        };
+        let (done, text) = if let Some(text) = item.strip_prefix("[ ]") {
+            (false, text)
+        } else if let Some(text) = item
+            .strip_prefix("[x]")
+            .or_else(|| item.strip_prefix("[X]"))
+        {
+            (true, text)
+        } else {
+            continue;
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let mut item = TodoItem::new(text.to_owned(), None);
+        if done {
+            item.status = STATUS_DONE;
+            item.completed_on = Some(date);
+        }
+        preview.push_task(data, date, item);
+    }
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    fn csv(text: &str, stats: &Stats) -> Preview {
+        let mapping = CsvMapping::guess(&csv_headers(text));
+        preview(
+            ImportFormat::Csv,
+            text,
+            &mapping,
+            &AppData::default(),
+            stats,
+            day(1),
+        )
+    }
+
+    #[test]
+    fn durations_are_read_and_capped() {
+        use DurationUnit::{Minutes, Seconds};
+        assert_eq!(parse_duration("25", Minutes), Some(1500));
+        assert_eq!(parse_duration("1.5", Minutes), Some(90));
+        assert_eq!(parse_duration("90", Seconds), Some(90));
+        assert_eq!(parse_duration("1:02:03", Minutes), Some(3723));
+        assert_eq!(parse_duration("25:00", Seconds), Some(1500));
+        assert_eq!(parse_duration("100000", Minutes), Some(MAX_SESSION_SECONDS));
+        assert_eq!(parse_duration("99999999999999999:0:0", Minutes), None);
+        for bad in ["inf", "-inf", "NaN", "1e400", "-5", "", "soon", "1::"] {
+            assert_eq!(parse_duration(bad, Minutes), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn csv_rows_become_tasks_and_sessions() {
+        let preview = csv(
+            "task,date,done,minutes,start\n\
+             Read,2025-03-02,yes,25,2025-03-02 09:00\n\
+             ,2025-03-03,,1:00:00,2025-03-03T10:00:00\n",
+            &Stats::default(),
+        );
+        let [task] = &preview.tasks[..] else {
+            panic!("expected one task");
+        };
+        assert_eq!((task.date, task.item.text.as_str()), (day(2), "Read"));
+        assert!(task.item.is_done());
+        assert_eq!(task.item.completed_on, Some(day(2)));
+        assert_eq!(preview.sessions.len(), 2);
+        assert_eq!(preview.sessions[0].task, Some(task.item.id));
+        assert_eq!(preview.sessions[1].task, None);
+        assert_eq!(preview.session_seconds(), 25 * 60 + 3600);
+        assert!(preview.problems.is_empty());
+    }
+
+    #[test]
+    fn unreadable_csv_rows_are_reported_by_row() {
+        let preview = csv(
+            "task,date,minutes,start\n\
+             Bad date,someday,,\n\
+             Bad start,,5,half past nine\n\
+             Bad length,2025-03-02,inf,\n\
+             No start,,5,\n\
+             Fine,2025-03-02,5,\n",
+            &Stats::default(),
+        );
+        assert_eq!(
+            preview.problems,
+            [
+                "Row 2: couldn't read the date \"someday\"",
+                "Row 3: couldn't read the start time \"half past nine\"",
+                "Row 4: couldn't read the duration \"inf\"",
+                "Row 5: a session needs a start time or a date",
+            ]
+        );
+        // The task of a row whose session couldn't be read is still imported.
+        let texts: Vec<&str> = preview.tasks.iter().map(|t| t.item.text.as_str()).collect();
+        assert_eq!(texts, ["No start", "Fine"]);
+        assert_eq!(preview.session_seconds(), 300);
+    }
+
+    #[test]
+    fn importing_twice_adds_nothing() {
+        let text = "task,date,minutes,start\nRead,2025-03-02,25,2025-03-02 09:00\n";
+        let first = csv(text, &Stats::default());
+        let mut stats = Stats::default();
+        first.add_sessions(&mut stats);
+        assert_eq!(stats.daily_study_seconds[&day(2)], 1500);
+        assert_eq!(stats.daily_sessions[&day(2)], 1);
+
+        let again = csv(text, &stats);
+        assert!(again.sessions.is_empty());
+        assert_eq!(again.duplicates, 1);
+    }
+
+    #[test]
+    fn taskwarrior_and_markdown_tasks_are_dated() {
+        let data = AppData::default();
+        let taskwarrior = r#"[
+            {"uuid":"u1","description":"Write","status":"completed","entry":"20250301T120000Z",
+             "due":"20250304T120000Z","end":"20250303T120000Z","priority":"H","tags":["thesis"]},
+            {"uuid":"u2","description":"Gone","status":"deleted"},
+            {"uuid":"u3","status":"pending"}
+        ]"#;
+        let preview = preview(
+            ImportFormat::Taskwarrior,
+            taskwarrior,
+            &CsvMapping::default(),
+            &data,
+            &Stats::default(),
+            day(1),
+        );
+        let [task] = &preview.tasks[..] else {
+            panic!("expected one task");
+        };
+        assert_eq!(task.item.text, "Write #thesis");
+        assert_eq!(
+            (task.item.priority, task.item.uid.as_deref()),
+            (Some('A'), Some("u1"))
+        );
+        assert!(task.item.is_done());
+        assert_eq!(preview.problems, ["Task 3: no description"]);
+
+        let markdown = "- [ ] Today\n# Monday, March 3, 2025\n- [x] Done\n### Errands\n* [ ] Shop\n## 2025-03-04 plan\n- [X] Later\n- not a task\n";
+        let preview = preview_markdown(markdown, &data, day(1));
+        let tasks: Vec<_> = preview
+            .tasks
+            .iter()
+            .map(|t| (t.date, t.item.text.as_str(), t.item.is_done()))
+            .collect();
+        assert_eq!(
+            tasks,
+            [
+                (day(1), "Today", false),
+                (day(3), "Done", true),
+                (day(3), "Shop", false),
+                (day(4), "Later", true),
+            ]
+        );
+    }
+}
@@ -1,22 +1,53 @@
 #![windows_subsystem = "windows"]
 
+mod achievements;
+mod analytics;
 mod app_data;
+mod cli;
+mod crypto;
+mod export;
 mod gif_handler;
+mod goals;
+mod history;
+mod ical;
+mod import;
+mod points;
+mod profiles;
+mod review;
+mod storage;
+mod streaks;
+mod sync;
 mod timer;
+mod todotxt;
 mod ui;
+mod webdav;
+mod xp;
 
 use std::path::PathBuf;
 use std::sync::mpsc::{self, Receiver};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use chrono::prelude::*;
+use clap::Parser;
 use eframe::egui;
 use rodio::{OutputStream, OutputStreamHandle};
 
-use app_data::AppData;
+use app_data::{AppData, GoalUnit, StreakGoal};
 use gif_handler::GifHandler;
-use timer::{StudyTimer, TimerState};
+use history::{Command, History};
+use storage::Storage;
+use timer::{StudyTimer, TimerMode, TimerState};
+use ui::board::BoardState;
+use ui::calendar::CalendarState;
+use ui::encryption::{EncryptionAction, EncryptionState, UnlockState};
+use ui::export::ExportState;
+use ui::import::ImportState;
+use ui::profiles::{ProfileAction, ProfilesState};
+use ui::review::ReviewState;
+use ui::rewards::RewardsState;
+use ui::stats::StatsState;
+use ui::todo_window::TodoWindowState;
 
 // Main application state struct
 pub struct FocusHubApp {
@@ -24,38 +55,77 @@ pub struct FocusHubApp {
     timer: StudyTimer,
     gif_handler: GifHandler,
     ui_manager: UIManager,
+    history: History,
+    storage: Box<dyn Storage>,
 
     // UI state and inputs
-    new_todo_input: String,
-    new_reward_input: String,
+    todo_window: TodoWindowState,
+    board: BoardState,
+    stats_view: StatsState,
+    rewards_view: RewardsState,
+    review: ReviewState,
+    export: ExportState,
+    import: ImportState,
+    encryption: EncryptionState,
+    profiles: ProfilesState,
+    // Set when the user picks another profile; the launcher swaps the app out.
+    switch_profile: Option<String>,
+    pending_commands: Vec<Command>,
     selected_date: NaiveDate,
-    calendar_date: NaiveDate,
+    calendar: CalendarState,
     selected_gmt_offset: i32,
     repaint_fps: u64,
     current_time: String,
     should_quit: bool,
+    // The level shown last frame, and when the latest level-up happened.
+    last_level: Option<u32>,
+    level_up_at: Option<Instant>,
 
     // Asynchronous operations
     file_dialog_receiver: Receiver<PathBuf>,
+    import_receiver: Receiver<FileRequest>,
+    todotxt_sync: Option<todotxt::LiveSync>,
+    folder_sync: Option<sync::FolderSync>,
+    // Set while syncing through a WebDAV server; `folder_sync` then runs on its mirror.
+    webdav: Option<webdav::WebDavSync>,
+    webdav_form: webdav::WebDavConfig,
 
     // Audio
     _stream: OutputStream,
     stream_handle: OutputStreamHandle,
 }
 
+// A file picked on a background thread, waiting to be handled by the app.
+enum FileRequest {
+    Ics(String),
+    TodoTxt(String),
+    SyncTodoTxt(PathBuf),
+    SyncFolder(PathBuf),
+}
+
 // Manages the visibility of different UI windows
 pub struct UIManager {
     show_todos: bool,
+    show_board: bool,
     show_calendar: bool,
     show_stats: bool,
     show_rewards: bool,
+    show_achievements: bool,
+    show_review: bool,
+    show_export: bool,
+    show_import: bool,
+    show_sync_conflicts: bool,
+    show_webdav: bool,
+    show_encryption: bool,
+    show_storage: bool,
+    show_profiles: bool,
     show_notification: bool,
     notification_title: String,
     notification_message: String,
 }
 
 impl FocusHubApp {
-    fn new(cc: &eframe::CreationContext<'_>, app_data: AppData) -> Self {
+    fn new(ctx: &egui::Context, mut app_data: AppData, storage: Box<dyn Storage>) -> Self {
         let (stream, stream_handle) = OutputStream::try_default().unwrap();
         let (_file_tx, file_rx) = mpsc::channel();
 
@@ -72,9 +142,36 @@ impl FocusHubApp {
         } else {
             gif_handler.load_from_path(PathBuf::from("assets/background.gif"));
         }
-        gif_handler.prime_cache(&cc.egui_ctx);
+        gif_handler.prime_cache(ctx);
+        let profile = profiles::active();
+        let title = if profile == profiles::DEFAULT_PROFILE {
+            "Focus Hub".to_owned()
+        } else {
+            format!("Focus Hub – {profile}")
+        };
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title(title));
+
+        // On the first launch of a new week, offer a look back at the last one.
+        let this_week = goals::week_start(today);
+        let show_review = app_data.settings.review_on_new_week
+            && app_data
+                .last_review_week
+                .is_some_and(|week| week < this_week);
+        app_data.last_review_week = Some(this_week);
+        let todotxt_sync = app_data
+            .settings
+            .todotxt_sync_path
+            .as_ref()
+            .map(|path| todotxt::LiveSync::new(PathBuf::from(path)));
+
+        let mut review = ReviewState::new(today);
+        if show_review {
+            review.date = review::ReviewPeriod::Week.previous(today);
+        }
 
-        Self {
+        let sync_folder = app_data.settings.sync_folder.clone();
+        let webdav_form = webdav::load_config();
+        let mut app = Self {
             timer: StudyTimer::new(
                 app_data.stats.clone(),
                 Duration::from_secs(60 * 60),
@@ -85,33 +182,186 @@ impl FocusHubApp {
             gif_handler,
             ui_manager: UIManager {
                 show_todos: false,
+                show_board: false,
                 show_calendar: false,
                 show_stats: false,
                 show_rewards: false,
+                show_achievements: false,
+                show_review,
+                show_export: false,
+                show_import: false,
+                show_sync_conflicts: false,
+                show_webdav: false,
+                show_encryption: false,
+                show_storage: false,
+                show_profiles: false,
                 show_notification: false,
                 notification_title: String::new(),
                 notification_message: String::new(),
             },
-            new_todo_input: String::new(),
-            new_reward_input: String::new(),
+            history: History::default(),
+            storage,
+            todo_window: TodoWindowState::default(),
+            board: BoardState::default(),
+            stats_view: StatsState::default(),
+            rewards_view: RewardsState::default(),
+            review,
+            export: ExportState::default(),
+            import: ImportState::default(),
+            encryption: EncryptionState::default(),
+            profiles: ProfilesState::default(),
+            switch_profile: None,
+            pending_commands: Vec::new(),
             selected_date: today,
-            calendar_date: today,
+            calendar: CalendarState::new(today),
             selected_gmt_offset: (offset_seconds / 3600),
             repaint_fps: 30,
             current_time: String::new(),
             should_quit: false,
+            last_level: None,
+            level_up_at: None,
             file_dialog_receiver: file_rx,
+            import_receiver: mpsc::channel().1,
+            todotxt_sync,
+            folder_sync: None,
+            webdav: None,
+            webdav_form,
             _stream: stream,
             stream_handle,
+        };
+        if app.webdav_form.enabled {
+            app.start_webdav();
+        } else if let Some(folder) = sync_folder {
+            app.start_folder_sync(PathBuf::from(folder));
+        }
+        app
+    }
+}
+
+// Starts on the profile picker when there are several profiles, and on the
+// unlock screen when the chosen profile's data file is encrypted.
+enum Launcher {
+    Picking(ProfilesState),
+    Locked(Box<dyn Storage>, UnlockState),
+    Running(Box<FocusHubApp>),
+    Closed,
+}
+
+impl Launcher {
+    fn open(ctx: &egui::Context, profile: &str) -> Self {
+        profiles::set_active(profile);
+        match open_storage() {
+            Some((storage, Some(app_data))) => {
+                Launcher::Running(Box::new(FocusHubApp::new(ctx, app_data, storage)))
+            }
+            Some((storage, None)) => Launcher::Locked(storage, UnlockState::default()),
+            None => {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                Launcher::Closed
+            }
+        }
+    }
+}
+
+impl eframe::App for Launcher {
+    fn on_exit(&mut self, gl: Option<&eframe::glow::Context>) {
+        if let Launcher::Running(app) = self {
+            app.on_exit(gl);
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        match self {
+            Launcher::Picking(state) => {
+                if let Some(profile) = ui::profiles::draw_profile_picker(ctx, state) {
+                    *self = Launcher::open(ctx, &profile);
+                }
+            }
+            Launcher::Locked(storage, state) => {
+                if let Some(app_data) =
+                    ui::encryption::draw_unlock_screen(ctx, state, storage.as_mut())
+                {
+                    let Launcher::Locked(storage, _) = std::mem::replace(self, Launcher::Closed)
+                    else {
+                        unreachable!()
+                    };
+                    *self = Launcher::Running(Box::new(FocusHubApp::new(ctx, app_data, storage)));
+                }
+            }
+            Launcher::Running(app) => {
+                app.update(ctx, frame);
+                if let Some(profile) = app.switch_profile.take() {
+                    app.on_exit(None);
+                    *self = Launcher::open(ctx, &profile);
+                }
+            }
+            Launcher::Closed => {}
+        }
+    }
+}
+
+// Opens the active profile's storage and reads it, unless it is locked.
+// Reports an error and returns `None` if the storage can't be opened at all.
+fn open_storage() -> Option<(Box<dyn Storage>, Option<AppData>)> {
+    // Keeps commands from changing the data while the window would overwrite
+    // it. Another window on the same profile is let through as before.
+    profiles::claim_active().ok();
+    match storage::open() {
+        Ok(storage) if storage.is_locked() => Some((storage, None)),
+        Ok(mut storage) => {
+            let app_data = storage.load().unwrap_or_default();
+            Some((storage, Some(app_data)))
+        }
+        Err(e) => {
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Error)
+                .set_title("Load Error")
+                .set_description(format!("Could not open the saved data: {e}"))
+                .show();
+            None
         }
     }
 }
 
 fn main() -> Result<(), eframe::Error> {
-    let app_data = app_data::load().unwrap_or_default();
-    let initial_size = app_data
-        .gif_path
+    // Only commands, help and usage errors write to the console; opening the
+    // window, even on a profile, shouldn't take over the shell's.
+    let args = cli::Cli::try_parse().unwrap_or_else(|e| {
+        cli::attach_console();
+        e.exit()
+    });
+    if let Some(command) = args.command {
+        cli::attach_console();
+        std::process::exit(cli::run(args.profile, command));
+    }
+
+    let picking = match args.profile {
+        Some(name) if !profiles::exists(&name) => {
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Error)
+                .set_title("Unknown Profile")
+                .set_description(format!("There is no profile called {name}."))
+                .show();
+            return Ok(());
+        }
+        Some(name) => {
+            profiles::set_active(&name);
+            false
+        }
+        None => profiles::list().len() > 1,
+    };
+    let opened = if picking {
+        None
+    } else {
+        match open_storage() {
+            Some(opened) => Some(opened),
+            None => return Ok(()),
+        }
+    };
+    let initial_size = opened
         .as_ref()
+        .and_then(|(_, app_data)| app_data.as_ref())
+        .and_then(|data| data.gif_path.as_ref())
         .and_then(|p| gif_handler::get_gif_dimensions(&PathBuf::from(p)).ok())
         .map(|(w, h)| egui::vec2(w as f32, h as f32))
         .unwrap_or_else(|| egui::vec2(500.0, 450.0));
@@ -129,16 +379,31 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "Focus Hub",
         options,
-        Box::new(move |cc| Ok(Box::new(FocusHubApp::new(cc, app_data)))),
+        Box::new(move |cc| {
+            Ok(Box::new(match opened {
+                Some((storage, Some(app_data))) => {
+                    Launcher::Running(Box::new(FocusHubApp::new(&cc.egui_ctx, app_data, storage)))
+                }
+                Some((storage, None)) => Launcher::Locked(storage, UnlockState::default()),
+                None => Launcher::Picking(ProfilesState::default()),
+            }))
+        }),
     )
 }
 
 impl eframe::App for FocusHubApp {
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.timer.finish_session();
+        self.sync_folder_now();
+        if let Some(webdav) = &mut self.webdav
+            && let Err(e) = webdav.upload_now(&self.app_data.settings.sync_device)
+        {
+            eprintln!("Failed to upload to the WebDAV server: {e}");
+        }
         self.app_data.stats = self.timer.stats.clone();
         self.app_data.gif_path = self.gif_handler.get_path_string();
 
-        if let Err(e) = app_data::save(&self.app_data) {
+        if let Err(e) = self.storage.save(&self.app_data) {
             rfd::MessageDialog::new()
                 .set_level(rfd::MessageLevel::Error)
                 .set_title("Save Error")
@@ -157,36 +422,222 @@ impl eframe::App for FocusHubApp {
 
         self.update_clock();
         self.handle_file_dialog(ctx);
+        self.handle_import();
+        self.poll_todotxt_sync();
+        self.poll_folder_sync();
+        self.poll_webdav();
+        self.handle_undo_shortcuts(ctx);
         if self.timer.tick() {
             self.handle_session_switch();
         }
         self.gif_handler.tick(ctx);
 
+        let today = Local::now().date_naive();
+        streaks::update_freezes(&mut self.timer.stats, &self.app_data.settings, today);
+        let streak = streaks::summary(&self.timer.stats, &self.app_data.settings, today);
+        for event in goals::update(&mut self.timer.stats, &self.app_data.settings.goals, today) {
+            let (message, reward) = match event {
+                goals::GoalEvent::DailyReached => (
+                    "You reached today's focus goal.",
+                    points::POINTS_PER_DAILY_GOAL,
+                ),
+                goals::GoalEvent::WeeklyReached => (
+                    "You reached this week's focus goal.",
+                    points::POINTS_PER_WEEKLY_GOAL,
+                ),
+            };
+            self.app_data
+                .wallet
+                .record(points::transaction(reward as i64, message));
+            self.notify("Goal Reached! 🎯", message);
+        }
+        // Several can unlock at once, e.g. on the first launch with existing history.
+        let unlocked: Vec<String> =
+            achievements::update(&mut self.app_data, &self.timer.stats, &streak)
+                .iter()
+                .map(|a| format!("{} {}: {}", a.icon, a.name, a.description))
+                .collect();
+        if !unlocked.is_empty() {
+            self.notify("Achievement Unlocked! 🏅", &unlocked.join("\n"));
+        }
+
+        let level = xp::level_info(
+            xp::total(&self.app_data, &self.timer.stats),
+            &self.app_data.settings.level_curve,
+        );
+        if self.last_level.is_some_and(|last| level.level > last) {
+            self.level_up_at = Some(Instant::now());
+        }
+        self.last_level = Some(level.level);
+
         self.gif_handler.draw_background(ctx);
-        self.ui_top_menu(ctx);
-        ui::draw_central_panel(ctx, &mut self.timer, &self.current_time);
+        self.ui_top_menu(ctx, &level);
+        ui::draw_central_panel(
+            ctx,
+            &mut self.timer,
+            &self.current_time,
+            &streak,
+            &self.app_data.settings.goals,
+        );
+        ui::draw_notification(
+            ctx,
+            &mut self.ui_manager.show_notification,
+            &self.ui_manager.notification_title,
+            &self.ui_manager.notification_message,
+        );
 
         ui::draw_todo_window(
             ctx,
             &mut self.ui_manager.show_todos,
-            &mut self.app_data.todos_by_date,
-            &mut self.new_todo_input,
+            &self.app_data,
             &mut self.selected_date,
+            &mut self.todo_window,
+            &mut self.timer.focus,
+            &mut self.pending_commands,
+        );
+        ui::draw_board_window(
+            ctx,
+            &mut self.ui_manager.show_board,
+            &self.app_data,
+            &self.timer.stats,
+            self.selected_date,
+            &mut self.board,
+            &mut self.pending_commands,
         );
         ui::draw_calendar_window(
             ctx,
             &mut self.ui_manager.show_calendar,
-            &mut self.calendar_date,
+            &mut self.calendar,
             &mut self.selected_date,
-            &self.app_data.todos_by_date,
+            &self.app_data,
+            &self.timer.stats,
+            &mut self.pending_commands,
+        );
+        ui::draw_stats_window(
+            ctx,
+            &mut self.ui_manager.show_stats,
+            &self.timer.stats,
+            &self.app_data.projects,
+            &streak,
+            &mut self.stats_view,
         );
-        ui::draw_stats_window(ctx, &mut self.ui_manager.show_stats, &self.timer.stats);
         ui::draw_rewards_window(
             ctx,
             &mut self.ui_manager.show_rewards,
-            &mut self.app_data.rewards,
-            &mut self.new_reward_input,
+            &self.app_data.rewards,
+            &self.app_data.wallet,
+            level.level,
+            &mut self.rewards_view,
+            &mut self.pending_commands,
+        );
+        ui::draw_achievements_window(
+            ctx,
+            &mut self.ui_manager.show_achievements,
+            &self.app_data,
+            &self.timer.stats,
+            &streak,
+        );
+        ui::draw_review_window(
+            ctx,
+            &mut self.ui_manager.show_review,
+            &mut self.review,
+            &self.app_data,
+            &self.timer.stats,
+            &streak,
+        );
+        ui::draw_export_window(
+            ctx,
+            &mut self.ui_manager.show_export,
+            &mut self.export,
+            &self.app_data,
+            &self.timer.stats,
         );
+        if let Some(preview) = ui::draw_import_window(
+            ctx,
+            &mut self.ui_manager.show_import,
+            &mut self.import,
+            &self.app_data,
+            &self.timer.stats,
+        ) {
+            let commands = preview.task_commands(&self.app_data);
+            if !commands.is_empty() {
+                self.pending_commands.push(Command::Batch(commands));
+            }
+            preview.add_sessions(&mut self.timer.stats);
+            let message = format!(
+                "Added {} tasks and {} focus sessions ({}).",
+                preview.tasks.len(),
+                preview.sessions.len(),
+                ui::format_duration(preview.session_seconds())
+            );
+            self.notify("Import Complete", &message);
+        }
+        if let Some(sync) = &mut self.folder_sync {
+            ui::draw_sync_conflicts(
+                ctx,
+                &mut self.ui_manager.show_sync_conflicts,
+                &mut sync.conflicts,
+                &self.app_data,
+                &mut self.pending_commands,
+            );
+        }
+        let status = self.webdav.as_ref().map(|w| &w.status);
+        match ui::draw_webdav_window(
+            ctx,
+            &mut self.ui_manager.show_webdav,
+            &mut self.webdav_form,
+            status,
+        ) {
+            Some(ui::webdav::WebDavAction::Enable) => self.start_webdav(),
+            Some(ui::webdav::WebDavAction::Disable) => self.stop_sync(),
+            Some(ui::webdav::WebDavAction::SyncNow) => self.sync_webdav_now(),
+            None => {}
+        }
+        match ui::draw_encryption_window(
+            ctx,
+            &mut self.ui_manager.show_encryption,
+            &mut self.encryption,
+            self.storage.key(),
+        ) {
+            Some(EncryptionAction::SetPassphrase(key)) => self.set_key(Some(key)),
+            Some(EncryptionAction::TurnOff) => self.set_key(None),
+            None => {}
+        }
+        if ui::draw_storage_window(
+            ctx,
+            &mut self.ui_manager.show_storage,
+            self.storage.kind(),
+            self.storage.key().is_some(),
+        )
+        .is_some()
+        {
+            self.migrate_storage();
+        }
+        match ui::draw_profiles_window(ctx, &mut self.ui_manager.show_profiles, &mut self.profiles)
+        {
+            Some(ProfileAction::Switch(profile)) => self.switch_profile = Some(profile),
+            Some(ProfileAction::Duplicate(profile)) => self.duplicate_profile(&profile),
+            None => {}
+        }
+        if let Some(since) = self.level_up_at
+            && !ui::level::draw_level_up(ctx, level.level, since)
+        {
+            self.level_up_at = None;
+        }
+
+        for mut command in std::mem::take(&mut self.pending_commands) {
+            if self.app_data.settings.surface_unblocked {
+                command = history::surface_unblocked(command, &self.app_data, today);
+            }
+            command = points::award_for_tasks(command, &self.app_data);
+            self.history.execute(command, &mut self.app_data);
+        }
+        self.write_todotxt_sync();
+        if let Some(sync) = &mut self.folder_sync
+            && let Err(e) = sync.write(&self.app_data, &self.timer.stats, false)
+        {
+            eprintln!("Failed to write to the sync folder: {e}");
+        }
     }
 }
 
@@ -199,10 +650,18 @@ impl FocusHubApp {
             .to_string();
     }
 
-    fn ui_top_menu(&mut self, ctx: &egui::Context) {
+    fn ui_top_menu(&mut self, ctx: &egui::Context, level: &xp::LevelInfo) {
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
+                    if ui
+                        .button(format!("Profile: {}...", profiles::active()))
+                        .clicked()
+                    {
+                        self.ui_manager.show_profiles = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
                     if ui.button("Open GIF...").clicked() {
                         let (tx, rx) = mpsc::channel();
                         self.file_dialog_receiver = rx;
@@ -216,14 +675,183 @@ impl FocusHubApp {
                         });
                         ui.close_menu();
                     }
+                    if ui.button("Import...").clicked() {
+                        self.ui_manager.show_import = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Export...").clicked() {
+                        self.ui_manager.show_export = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Import iCalendar...").clicked() {
+                        self.read_in_background("iCalendar", &["ics"], FileRequest::Ics);
+                        ui.close_menu();
+                    }
+                    if ui.button("Export iCalendar...").clicked() {
+                        let contents = ical::export(&self.app_data, &self.timer.stats);
+                        ui::save_in_background(
+                            "iCalendar",
+                            "ics",
+                            "focushub.ics".to_owned(),
+                            |path| Ok(std::fs::write(path, contents)?),
+                        );
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Import todo.txt...").clicked() {
+                        self.read_in_background("todo.txt", &["txt"], FileRequest::TodoTxt);
+                        ui.close_menu();
+                    }
+                    if ui.button("Export todo.txt...").clicked() {
+                        let contents = todotxt::export(&self.app_data);
+                        ui::save_in_background("todo.txt", "txt", "todo.txt".to_owned(), |path| {
+                            Ok(std::fs::write(path, contents)?)
+                        });
+                        ui.close_menu();
+                    }
+                    if let Some(sync) = &self.todotxt_sync {
+                        let label = format!("Stop Syncing {}", sync.path().display());
+                        if ui.button(label).clicked() {
+                            self.todotxt_sync = None;
+                            self.app_data.settings.todotxt_sync_path = None;
+                            ui.close_menu();
+                        }
+                    } else if ui.button("Sync with todo.txt...").clicked() {
+                        let (tx, rx) = mpsc::channel();
+                        self.import_receiver = rx;
+                        thread::spawn(move || {
+                            // A save dialog, so a file that doesn't exist yet can be chosen too.
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("todo.txt", &["txt"])
+                                .set_file_name("todo.txt")
+                                .save_file()
+                            {
+                                tx.send(FileRequest::SyncTodoTxt(path)).ok();
+                            }
+                        });
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if let Some(webdav) = &self.webdav {
+                        ui.weak(format!("Syncing with {}", webdav.config.url));
+                        if ui.button("Sync Now").clicked() {
+                            self.sync_webdav_now();
+                            ui.close_menu();
+                        }
+                        if ui.button("Review Sync Conflicts...").clicked() {
+                            self.ui_manager.show_sync_conflicts = true;
+                            ui.close_menu();
+                        }
+                        if ui.button("WebDAV Settings...").clicked() {
+                            self.ui_manager.show_webdav = true;
+                            ui.close_menu();
+                        }
+                    } else if let Some(sync) = &self.folder_sync {
+                        let status = match sync.last_sync {
+                            Some(time) => format!("Synced at {}", time.format("%H:%M")),
+                            None => "Not synced yet".to_owned(),
+                        };
+                        ui.weak(format!("{} ({status})", sync.folder().display()));
+                        if ui.button("Sync Now").clicked() {
+                            self.sync_folder_now();
+                            ui.close_menu();
+                        }
+                        if ui.button("Review Sync Conflicts...").clicked() {
+                            self.ui_manager.show_sync_conflicts = true;
+                            ui.close_menu();
+                        }
+                        if ui.button("Stop Folder Sync").clicked() {
+                            self.stop_sync();
+                            ui.close_menu();
+                        }
+                    } else {
+                        if ui.button("Sync Through Folder...").clicked() {
+                            let (tx, rx) = mpsc::channel();
+                            self.import_receiver = rx;
+                            thread::spawn(move || {
+                                if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                                    tx.send(FileRequest::SyncFolder(path)).ok();
+                                }
+                            });
+                            ui.close_menu();
+                        }
+                        if ui.button("Sync Through WebDAV...").clicked() {
+                            self.ui_manager.show_webdav = true;
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    if ui.button("Encryption...").clicked() {
+                        self.ui_manager.show_encryption = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Storage...").clicked() {
+                        self.ui_manager.show_storage = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Back Up Data...").clicked() {
+                        self.app_data.stats = self.timer.stats.clone();
+                        self.app_data.gif_path = self.gif_handler.get_path_string();
+                        match storage::json::to_file_contents(&self.app_data, self.storage.key()) {
+                            Ok(contents) => {
+                                let file_name = format!(
+                                    "focushub_backup_{}.json",
+                                    Local::now().format("%Y-%m-%d")
+                                );
+                                ui::save_in_background(
+                                    "Focus Hub data",
+                                    "json",
+                                    file_name,
+                                    |path| Ok(std::fs::write(path, contents)?),
+                                );
+                            }
+                            Err(e) => {
+                                self.notify("Backup Error", &format!("Could not back up: {e}"))
+                            }
+                        }
+                        ui.close_menu();
+                    }
+                    ui.separator();
                     if ui.button("Quit").clicked() {
                         self.should_quit = true;
                     }
                 });
 
+                ui.menu_button("Edit", |ui| {
+                    let undo_text = match self.history.undo_label() {
+                        Some(label) => format!("Undo {label}"),
+                        None => "Undo".to_owned(),
+                    };
+                    let undo_button = egui::Button::new(undo_text).shortcut_text("Ctrl+Z");
+                    if ui
+                        .add_enabled(self.history.undo_label().is_some(), undo_button)
+                        .clicked()
+                    {
+                        self.history.undo(&mut self.app_data);
+                        ui.close_menu();
+                    }
+
+                    let redo_text = match self.history.redo_label() {
+                        Some(label) => format!("Redo {label}"),
+                        None => "Redo".to_owned(),
+                    };
+                    let redo_button = egui::Button::new(redo_text).shortcut_text("Ctrl+Shift+Z");
+                    if ui
+                        .add_enabled(self.history.redo_label().is_some(), redo_button)
+                        .clicked()
+                    {
+                        self.history.redo(&mut self.app_data);
+                        ui.close_menu();
+                    }
+                });
+
                 if ui.button("To-Do List").clicked() {
                     self.ui_manager.show_todos = !self.ui_manager.show_todos;
                 }
+                if ui.button("🗂 Board").clicked() {
+                    self.ui_manager.show_board = !self.ui_manager.show_board;
+                }
                 if ui.button("📅 Calendar").clicked() {
                     self.ui_manager.show_calendar = !self.ui_manager.show_calendar;
                 }
@@ -233,6 +861,16 @@ impl FocusHubApp {
                 if ui.button("🏆 Rewards").clicked() {
                     self.ui_manager.show_rewards = !self.ui_manager.show_rewards;
                 }
+                if ui.button("🏅 Achievements").clicked() {
+                    self.ui_manager.show_achievements = !self.ui_manager.show_achievements;
+                }
+                if ui.button("📝 Review").clicked() {
+                    self.ui_manager.show_review = !self.ui_manager.show_review;
+                }
+                let conflicts = self.folder_sync.as_ref().map_or(0, |s| s.conflicts.len());
+                if conflicts > 0 && ui.button(format!("⚠ {conflicts} conflicts")).clicked() {
+                    self.ui_manager.show_sync_conflicts = true;
+                }
 
                 ui.menu_button("Settings", |ui| {
                     ui.label("Time Zone (GMT):");
@@ -240,6 +878,104 @@ impl FocusHubApp {
                     ui.separator();
                     ui.label("Max FPS:");
                     ui.add(egui::DragValue::new(&mut self.repaint_fps).range(5..=500));
+                    ui.separator();
+                    ui.checkbox(
+                        &mut self.app_data.settings.surface_unblocked,
+                        "Move unblocked tasks to today",
+                    );
+                    ui.separator();
+                    ui.label("Heatmap levels (minutes):");
+                    ui.horizontal(|ui| {
+                        for minutes in &mut self.app_data.settings.heatmap_thresholds {
+                            ui.add(egui::DragValue::new(minutes).range(1..=1440));
+                        }
+                    });
+                    ui.separator();
+                    ui.label("Streak goal per day:");
+                    ui.horizontal(|ui| {
+                        let goal = &mut self.app_data.settings.streak_goal;
+                        let (mut amount, mut by_sessions) = match *goal {
+                            StreakGoal::Minutes(m) => (m, false),
+                            StreakGoal::Sessions(n) => (n, true),
+                        };
+                        ui.add(egui::DragValue::new(&mut amount).range(1..=1440));
+                        ui.selectable_value(&mut by_sessions, false, "minutes");
+                        ui.selectable_value(&mut by_sessions, true, "sessions");
+                        *goal = if by_sessions {
+                            StreakGoal::Sessions(amount)
+                        } else {
+                            StreakGoal::Minutes(amount)
+                        };
+                    });
+                    ui.menu_button("Focus goals", |ui| {
+                        let goals = &mut self.app_data.settings.goals;
+                        ui.horizontal(|ui| {
+                            ui.label("Measure in:");
+                            ui.selectable_value(&mut goals.unit, GoalUnit::Minutes, "minutes");
+                            ui.selectable_value(&mut goals.unit, GoalUnit::Sessions, "sessions");
+                        });
+                        ui.checkbox(&mut goals.per_weekday, "Different goal per weekday");
+                        if goals.per_weekday {
+                            let names = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+                            for (target, name) in goals.weekday_targets.iter_mut().zip(names) {
+                                ui.horizontal(|ui| {
+                                    ui.label(name);
+                                    ui.add(egui::DragValue::new(target).range(0..=1440));
+                                });
+                            }
+                        } else {
+                            ui.horizontal(|ui| {
+                                ui.label("Daily goal:");
+                                ui.add(egui::DragValue::new(&mut goals.daily).range(0..=1440));
+                            });
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Weekly goal:");
+                            ui.add(egui::DragValue::new(&mut goals.weekly).range(0..=10080));
+                        });
+                        ui.weak("Set a goal to 0 to turn it off.");
+                    });
+                    ui.label("Rest days:");
+                    ui.horizontal(|ui| {
+                        let names = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+                        for (rest, name) in self.app_data.settings.rest_days.iter_mut().zip(names) {
+                            ui.checkbox(rest, name);
+                        }
+                    });
+                    ui.checkbox(
+                        &mut self.app_data.settings.review_on_new_week,
+                        "Show last week's review each new week",
+                    );
+                    ui.separator();
+                    ui.menu_button("Level curve", |ui| {
+                        let curve = &mut self.app_data.settings.level_curve;
+                        ui.horizontal(|ui| {
+                            ui.label("XP for level 2:");
+                            ui.add(egui::DragValue::new(&mut curve.base).range(1..=10_000));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Steepness:");
+                            ui.add(
+                                egui::DragValue::new(&mut curve.exponent)
+                                    .speed(0.05)
+                                    .range(1.0..=3.0),
+                            );
+                        });
+                        ui.weak(format!(
+                            "{} XP per focused minute, {} XP per finished task.",
+                            xp::XP_PER_MINUTE,
+                            xp::XP_PER_TASK
+                        ));
+                    });
+                });
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui::level::draw_level_badge(ui, level);
+                    if let Some(webdav) = &self.webdav
+                        && ui::webdav::draw_webdav_status(ui, &webdav.status).clicked()
+                    {
+                        self.ui_manager.show_webdav = true;
+                    }
                 });
             });
         });
@@ -260,16 +996,372 @@ impl FocusHubApp {
         }
     }
 
-    fn handle_session_switch(&mut self) {
-        timer::play_beep(&self.stream_handle);
-        let (title, message) = self.timer.get_session_switch_messages();
+    // Asks for a file on a background thread and queues its contents for `handle_import`.
+    fn read_in_background(
+        &mut self,
+        filter: &'static str,
+        extensions: &'static [&'static str],
+        request: fn(String) -> FileRequest,
+    ) {
+        let (tx, rx) = mpsc::channel();
+        self.import_receiver = rx;
+        thread::spawn(move || {
+            let Some(path) = rfd::FileDialog::new()
+                .add_filter(filter, extensions)
+                .pick_file()
+            else {
+                return;
+            };
+            match std::fs::read_to_string(&path) {
+                Ok(text) => {
+                    tx.send(request(text)).ok();
+                }
+                Err(e) => {
+                    rfd::MessageDialog::new()
+                        .set_level(rfd::MessageLevel::Error)
+                        .set_title("Import Error")
+                        .set_description(format!("Could not read {}: {e}", path.display()))
+                        .show();
+                }
+            }
+        });
+    }
+
+    fn handle_import(&mut self) {
+        let Ok(request) = self.import_receiver.try_recv() else {
+            return;
+        };
+        let today = Local::now().date_naive();
+        match request {
+            FileRequest::Ics(text) => {
+                let calendar = ical::parse(&text);
+                let (commands, summary) = ical::import_commands(&self.app_data, calendar, today);
+                if !commands.is_empty() {
+                    self.pending_commands.push(Command::Batch(commands));
+                }
+                let message = format!(
+                    "Tasks: {} added, {} updated.\nEvents: {} added, {} updated.",
+                    summary.tasks_added,
+                    summary.tasks_updated,
+                    summary.events_added,
+                    summary.events_updated
+                );
+                self.notify("Calendar Imported", &message);
+            }
+            FileRequest::TodoTxt(text) => {
+                let lines = todotxt::parse(&text);
+                let merged = todotxt::merge(&self.app_data, lines, &Default::default(), today);
+                let message = match merged {
+                    Some((command, summary)) => {
+                        self.pending_commands.push(command);
+                        format!(
+                            "Tasks: {} added, {} updated.",
+                            summary.added, summary.updated
+                        )
+                    }
+                    None => "Everything was already up to date.".to_owned(),
+                };
+                self.notify("todo.txt Imported", &message);
+            }
+            FileRequest::SyncFolder(path) => self.start_folder_sync(path),
+            FileRequest::SyncTodoTxt(path) => {
+                self.app_data.settings.todotxt_sync_path =
+                    Some(path.to_string_lossy().into_owned());
+                self.todotxt_sync = Some(todotxt::LiveSync::new(path));
+            }
+        }
+    }
+
+    // Picks up edits other programs made to the synced todo.txt file.
+    fn poll_todotxt_sync(&mut self) {
+        let Some(sync) = &mut self.todotxt_sync else {
+            return;
+        };
+        match sync.poll(&self.app_data, Local::now().date_naive()) {
+            Ok(Some((command, _))) => self.pending_commands.push(command),
+            Ok(None) => {}
+            Err(e) => {
+                let message = format!("Could not read {}: {e}", sync.path().display());
+                self.stop_todotxt_sync(&message);
+            }
+        }
+    }
+
+    fn write_todotxt_sync(&mut self) {
+        let Some(sync) = &mut self.todotxt_sync else {
+            return;
+        };
+        if let Err(e) = sync.write(&self.app_data, self.history.revision()) {
+            let message = format!("Could not write {}: {e}", sync.path().display());
+            self.stop_todotxt_sync(&message);
+        }
+    }
+
+    fn start_folder_sync(&mut self, folder: PathBuf) {
+        self.stop_sync();
+        if self.sync_refused() {
+            return;
+        }
+        if self.open_folder_sync(folder.clone()) {
+            self.app_data.settings.sync_folder = Some(folder.to_string_lossy().into_owned());
+        }
+    }
+
+    // Sync writes unencrypted copies of the data, so it is refused while the
+    // data file is encrypted.
+    fn sync_refused(&mut self) -> bool {
+        if self.storage.key().is_none() {
+            return false;
+        }
+        self.notify(
+            "Sync Stopped",
+            "Sync keeps unencrypted copies of your data, so it can't be used while your data is encrypted.",
+        );
+        true
+    }
+
+    fn open_folder_sync(&mut self, folder: PathBuf) -> bool {
+        let device = &self.app_data.settings.sync_device;
+        match sync::FolderSync::open(folder.clone(), device, &self.timer.stats) {
+            Ok(sync) => {
+                self.app_data.settings.sync_device = sync.device().to_owned();
+                self.folder_sync = Some(sync);
+                true
+            }
+            Err(e) => {
+                let message = format!("Could not use {} for syncing: {e}", folder.display());
+                self.notify("Sync Stopped", &message);
+                false
+            }
+        }
+    }
+
+    // Saves the WebDAV form and syncs through the server from now on.
+    fn start_webdav(&mut self) {
+        self.stop_sync();
+        if self.sync_refused() {
+            self.webdav_form.enabled = false;
+            return;
+        }
+        self.webdav_form.enabled = true;
+        let webdav = match webdav::WebDavSync::new(self.webdav_form.clone()) {
+            Ok(webdav) => webdav,
+            Err(e) => {
+                self.webdav_form.enabled = false;
+                self.notify(
+                    "Sync Stopped",
+                    &format!("Could not set up WebDAV sync: {e}"),
+                );
+                return;
+            }
+        };
+        if self.open_folder_sync(webdav.mirror().to_path_buf()) {
+            self.webdav = Some(webdav);
+        } else {
+            self.webdav_form.enabled = false;
+        }
+        if let Err(e) = webdav::save_config(&self.webdav_form) {
+            eprintln!("Failed to save the WebDAV settings: {e}");
+        }
+    }
+
+    // Stops syncing through a folder or a WebDAV server.
+    fn stop_sync(&mut self) {
+        self.folder_sync = None;
+        self.app_data.settings.sync_folder = None;
+        if self.webdav.take().is_some() {
+            self.webdav_form.enabled = false;
+            if let Err(e) = webdav::save_config(&self.webdav_form) {
+                eprintln!("Failed to save the WebDAV settings: {e}");
+            }
+        }
+    }
+
+    // Merges what the last WebDAV run downloaded, and starts the next run when due.
+    fn poll_webdav(&mut self) {
+        let Some(webdav) = &mut self.webdav else {
+            return;
+        };
+        let finished = webdav.poll();
+        let due = webdav.due();
+        if finished == Some(true) {
+            self.merge_folder_sync();
+        }
+        if due {
+            self.sync_webdav_now();
+        }
+    }
+
+    // Writes this device's file into the mirror and starts uploading it.
+    fn sync_webdav_now(&mut self) {
+        self.sync_folder_now();
+        if let Some(webdav) = &mut self.webdav {
+            webdav.start(&self.app_data.settings.sync_device);
+        }
+    }
+
+    // Merges in other devices' changes whenever their files change.
+    fn poll_folder_sync(&mut self) {
+        let Some(sync) = &mut self.folder_sync else {
+            return;
+        };
+        match sync.poll() {
+            Ok(true) => self.merge_folder_sync(),
+            Ok(false) => {}
+            Err(e) => eprintln!("Failed to check the sync folder: {e}"),
+        }
+    }
+
+    fn merge_folder_sync(&mut self) {
+        let Some(sync) = &mut self.folder_sync else {
+            return;
+        };
+        let before = sync.conflicts.len();
+        match sync.merge(&mut self.app_data, &mut self.timer.stats, &mut self.history) {
+            Ok(summary) if summary.conflicts > before => {
+                let message = format!(
+                    "{} changes clash with another device. Your versions are kept until you review them.",
+                    summary.conflicts - before
+                );
+                self.notify("Sync Conflicts", &message);
+            }
+            Ok(_) => {}
+            Err(e) => self.notify("Sync Error", &format!("Could not merge: {e}")),
+        }
+    }
+
+    // Merges, then writes this device's file straight away.
+    fn sync_folder_now(&mut self) {
+        self.merge_folder_sync();
+        if let Some(sync) = &mut self.folder_sync
+            && let Err(e) = sync.write(&self.app_data, &self.timer.stats, true)
+        {
+            self.notify("Sync Error", &format!("Could not write: {e}"));
+        }
+    }
+
+    // Saves straight away with the new key, or unencrypted.
+    fn set_key(&mut self, key: Option<crypto::Key>) {
+        if key.is_some() && (self.folder_sync.is_some() || self.webdav.is_some()) {
+            self.encryption.set_error(
+                "Stop syncing first: sync keeps unencrypted copies of your data.".to_owned(),
+            );
+            return;
+        }
+        self.app_data.stats = self.timer.stats.clone();
+        self.app_data.gif_path = self.gif_handler.get_path_string();
+        match self.storage.set_key(key, &self.app_data) {
+            Ok(()) if self.storage.key().is_some() => {
+                self.webdav_form = webdav::WebDavConfig::default();
+                let removed = sync::remove_base()
+                    .and_then(|()| webdav::remove_local_files())
+                    .and_then(|()| storage::remove_set_aside());
+                let message = match removed {
+                    Ok(()) => "Your data is now encrypted with the new passphrase.".to_owned(),
+                    Err(e) => format!(
+                        "Your data is now encrypted with the new passphrase, but unencrypted copies left by sync or a storage switch could not be removed: {e}"
+                    ),
+                };
+                self.notify("Encryption", &message);
+            }
+            Ok(()) => self.notify("Encryption", "Your data is no longer encrypted."),
+            Err(e) => self.encryption.set_error(e.to_string()),
+        }
+    }
+
+    // Moves the data to the other kind of storage, which is used from then on.
+    fn migrate_storage(&mut self) {
+        self.app_data.stats = self.timer.stats.clone();
+        self.app_data.gif_path = self.gif_handler.get_path_string();
+        let from = std::mem::replace(
+            &mut self.storage,
+            Box::new(storage::json::JsonStorage::default()),
+        );
+        match storage::migrate(from, &self.app_data) {
+            Ok(storage) => {
+                self.storage = storage;
+                let message = format!("Your data is now kept in a {}.", self.storage.kind().name());
+                self.notify("Storage", &message);
+            }
+            Err((storage, e)) => {
+                self.storage = storage;
+                self.notify("Storage Error", &format!("Could not switch: {e}"));
+            }
+        }
+    }
+
+    // Starts a new profile from a copy of this one's data.
+    fn duplicate_profile(&mut self, profile: &str) {
+        self.app_data.stats = self.timer.stats.clone();
+        self.app_data.gif_path = self.gif_handler.get_path_string();
+        let mut copy = self.app_data.clone();
+        // The copy syncs nowhere, so the two never write each other's sync files.
+        copy.settings.sync_folder = None;
+        copy.settings.sync_device.clear();
+        copy.settings.todotxt_sync_path = None;
+        let result = storage::json::to_file_contents(&copy, self.storage.key())
+            .and_then(|contents| profiles::duplicate(profile, &contents));
+        match result {
+            Ok(()) => {
+                let mut message = format!(
+                    "{profile} starts with a copy of {}'s data.",
+                    profiles::active()
+                );
+                if self.storage.key().is_some() {
+                    message += " It is encrypted with the same passphrase.";
+                }
+                self.notify("Profile Created", &message);
+            }
+            Err(e) => self.profiles.set_error(e.to_string()),
+        }
+    }
+
+    fn stop_todotxt_sync(&mut self, reason: &str) {
+        self.todotxt_sync = None;
+        self.app_data.settings.todotxt_sync_path = None;
+        self.notify("todo.txt Sync Stopped", reason);
+    }
+
+    fn handle_undo_shortcuts(&mut self, ctx: &egui::Context) {
+        // Leave Ctrl+Z to text fields while one of them is being edited.
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let redo = egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+            egui::Key::Z,
+        );
+        let undo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+        if ctx.input_mut(|i| i.consume_shortcut(&redo)) {
+            self.history.redo(&mut self.app_data);
+        } else if ctx.input_mut(|i| i.consume_shortcut(&undo)) {
+            self.history.undo(&mut self.app_data);
+        }
+    }
+
+    fn notify(&mut self, title: &str, message: &str) {
         self.ui_manager.notification_title = title.to_string();
         self.ui_manager.notification_message = message.to_string();
         self.ui_manager.show_notification = true;
+    }
+
+    fn handle_session_switch(&mut self) {
+        timer::play_beep(&self.stream_handle);
+        let (title, message) = self.timer.get_session_switch_messages();
+        self.notify(title, message);
+
+        // Back in work mode means a break just ended, which completes a pomodoro.
+        if self.timer.timer_mode == TimerMode::Work {
+            self.app_data.wallet.record(points::transaction(
+                points::POINTS_PER_SESSION as i64,
+                "Completed a focus session",
+            ));
+        }
 
         if self.timer.timer_state == TimerState::Paused {
+            self.sync_folder_now();
             self.app_data.stats = self.timer.stats.clone();
-            if let Err(e) = app_data::save(&self.app_data) {
+            if let Err(e) = self.storage.save(&self.app_data) {
                 eprintln!("Failed to quick-save stats: {e}");
             }
         }
@@ -0,0 +1,145 @@
+use crate::app_data::{AppData, Cost, STATUS_DONE, Transaction};
+use crate::history::Command;
+use chrono::Local;
+
+pub const POINTS_PER_SESSION: u32 = 10;
+pub const POINTS_PER_TASK: u32 = 5;
+pub const POINTS_PER_DAILY_GOAL: u32 = 20;
+pub const POINTS_PER_WEEKLY_GOAL: u32 = 50;
+
+impl Cost {
+    pub fn points(self) -> u32 {
+        match self {
+            Cost::Points(points) => points,
+            Cost::Pomodoros(sessions) => sessions * POINTS_PER_SESSION,
+        }
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            Cost::Points(points) => format!("🪙 {points}"),
+            Cost::Pomodoros(sessions) => format!("🍅 {sessions}"),
+        }
+    }
+}
+
+pub fn transaction(amount: i64, reason: impl Into<String>) -> Transaction {
+    Transaction {
+        time: Local::now().naive_local(),
+        amount,
+        reason: reason.into(),
+    }
+}
+
+/// Extends a command that finishes (or reopens) tasks with the points earned
+/// (or taken back) for them, so undoing the change also undoes the award.
+pub fn award_for_tasks(command: Command, data: &AppData) -> Command {
+    let mut awards = Vec::new();
+    collect_task_awards(&command, data, &mut awards);
+    if awards.is_empty() {
+        return command;
+    }
+    let commands = std::iter::once(command)
+        .chain(
+            awards
+                .into_iter()
+                .map(|entry| Command::AddTransaction { entry }),
+        )
+        .collect();
+    Command::Batch(commands)
+}
+
+fn collect_task_awards(command: &Command, data: &AppData, awards: &mut Vec<Transaction>) {
+    match command {
+        Command::SetStatus { task, old, new, .. }
+            if (*old == STATUS_DONE) != (*new == STATUS_DONE) =>
+        {
+            let Some(todo) = data.tasks(task.list).get(task.index) else {
+                return;
+            };
+            let points = POINTS_PER_TASK as i64;
+            awards.push(if *new == STATUS_DONE {
+                transaction(points, format!("Finished \"{}\"", todo.text))
+            } else {
+                transaction(-points, format!("Reopened \"{}\"", todo.text))
+            });
+        }
+        Command::Batch(commands) => {
+            for command in commands {
+                collect_task_awards(command, data, awards);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_data::{STATUS_IN_PROGRESS, STATUS_TODO, TaskList, TaskRef, TodoItem};
+    use crate::history::History;
+    use chrono::NaiveDate;
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, 1).unwrap()
+    }
+
+    fn with_task() -> AppData {
+        let mut data = AppData::default();
+        data.todos_by_date
+            .insert(day(), vec![TodoItem::new("Read".to_owned(), None)]);
+        data
+    }
+
+    fn set_status(data: &AppData, new: u64) -> Command {
+        let task = TaskRef {
+            list: TaskList::Date(day()),
+            index: 0,
+        };
+        let todo = &data.tasks(task.list)[0];
+        Command::SetStatus {
+            task,
+            old: todo.status,
+            new,
+            old_completed_on: todo.completed_on,
+        }
+    }
+
+    #[test]
+    fn finishing_pays_and_reopening_takes_it_back() {
+        let mut data = with_task();
+        let mut history = History::default();
+
+        let finish = award_for_tasks(set_status(&data, STATUS_DONE), &data);
+        history.execute(finish, &mut data);
+        assert_eq!(data.wallet.balance, POINTS_PER_TASK as i64);
+        assert_eq!(data.wallet.ledger[0].reason, "Finished \"Read\"");
+
+        let reopen = award_for_tasks(set_status(&data, STATUS_TODO), &data);
+        history.execute(reopen, &mut data);
+        assert_eq!(data.wallet.balance, 0);
+        assert_eq!(data.wallet.ledger.len(), 2);
+
+        // Undoing both leaves no trace in the ledger.
+        history.undo(&mut data);
+        history.undo(&mut data);
+        assert_eq!(data.wallet.balance, 0);
+        assert!(data.wallet.ledger.is_empty());
+    }
+
+    #[test]
+    fn moves_between_open_columns_earn_nothing() {
+        let data = with_task();
+        let command = set_status(&data, STATUS_IN_PROGRESS);
+        assert!(matches!(
+            award_for_tasks(command, &data),
+            Command::SetStatus { .. }
+        ));
+    }
+
+    #[test]
+    fn pomodoro_prices_convert_to_points() {
+        assert_eq!(Cost::Pomodoros(3).points(), 3 * POINTS_PER_SESSION);
+        assert_eq!(Cost::Points(7).points(), 7);
+    }
+}
@@ -0,0 +1,240 @@
+//! Separate sets of data for people sharing one computer.
+//!
+//! The default profile keeps its files next to the executable, where they
+//! always were. Every other profile gets its own folder under `profiles/`,
+//! holding its data file, backup and sync state. Everything that goes
+//! through [`app_data::local_path`] follows the active profile.
+
+use crate::app_data;
+use crate::storage;
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+pub const DEFAULT_PROFILE: &str = "Default";
+const PROFILES_DIR: &str = "profiles";
+// Next to the executable: the profile opened last, preselected in the picker.
+const LAST_PROFILE_FILE: &str = "focushub_profile.txt";
+// In the profile's folder: locked by whichever process has the profile open.
+const LOCK_FILE: &str = "focushub.lock";
+
+// The profile whose files are in use; `None` for the default one.
+static ACTIVE: Mutex<Option<String>> = Mutex::new(None);
+// The lock file of the profile this process has claimed.
+static CLAIMED: Mutex<Option<File>> = Mutex::new(None);
+
+/// The name of the profile in use.
+pub fn active() -> String {
+    let active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    active.clone().unwrap_or_else(|| DEFAULT_PROFILE.to_owned())
+}
+
+/// Switches to `name` for this run only.
+pub fn select(name: &str) {
+    let profile = (name != DEFAULT_PROFILE).then(|| name.to_owned());
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = profile;
+}
+
+/// Claims the active profile for this process, letting go of any profile it
+/// claimed before. Returns `false` if another process has it, such as the
+/// window while a command runs. The claim ends with the process, however it
+/// ends, so a crash doesn't leave the profile claimed.
+pub fn claim_active() -> Result<bool, std::io::Error> {
+    let mut claimed = CLAIMED.lock().unwrap_or_else(|e| e.into_inner());
+    *claimed = None;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(app_data::local_path(LOCK_FILE)?)?;
+    match file.try_lock() {
+        Ok(()) => {
+            *claimed = Some(file);
+            Ok(true)
+        }
+        Err(fs::TryLockError::WouldBlock) => Ok(false),
+        Err(fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+// Whether another process has `name` claimed, such as a second window.
+fn in_use(name: &str) -> Result<bool, std::io::Error> {
+    let path = dir(name)?.join(LOCK_FILE);
+    if !fs::exists(&path)? {
+        return Ok(false);
+    }
+    match File::open(path)?.try_lock() {
+        Ok(()) => Ok(false),
+        Err(fs::TryLockError::WouldBlock) => Ok(true),
+        Err(fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Switches to a new, empty profile for the length of a test. Tests that
+/// touch profile files share the active profile, so they take turns.
+#[cfg(test)]
+pub fn test_profile(name: &str) -> std::sync::MutexGuard<'static, ()> {
+    static TURN: Mutex<()> = Mutex::new(());
+    let turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = dir(name).unwrap();
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    select(name);
+    turn
+}
+
+/// Switches to `name` and remembers it for the next start.
+pub fn set_active(name: &str) {
+    select(name);
+    if let Ok(dir) = app_data::exe_dir() {
+        fs::write(dir.join(LAST_PROFILE_FILE), name).ok();
+    }
+}
+
+/// The profile opened last, if it still exists.
+pub fn last_used() -> Option<String> {
+    let name = fs::read_to_string(app_data::exe_dir().ok()?.join(LAST_PROFILE_FILE)).ok()?;
+    let name = name.trim();
+    exists(name).then(|| name.to_owned())
+}
+
+/// The folder holding `name`'s files.
+pub fn dir(name: &str) -> Result<PathBuf, std::io::Error> {
+    let exe_dir = app_data::exe_dir()?;
+    if name == DEFAULT_PROFILE {
+        Ok(exe_dir)
+    } else {
+        Ok(exe_dir.join(PROFILES_DIR).join(name))
+    }
+}
+
+/// Every profile, the default one first.
+pub fn list() -> Vec<String> {
+    let mut names = Vec::new();
+    if let Ok(entries) = dir(DEFAULT_PROFILE).and_then(|d| fs::read_dir(d.join(PROFILES_DIR))) {
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    names.sort_by_key(|name| name.to_lowercase());
+    names.insert(0, DEFAULT_PROFILE.to_owned());
+    names
+}
+
+pub fn exists(name: &str) -> bool {
+    list().iter().any(|n| n == name)
+}
+
+// Names become folder names, so they must be valid ones on every system,
+// and must not differ from an existing one by case alone.
+fn check_new_name(name: &str) -> Result<(), Box<dyn Error>> {
+    if name.is_empty() || name.chars().count() > 40 {
+        return Err("A profile name needs 1 to 40 characters.".into());
+    }
+    if name.starts_with('.') || name.ends_with(['.', ' ']) {
+        return Err("A profile name can't start with a dot or end with a dot or space.".into());
+    }
+    if name
+        .chars()
+        .any(|c| c.is_control() || r#"/\:*?"<>|"#.contains(c))
+    {
+        return Err("A profile name can't contain / \\ : * ? \" < > |.".into());
+    }
+    if is_reserved(name) {
+        return Err(format!("{name} is a reserved name on Windows.").into());
+    }
+    if list().iter().any(|n| n.eq_ignore_ascii_case(name)) {
+        return Err(format!("There is already a profile called {name}.").into());
+    }
+    Ok(())
+}
+
+// Windows reserves these device names, with or without an extension.
+fn is_reserved(name: &str) -> bool {
+    let stem = name
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    let numbered = |prefix: &str| {
+        stem.strip_prefix(prefix)
+            .is_some_and(|n| n.len() == 1 && matches!(n.as_bytes()[0], b'1'..=b'9'))
+    };
+    matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL") || numbered("COM") || numbered("LPT")
+}
+
+/// Makes a new, empty profile.
+pub fn create(name: &str) -> Result<(), Box<dyn Error>> {
+    check_new_name(name)?;
+    fs::create_dir_all(dir(name)?)?;
+    Ok(())
+}
+
+/// Makes a new profile starting from `data_file`, the contents of another
+/// profile's data file.
+pub fn duplicate(name: &str, data_file: &str) -> Result<(), Box<dyn Error>> {
+    create(name)?;
+    fs::write(dir(name)?.join(storage::json::DATA_FILE), data_file)?;
+    Ok(())
+}
+
+/// Renames a profile other than the default and active ones, unless another
+/// window has it open.
+pub fn rename(name: &str, new_name: &str) -> Result<(), Box<dyn Error>> {
+    if name == DEFAULT_PROFILE || name == active() {
+        return Err("The default and active profiles can't be renamed.".into());
+    }
+    if in_use(name)? {
+        return Err(format!("{name} is open in another window.").into());
+    }
+    check_new_name(new_name)?;
+    fs::rename(dir(name)?, dir(new_name)?)?;
+    Ok(())
+}
+
+/// Deletes a profile other than the default and active ones, with all its
+/// data, unless another window has it open.
+pub fn delete(name: &str) -> Result<(), Box<dyn Error>> {
+    if name == DEFAULT_PROFILE || name == active() {
+        return Err("The default and active profiles can't be deleted.".into());
+    }
+    if in_use(name)? {
+        return Err(format!("{name} is open in another window.").into());
+    }
+    fs::remove_dir_all(dir(name)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_device_names_are_refused() {
+        for name in ["con", "Aux.txt", "COM1", "lpt9.backup", "Notes.", "Notes "] {
+            assert!(check_new_name(name).is_err(), "{name}");
+        }
+        for name in ["Console", "COM10", "LPT", "com0"] {
+            assert!(!is_reserved(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn a_profile_open_elsewhere_is_kept() {
+        let _turn = test_profile("test-profiles-open");
+        let other = "test-profiles-open-elsewhere";
+        fs::remove_dir_all(dir(other).unwrap()).ok();
+        create(other).unwrap();
+        let lock = File::create(dir(other).unwrap().join(LOCK_FILE)).unwrap();
+        lock.lock().unwrap();
+        assert!(rename(other, "test-profiles-renamed").is_err());
+        assert!(delete(other).is_err());
+
+        drop(lock);
+        delete(other).unwrap();
+        assert!(!exists(other));
+    }
+}
@@ -0,0 +1,377 @@
+use crate::app_data::{AppData, Stats};
+use crate::goals::week_start;
+use crate::streaks::StreakSummary;
+use crate::ui::format_duration;
+use chrono::{Datelike, Days, Months, NaiveDate};
+use std::collections::HashMap;
+
+// How many projects and tags the review lists.
+const TOP_COUNT: usize = 5;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ReviewPeriod {
+    Week,
+    Month,
+}
+
+impl ReviewPeriod {
+    /// The first and last day of the period containing `date`.
+    pub fn bounds(self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            ReviewPeriod::Week => {
+                let monday = week_start(date);
+                (monday, monday + Days::new(6))
+            }
+            ReviewPeriod::Month => {
+                let first = date.with_day(1).unwrap();
+                (first, first + Months::new(1) - Days::new(1))
+            }
+        }
+    }
+
+    pub fn previous(self, date: NaiveDate) -> NaiveDate {
+        let (start, _) = self.bounds(date);
+        start - Days::new(1)
+    }
+
+    pub fn next(self, date: NaiveDate) -> NaiveDate {
+        let (_, end) = self.bounds(date);
+        end + Days::new(1)
+    }
+
+    pub fn title(self, date: NaiveDate) -> String {
+        let (start, end) = self.bounds(date);
+        match self {
+            ReviewPeriod::Week => format!(
+                "Week of {} – {}",
+                start.format("%B %-d"),
+                end.format("%B %-d, %Y")
+            ),
+            ReviewPeriod::Month => start.format("%B %Y").to_string(),
+        }
+    }
+}
+
+/// A summary of one week or month, compared with the period before it.
+pub struct Review {
+    pub title: String,
+    pub focus_seconds: u64,
+    pub previous_focus_seconds: u64,
+    pub sessions: u32,
+    pub previous_sessions: u32,
+    /// Tasks scheduled on days in the period.
+    pub tasks_added: usize,
+    pub tasks_completed: usize,
+    /// Scheduled tasks that were left unfinished.
+    pub tasks_rolled_over: usize,
+    /// Focus time per project name, most first.
+    pub top_projects: Vec<(String, u64)>,
+    /// Tasks per tag, most first.
+    pub top_tags: Vec<(String, usize)>,
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+fn focus_between(stats: &Stats, start: NaiveDate, end: NaiveDate) -> u64 {
+    start
+        .iter_days()
+        .take_while(|d| *d <= end)
+        .filter_map(|d| stats.daily_study_seconds.get(&d))
+        .sum()
+}
+
+fn sessions_between(stats: &Stats, start: NaiveDate, end: NaiveDate) -> u32 {
+    start
+        .iter_days()
+        .take_while(|d| *d <= end)
+        .filter_map(|d| stats.daily_sessions.get(&d))
+        .sum()
+}
+
+// Ties go by name, so the list doesn't change from one frame to the next.
+fn top<K: Ord>(counts: HashMap<K, u64>) -> Vec<(K, u64)> {
+    let mut sorted: Vec<_> = counts.into_iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sorted.truncate(TOP_COUNT);
+    sorted
+}
+
+pub fn build(
+    data: &AppData,
+    stats: &Stats,
+    streak: &StreakSummary,
+    period: ReviewPeriod,
+    date: NaiveDate,
+) -> Review {
+    let (start, end) = period.bounds(date);
+    let (previous_start, previous_end) = period.bounds(period.previous(date));
+
+    let mut tasks_added = 0;
+    let mut tasks_completed = 0;
+    let mut tags: HashMap<String, u64> = HashMap::new();
+    for day in start.iter_days().take_while(|d| *d <= end) {
+        for todo in data.todos_by_date.get(&day).into_iter().flatten() {
+            tasks_added += 1;
+            if todo.is_done() {
+                tasks_completed += 1;
+            }
+            for tag in todo.tags() {
+                *tags.entry(tag.to_lowercase()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut projects: HashMap<String, u64> = HashMap::new();
+    for session in &stats.sessions {
+        let day = session.start.date();
+        if day < start || day > end {
+            continue;
+        }
+        if let Some(project) = session.project.and_then(|id| data.project(id)) {
+            *projects.entry(project.name.clone()).or_insert(0) += session.seconds;
+        }
+    }
+
+    Review {
+        title: period.title(date),
+        focus_seconds: focus_between(stats, start, end),
+        previous_focus_seconds: focus_between(stats, previous_start, previous_end),
+        sessions: sessions_between(stats, start, end),
+        previous_sessions: sessions_between(stats, previous_start, previous_end),
+        tasks_added,
+        tasks_completed,
+        tasks_rolled_over: tasks_added - tasks_completed,
+        top_projects: top(projects),
+        top_tags: top(tags)
+            .into_iter()
+            .map(|(tag, count)| (tag, count as usize))
+            .collect(),
+        current_streak: streak.current,
+        longest_streak: streak.longest,
+    }
+}
+
+impl Review {
+    /// The change in focus time against the previous period, e.g. "+1h 05m".
+    pub fn focus_change(&self) -> String {
+        let sign = if self.focus_seconds >= self.previous_focus_seconds {
+            "+"
+        } else {
+            "−"
+        };
+        let diff = self.focus_seconds.abs_diff(self.previous_focus_seconds);
+        format!("{sign}{}", format_duration(diff))
+    }
+
+    // Label/value pairs shared by every rendering of the review.
+    pub fn summary_rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "Focus time",
+                format!(
+                    "{} ({} vs previous)",
+                    format_duration(self.focus_seconds),
+                    self.focus_change()
+                ),
+            ),
+            (
+                "Sessions",
+                format!("{} (previous: {})", self.sessions, self.previous_sessions),
+            ),
+            ("Tasks added", self.tasks_added.to_string()),
+            ("Tasks completed", self.tasks_completed.to_string()),
+            ("Tasks rolled over", self.tasks_rolled_over.to_string()),
+            (
+                "Current streak",
+                format!(
+                    "{} days (longest {})",
+                    self.current_streak, self.longest_streak
+                ),
+            ),
+        ]
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Review: {}\n\n", self.title);
+        for (label, value) in self.summary_rows() {
+            out += &format!("- **{label}:** {value}\n");
+        }
+        if !self.top_projects.is_empty() {
+            out += "\n## Top Projects\n\n";
+            for (name, seconds) in &self.top_projects {
+                out += &format!("- {name}: {}\n", format_duration(*seconds));
+            }
+        }
+        if !self.top_tags.is_empty() {
+            out += "\n## Top Tags\n\n";
+            for (tag, count) in &self.top_tags {
+                out += &format!("- #{tag}: {count} tasks\n");
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Review: {0}</title></head>\n<body>\n<h1>Review: {0}</h1>\n<ul>\n",
+            escape_html(&self.title)
+        );
+        for (label, value) in self.summary_rows() {
+            out += &format!(
+                "<li><strong>{label}:</strong> {}</li>\n",
+                escape_html(&value)
+            );
+        }
+        out += "</ul>\n";
+        if !self.top_projects.is_empty() {
+            out += "<h2>Top Projects</h2>\n<ul>\n";
+            for (name, seconds) in &self.top_projects {
+                out += &format!(
+                    "<li>{}: {}</li>\n",
+                    escape_html(name),
+                    format_duration(*seconds)
+                );
+            }
+            out += "</ul>\n";
+        }
+        if !self.top_tags.is_empty() {
+            out += "<h2>Top Tags</h2>\n<ul>\n";
+            for (tag, count) in &self.top_tags {
+                out += &format!("<li>#{}: {count} tasks</li>\n", escape_html(tag));
+            }
+            out += "</ul>\n";
+        }
+        out += "</body>\n</html>\n";
+        out
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ties_are_listed_by_name() {
+        let counts = HashMap::from([("b", 2), ("c", 2), ("a", 1), ("d", 5), ("e", 2), ("f", 2)]);
+        assert_eq!(
+            top(counts),
+            [("d", 5), ("b", 2), ("c", 2), ("e", 2), ("f", 2)]
+        );
+    }
+
+    fn day(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, m, d).unwrap()
+    }
+
+    #[test]
+    fn periods_cover_whole_weeks_and_months() {
+        // 2024-02-29 is a Thursday in a leap year.
+        let date = day(2, 29);
+        assert_eq!(ReviewPeriod::Week.bounds(date), (day(2, 26), day(3, 3)));
+        assert_eq!(ReviewPeriod::Month.bounds(date), (day(2, 1), day(2, 29)));
+        assert_eq!(ReviewPeriod::Week.previous(date), day(2, 25));
+        assert_eq!(ReviewPeriod::Month.next(date), day(3, 1));
+        let december = ReviewPeriod::Month.bounds(day(12, 10));
+        assert_eq!(december, (day(12, 1), day(12, 31)));
+        assert_eq!(
+            ReviewPeriod::Week.title(date),
+            "Week of February 26 – March 3, 2024"
+        );
+    }
+
+    #[test]
+    fn a_review_counts_only_its_own_period() {
+        use crate::app_data::{FocusSession, Project, STATUS_DONE, TodoItem};
+
+        let mut data = AppData::default();
+        let project = Project {
+            id: 7,
+            name: "Thesis".to_owned(),
+            tasks: Vec::new(),
+        };
+        data.projects.push(project);
+        let mut done = TodoItem::new("Read #Reading".to_owned(), None);
+        done.status = STATUS_DONE;
+        let open = TodoItem::new("Write #writing #reading".to_owned(), None);
+        data.todos_by_date.insert(day(3, 4), vec![done, open]);
+        data.todos_by_date.insert(
+            day(3, 11),
+            vec![TodoItem::new("Later #later".to_owned(), None)],
+        );
+
+        let session = |d, seconds| FocusSession {
+            start: day(3, d).and_hms_opt(9, 0, 0).unwrap(),
+            seconds,
+            task: None,
+            project: Some(7),
+        };
+        let stats = Stats {
+            daily_study_seconds: [(day(2, 28), 600), (day(3, 4), 3600), (day(3, 10), 1800)].into(),
+            daily_sessions: [(day(3, 4), 2)].into(),
+            sessions: vec![session(4, 3600), session(10, 1800), session(11, 900)],
+            ..Stats::default()
+        };
+        let streak = StreakSummary {
+            current: 2,
+            longest: 5,
+            freezes: 0,
+        };
+
+        // The week of Monday 2024-03-04.
+        let review = build(&data, &stats, &streak, ReviewPeriod::Week, day(3, 6));
+        assert_eq!(review.focus_seconds, 5400);
+        assert_eq!(review.previous_focus_seconds, 600);
+        assert_eq!(review.sessions, 2);
+        assert_eq!(
+            (
+                review.tasks_added,
+                review.tasks_completed,
+                review.tasks_rolled_over
+            ),
+            (2, 1, 1)
+        );
+        assert_eq!(review.top_projects, [("Thesis".to_owned(), 5400)]);
+        assert_eq!(
+            review.top_tags,
+            [("reading".to_owned(), 2), ("writing".to_owned(), 1)]
+        );
+        assert_eq!(review.focus_change(), "+1h 20m");
+    }
+
+    #[test]
+    fn html_reviews_escape_names() {
+        let review = Review {
+            title: "March 2024".to_owned(),
+            focus_seconds: 0,
+            previous_focus_seconds: 60,
+            sessions: 0,
+            previous_sessions: 1,
+            tasks_added: 0,
+            tasks_completed: 0,
+            tasks_rolled_over: 0,
+            top_projects: vec![("R&D <draft>".to_owned(), 3600)],
+            top_tags: Vec::new(),
+            current_streak: 0,
+            longest_streak: 0,
+        };
+        assert_eq!(review.focus_change(), "−0h 01m");
+        assert!(
+            review
+                .to_html()
+                .contains("<li>R&amp;D &lt;draft&gt;: 1h 00m</li>")
+        );
+        assert!(
+            review
+                .to_markdown()
+                .contains("\n## Top Projects\n\n- R&D <draft>: 1h 00m\n")
+        );
+        assert!(!review.to_markdown().contains("Top Tags"));
+    }
+}
@@ -0,0 +1,159 @@
+//! The original storage: the whole of [`AppData`] as one pretty-printed JSON
+//! file, optionally sealed with a passphrase.
+
+use super::{DayTotals, Storage, StorageKind};
+use crate::app_data::{self, AppData, FocusSession};
+use crate::crypto::{self, Key};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+pub const DATA_FILE: &str = "focushub_data.json";
+const BACKUP_FILE: &str = "focushub_data.json.bak";
+
+fn get_data_path() -> Result<PathBuf, std::io::Error> {
+    app_data::local_path(DATA_FILE)
+}
+
+fn get_backup_path() -> Result<PathBuf, std::io::Error> {
+    app_data::local_path(BACKUP_FILE)
+}
+
+/// The data file's contents for `data`, sealed with `key` if one is given.
+pub fn to_file_contents(data: &AppData, key: Option<&Key>) -> Result<String, Box<dyn Error>> {
+    let json = serde_json::to_string_pretty(data)?;
+    match key {
+        Some(key) => key.seal(json.as_bytes()),
+        None => Ok(json),
+    }
+}
+
+fn parse(json: &str) -> Result<AppData, Box<dyn Error>> {
+    let mut data: AppData = serde_json::from_str(json)?;
+    data.migrate_tasks();
+    Ok(data)
+}
+
+// Re-seals the backup after the passphrase changed or encryption was turned
+// on or off, so it is never left readable with an old passphrase or none.
+// A backup that can't be opened with `old` is deleted instead.
+fn reseal_backup(old: Option<&Key>, new: Option<&Key>) -> Result<(), Box<dyn Error>> {
+    let path = get_backup_path()?;
+    let Ok(text) = fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    let json = match (crypto::is_sealed(&text), old) {
+        (true, Some(old)) => old.unseal(&text).ok(),
+        (true, None) => None,
+        (false, _) => Some(text.into_bytes()),
+    };
+    match (json, new) {
+        (Some(json), Some(new)) => fs::write(&path, new.seal(&json)?)?,
+        (Some(json), None) => fs::write(&path, json)?,
+        (None, _) => fs::remove_file(&path)?,
+    }
+    Ok(())
+}
+
+/// `focushub_data.json` in the active profile's folder.
+#[derive(Default)]
+pub struct JsonStorage {
+    /// Set once an encrypted file is unlocked, or encryption is turned on.
+    key: Option<Key>,
+}
+
+impl JsonStorage {
+    pub fn path() -> Result<PathBuf, std::io::Error> {
+        get_data_path()
+    }
+}
+
+impl Storage for JsonStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Json
+    }
+
+    fn is_locked(&self) -> bool {
+        self.key.is_none()
+            && get_data_path()
+                .and_then(fs::read_to_string)
+                .is_ok_and(|text| crypto::is_sealed(&text))
+    }
+
+    fn unlock(&mut self, passphrase: &str) -> Result<AppData, Box<dyn Error>> {
+        let text = fs::read_to_string(get_data_path()?)?;
+        let (json, key) = crypto::open(&text, passphrase)?;
+        let data = parse(std::str::from_utf8(&json)?)?;
+        self.key = Some(key);
+        Ok(data)
+    }
+
+    fn load(&mut self) -> Result<AppData, Box<dyn Error>> {
+        let text = fs::read_to_string(get_data_path()?)?;
+        match &self.key {
+            Some(key) => parse(std::str::from_utf8(&key.unseal(&text)?)?),
+            None if crypto::is_sealed(&text) => Err("the data file is locked".into()),
+            None => parse(&text),
+        }
+    }
+
+    /// Writes the whole file, keeping the one it replaces as a backup.
+    fn save(&mut self, data: &AppData) -> Result<(), Box<dyn Error>> {
+        let path = get_data_path()?;
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, to_file_contents(data, self.key.as_ref())?)?;
+        if path.exists() {
+            fs::rename(&path, get_backup_path()?)?;
+        }
+        fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    fn key(&self) -> Option<&Key> {
+        self.key.as_ref()
+    }
+
+    fn set_key(&mut self, key: Option<Key>, data: &AppData) -> Result<(), Box<dyn Error>> {
+        let old = std::mem::replace(&mut self.key, key);
+        if let Err(e) = self.save(data) {
+            self.key = old;
+            return Err(format!("Could not save: {e}").into());
+        }
+        // The data file needs the new key from here on, whatever happens to
+        // the backup, so a backup that can't follow is deleted.
+        if reseal_backup(old.as_ref(), self.key.as_ref()).is_err() {
+            let path = get_backup_path()?;
+            if path.exists() {
+                fs::remove_file(&path).map_err(|e| {
+                    format!(
+                        "Your data was saved, but its backup could not be updated or deleted: {e}"
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    // A single file can only be read whole, so these load everything.
+    fn daily_totals(
+        &mut self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<BTreeMap<NaiveDate, DayTotals>, Box<dyn Error>> {
+        Ok(DayTotals::from_stats(&self.load()?.stats, from, to))
+    }
+
+    fn sessions_between(
+        &mut self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<FocusSession>, Box<dyn Error>> {
+        let sessions = self.load()?.stats.sessions;
+        Ok(sessions
+            .into_iter()
+            .filter(|s| (from..=to).contains(&s.start.date()))
+            .collect())
+    }
+}
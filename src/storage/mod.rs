@@ -0,0 +1,298 @@
+//! Where [`AppData`] is kept between runs.
+//!
+//! The JSON file is simple and easy to look into; the SQLite database only
+//! writes what changed and can answer questions about a stretch of history
+//! without reading all of it. Each profile uses whichever of the two it has,
+//! and [`migrate`] moves data from one to the other.
+
+pub mod json;
+pub mod sqlite;
+
+use crate::app_data::{AppData, FocusSession, Stats};
+use crate::crypto::Key;
+use chrono::NaiveDate;
+use json::JsonStorage;
+use sqlite::SqliteStorage;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageKind {
+    Json,
+    Sqlite,
+}
+
+impl StorageKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            StorageKind::Json => "JSON file",
+            StorageKind::Sqlite => "SQLite database",
+        }
+    }
+}
+
+/// Focus time and completed sessions on one day.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DayTotals {
+    pub study_seconds: u64,
+    pub sessions: u32,
+}
+
+impl DayTotals {
+    /// The totals for every day from `from` to `to` that has any, worked out
+    /// from stats already in memory.
+    pub fn from_stats(stats: &Stats, from: NaiveDate, to: NaiveDate) -> BTreeMap<NaiveDate, Self> {
+        let mut days = BTreeMap::<NaiveDate, Self>::new();
+        let range = from..=to;
+        for (date, seconds) in &stats.daily_study_seconds {
+            if range.contains(date) {
+                days.entry(*date).or_default().study_seconds = *seconds;
+            }
+        }
+        for (date, sessions) in &stats.daily_sessions {
+            if range.contains(date) {
+                days.entry(*date).or_default().sessions = *sessions;
+            }
+        }
+        days
+    }
+}
+
+/// Reads and writes one profile's [`AppData`].
+pub trait Storage {
+    fn kind(&self) -> StorageKind;
+
+    /// Whether the data can only be read through [`Storage::unlock`].
+    fn is_locked(&self) -> bool {
+        false
+    }
+
+    /// Reads encrypted data with `passphrase`, which then seals later saves too.
+    fn unlock(&mut self, _passphrase: &str) -> Result<AppData, Box<dyn Error>> {
+        Err("this storage isn't encrypted".into())
+    }
+
+    /// Reads everything.
+    fn load(&mut self) -> Result<AppData, Box<dyn Error>>;
+
+    fn save(&mut self, data: &AppData) -> Result<(), Box<dyn Error>>;
+
+    /// The key the data is sealed with, if it is encrypted.
+    fn key(&self) -> Option<&Key> {
+        None
+    }
+
+    /// Turns encryption on, changes the key, or turns it off with `None`,
+    /// saving `data` with the new setting straight away. The old setting is
+    /// kept only if that save fails.
+    fn set_key(&mut self, _key: Option<Key>, _data: &AppData) -> Result<(), Box<dyn Error>> {
+        Err("only the JSON file can be encrypted; switch to it under File → Storage first".into())
+    }
+
+    /// Focus time and sessions for each day from `from` to `to` that has any.
+    fn daily_totals(
+        &mut self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<BTreeMap<NaiveDate, DayTotals>, Box<dyn Error>>;
+
+    /// The focus sessions started from `from` to `to`, oldest first.
+    fn sessions_between(
+        &mut self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<FocusSession>, Box<dyn Error>>;
+}
+
+/// Opens the active profile's storage: its database if it has one, else its
+/// JSON file.
+pub fn open() -> Result<Box<dyn Storage>, Box<dyn Error>> {
+    if SqliteStorage::path()?.exists() {
+        Ok(Box::new(SqliteStorage::open()?))
+    } else {
+        Ok(Box::new(JsonStorage::default()))
+    }
+}
+
+// Wide enough for any history, and still sorting correctly as text.
+fn all_time() -> (NaiveDate, NaiveDate) {
+    (
+        NaiveDate::from_ymd_opt(1, 1, 1).unwrap_or_default(),
+        NaiveDate::from_ymd_opt(9999, 12, 31).unwrap_or_default(),
+    )
+}
+
+fn sorted(mut sessions: Vec<FocusSession>) -> Vec<FocusSession> {
+    sessions.sort_by_key(|s| (s.start, s.seconds, s.task, s.project));
+    sessions
+}
+
+/// Deletes files set aside by a migration that was interrupted before it
+/// could remove them.
+pub fn remove_set_aside() -> Result<(), std::io::Error> {
+    for path in [JsonStorage::path()?, SqliteStorage::path()?] {
+        let mut set_aside = path.into_os_string();
+        set_aside.push(".migrated");
+        if fs::exists(&set_aside)? {
+            fs::remove_file(set_aside)?;
+        }
+    }
+    Ok(())
+}
+
+/// Storage that stayed as it was, to keep using, and why it wasn't migrated.
+pub type Unmigrated = (Box<dyn Storage>, Box<dyn Error>);
+
+// Opens storage of `kind` again after `migrate` closed it.
+fn reopen(kind: StorageKind) -> Result<Box<dyn Storage>, Box<dyn Error>> {
+    Ok(match kind {
+        StorageKind::Json => Box::new(JsonStorage::default()),
+        StorageKind::Sqlite => Box::new(SqliteStorage::reopen()?),
+    })
+}
+
+/// Copies `data` from `from` into new storage of the other kind and checks
+/// that its history reads back the same. Only then is `from` closed and its
+/// file removed, so the profile opens the new one. If anything fails, nothing
+/// changes and the storage to keep using comes back with the error.
+pub fn migrate(from: Box<dyn Storage>, data: &AppData) -> Result<Box<dyn Storage>, Unmigrated> {
+    if from.key().is_some() {
+        let e = "turn off encryption first: the SQLite database can't be encrypted";
+        return Err((from, e.into()));
+    }
+    let kind = from.kind();
+    let paths = JsonStorage::path().and_then(|json| Ok((json, SqliteStorage::path()?)));
+    let ((old_path, new_path), mut to) = match (kind, paths) {
+        (_, Err(e)) => return Err((from, e.into())),
+        (StorageKind::Json, Ok((json, sqlite))) => match SqliteStorage::open() {
+            Ok(to) => ((json, sqlite), Box::new(to) as Box<dyn Storage>),
+            Err(e) => return Err((from, e)),
+        },
+        (StorageKind::Sqlite, Ok((json, sqlite))) => (
+            (sqlite, json),
+            Box::new(JsonStorage::default()) as Box<dyn Storage>,
+        ),
+    };
+
+    let (first, last) = all_time();
+    let copied = to.save(data).and_then(|()| {
+        let same = to.daily_totals(first, last)? == DayTotals::from_stats(&data.stats, first, last)
+            && sorted(to.sessions_between(first, last)?) == sorted(data.stats.sessions.clone());
+        if same {
+            Ok(())
+        } else {
+            Err("the new storage didn't read back the same history".into())
+        }
+    });
+    if let Err(e) = copied {
+        drop(to);
+        fs::remove_file(&new_path).ok();
+        return Err((from, format!("{e}, so nothing changed").into()));
+    }
+
+    // An open database can't be renamed on Windows.
+    drop(from);
+    let mut set_aside = old_path.clone().into_os_string();
+    set_aside.push(".migrated");
+    if old_path.exists()
+        && let Err(e) = fs::rename(&old_path, &set_aside)
+    {
+        return match reopen(kind) {
+            Ok(from) => {
+                drop(to);
+                fs::remove_file(&new_path).ok();
+                Err((from, format!("{e}, so nothing changed").into()))
+            }
+            // Then the copy is the only storage left to save to.
+            Err(reopen_error) => {
+                let e = format!("{e}, and the old storage didn't open again: {reopen_error}");
+                Err((to, e.into()))
+            }
+        };
+    }
+    // The new storage read everything back, so the plaintext copy can go.
+    fs::remove_file(&set_aside).ok();
+    Ok(to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_data::TodoItem;
+    use crate::profiles;
+
+    fn history() -> AppData {
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let mut data = AppData::default();
+        data.todos_by_date
+            .insert(day, vec![TodoItem::new("Read".to_owned(), None)]);
+        for hour in [9, 10, 11] {
+            data.stats.sessions.push(FocusSession {
+                start: day.and_hms_opt(hour, 0, 0).unwrap(),
+                seconds: 1500,
+                task: None,
+                project: None,
+            });
+        }
+        data.stats.daily_study_seconds.insert(day, 4500);
+        data.stats.daily_sessions.insert(day, 3);
+        data
+    }
+
+    // Whether any file in the profile's folder was set aside by a migration.
+    fn set_aside_files() -> usize {
+        let folder = profiles::dir(&profiles::active()).unwrap();
+        fs::read_dir(folder)
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".migrated"))
+            .count()
+    }
+
+    #[test]
+    fn migrating_there_and_back_keeps_everything() {
+        let _turn = profiles::test_profile("test-migrate");
+        let data = history();
+        let mut json = open().unwrap();
+        assert_eq!(json.kind(), StorageKind::Json);
+        json.save(&data).unwrap();
+
+        let sqlite = migrate(json, &data).map_err(|(_, e)| e).unwrap();
+        assert_eq!(sqlite.kind(), StorageKind::Sqlite);
+        assert!(!JsonStorage::path().unwrap().exists());
+        assert_eq!(set_aside_files(), 0);
+        // As the app does on its next start.
+        drop(sqlite);
+        let mut reopened = open().unwrap();
+        assert_eq!(reopened.kind(), StorageKind::Sqlite);
+        let loaded = reopened.load().unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&data).unwrap()
+        );
+
+        let mut json = migrate(reopened, &loaded).map_err(|(_, e)| e).unwrap();
+        assert!(!SqliteStorage::path().unwrap().exists());
+        assert_eq!(set_aside_files(), 0);
+        assert_eq!(open().unwrap().kind(), StorageKind::Json);
+        assert_eq!(
+            serde_json::to_value(json.load().unwrap()).unwrap(),
+            serde_json::to_value(&data).unwrap()
+        );
+    }
+
+    #[test]
+    fn encrypted_data_is_not_migrated() {
+        let _turn = profiles::test_profile("test-migrate-encrypted");
+        let data = history();
+        let mut json = open().unwrap();
+        json.set_key(Some(Key::new("correct horse").unwrap()), &data)
+            .unwrap();
+        let Err((kept, _)) = migrate(json, &data) else {
+            panic!("encrypted data was migrated");
+        };
+        assert!(kept.key().is_some());
+        assert!(!SqliteStorage::path().unwrap().exists());
+    }
+}
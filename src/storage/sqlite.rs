@@ -0,0 +1,427 @@
+//! [`AppData`] in an SQLite database, for long histories.
+//!
+//! Tasks, focus sessions and per-day totals each get a table keyed by date,
+//! and a save only touches the rows that changed since the last load or
+//! save. Everything else is small and is kept in `meta` as one JSON value
+//! per field.
+
+use super::{DayTotals, Storage, StorageKind};
+use crate::app_data::{self, AppData, FocusSession};
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{Connection, params};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::PathBuf;
+
+pub const DATABASE_FILE: &str = "focushub_data.sqlite3";
+const SCHEMA_VERSION: i32 = 1;
+// Sorts as text in time order, so ranges can be queried on the index.
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    name TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS todos (
+    date TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    item TEXT NOT NULL,
+    PRIMARY KEY (date, position)
+);
+CREATE TABLE IF NOT EXISTS sessions (
+    position INTEGER PRIMARY KEY,
+    start TEXT NOT NULL,
+    seconds INTEGER NOT NULL,
+    task INTEGER,
+    project INTEGER
+);
+CREATE INDEX IF NOT EXISTS sessions_by_start ON sessions (start);
+CREATE TABLE IF NOT EXISTS days (
+    date TEXT PRIMARY KEY,
+    study_seconds INTEGER,
+    sessions INTEGER
+);
+";
+
+// The fields of `Stats` that have tables of their own.
+const STATS_IN_TABLES: [&str; 3] = ["sessions", "daily_study_seconds", "daily_sessions"];
+
+// What the database holds, in the shape of its rows.
+#[derive(Default)]
+struct Rows {
+    todos: HashMap<(NaiveDate, usize), String>,
+    sessions: Vec<FocusSession>,
+    // Kept apart, since a day can be in one of the two maps and not the other.
+    days: HashMap<NaiveDate, (Option<u64>, Option<u32>)>,
+    meta: HashMap<String, String>,
+}
+
+impl Rows {
+    fn from_data(data: &AppData) -> Result<Self, Box<dyn Error>> {
+        let mut todos = HashMap::new();
+        for (date, items) in &data.todos_by_date {
+            for (position, item) in items.iter().enumerate() {
+                todos.insert((*date, position), serde_json::to_string(item)?);
+            }
+        }
+
+        let mut days = HashMap::<_, (Option<u64>, Option<u32>)>::new();
+        for (date, seconds) in &data.stats.daily_study_seconds {
+            days.entry(*date).or_default().0 = Some(*seconds);
+        }
+        for (date, sessions) in &data.stats.daily_sessions {
+            days.entry(*date).or_default().1 = Some(*sessions);
+        }
+
+        let mut meta = HashMap::new();
+        if let serde_json::Value::Object(mut fields) = serde_json::to_value(data)? {
+            fields.remove("todos_by_date");
+            if let Some(serde_json::Value::Object(stats)) = fields.get_mut("stats") {
+                for name in STATS_IN_TABLES {
+                    stats.remove(name);
+                }
+            }
+            for (name, value) in fields {
+                meta.insert(name, value.to_string());
+            }
+        }
+
+        Ok(Self {
+            todos,
+            sessions: data.stats.sessions.clone(),
+            days,
+            meta,
+        })
+    }
+}
+
+/// `focushub_data.sqlite3` in the active profile's folder.
+pub struct SqliteStorage {
+    connection: Connection,
+    written: Rows,
+}
+
+impl SqliteStorage {
+    pub fn path() -> Result<PathBuf, std::io::Error> {
+        app_data::local_path(DATABASE_FILE)
+    }
+
+    /// Opens the database, creating it if it doesn't exist yet.
+    pub fn open() -> Result<Self, Box<dyn Error>> {
+        let connection = Connection::open(Self::path()?)?;
+        let version: i32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err("the database was written by a newer version of Focus Hub".into());
+        }
+        connection.execute_batch(SCHEMA)?;
+        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(Self {
+            connection,
+            written: Rows::default(),
+        })
+    }
+
+    /// Opens the database knowing what it holds, so the next save only
+    /// writes what differs, as it would after a load.
+    pub fn reopen() -> Result<Self, Box<dyn Error>> {
+        let mut storage = Self::open()?;
+        storage.written = storage.read_rows()?;
+        Ok(storage)
+    }
+
+    fn read_rows(&self) -> Result<Rows, Box<dyn Error>> {
+        let mut rows = Rows::default();
+
+        let mut statement = self.connection.prepare("SELECT name, value FROM meta")?;
+        for row in statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+            let (name, value): (String, String) = row?;
+            rows.meta.insert(name, value);
+        }
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT date, position, item FROM todos")?;
+        for row in statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))? {
+            let (date, position, item): (String, usize, String) = row?;
+            rows.todos.insert((date.parse()?, position), item);
+        }
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT start, seconds, task, project FROM sessions ORDER BY position")?;
+        for row in statement.query_map([], session_columns)? {
+            rows.sessions.push(parse_session(row?)?);
+        }
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT date, study_seconds, sessions FROM days")?;
+        for row in statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))? {
+            let (date, seconds, sessions): (String, _, _) = row?;
+            rows.days.insert(date.parse()?, (seconds, sessions));
+        }
+        Ok(rows)
+    }
+}
+
+type SessionColumns = (String, u64, Option<u64>, Option<u64>);
+
+fn session_columns(row: &rusqlite::Row) -> rusqlite::Result<SessionColumns> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+}
+
+fn parse_session(
+    (start, seconds, task, project): SessionColumns,
+) -> Result<FocusSession, Box<dyn Error>> {
+    Ok(FocusSession {
+        start: NaiveDateTime::parse_from_str(&start, TIME_FORMAT)?,
+        seconds,
+        task,
+        project,
+    })
+}
+
+impl Storage for SqliteStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Sqlite
+    }
+
+    fn load(&mut self) -> Result<AppData, Box<dyn Error>> {
+        let rows = self.read_rows()?;
+        if rows.meta.is_empty() {
+            return Err("nothing has been saved to the database yet".into());
+        }
+        let mut fields = serde_json::Map::new();
+        for (name, value) in &rows.meta {
+            fields.insert(name.clone(), serde_json::from_str(value)?);
+        }
+        fields.insert("todos_by_date".to_owned(), serde_json::json!({}));
+        let mut data: AppData = serde_json::from_value(serde_json::Value::Object(fields))?;
+
+        // Positions run from 0 without gaps, so sorting puts every task in its place.
+        let mut todos: Vec<_> = rows.todos.iter().collect();
+        todos.sort_by_key(|(key, _)| **key);
+        for ((date, _), item) in todos {
+            let item = serde_json::from_str(item)?;
+            data.todos_by_date.entry(*date).or_default().push(item);
+        }
+        data.stats.sessions = rows.sessions.clone();
+        for (date, (seconds, sessions)) in &rows.days {
+            if let Some(seconds) = seconds {
+                data.stats.daily_study_seconds.insert(*date, *seconds);
+            }
+            if let Some(sessions) = sessions {
+                data.stats.daily_sessions.insert(*date, *sessions);
+            }
+        }
+        data.migrate_tasks();
+        self.written = rows;
+        Ok(data)
+    }
+
+    /// Writes the rows that differ from what was last loaded or saved, in
+    /// one transaction.
+    fn save(&mut self, data: &AppData) -> Result<(), Box<dyn Error>> {
+        let rows = Rows::from_data(data)?;
+        let written = &self.written;
+        let transaction = self.connection.transaction()?;
+
+        {
+            let mut upsert = transaction.prepare(
+                "INSERT OR REPLACE INTO todos (date, position, item) VALUES (?1, ?2, ?3)",
+            )?;
+            for ((date, position), item) in &rows.todos {
+                if written.todos.get(&(*date, *position)) != Some(item) {
+                    upsert.execute(params![date.to_string(), position, item])?;
+                }
+            }
+            let mut delete =
+                transaction.prepare("DELETE FROM todos WHERE date = ?1 AND position = ?2")?;
+            for (date, position) in written.todos.keys() {
+                if !rows.todos.contains_key(&(*date, *position)) {
+                    delete.execute(params![date.to_string(), position])?;
+                }
+            }
+
+            // The log mostly grows at the end, so rewrite from the first change.
+            let unchanged = written
+                .sessions
+                .iter()
+                .zip(&rows.sessions)
+                .take_while(|(old, new)| old == new)
+                .count();
+            if unchanged < written.sessions.len() {
+                transaction.execute(
+                    "DELETE FROM sessions WHERE position >= ?1",
+                    params![unchanged],
+                )?;
+            }
+            let mut insert = transaction.prepare(
+                "INSERT INTO sessions (position, start, seconds, task, project) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (position, session) in rows.sessions.iter().enumerate().skip(unchanged) {
+                insert.execute(params![
+                    position,
+                    session.start.format(TIME_FORMAT).to_string(),
+                    session.seconds,
+                    session.task,
+                    session.project
+                ])?;
+            }
+
+            let mut upsert = transaction.prepare(
+                "INSERT OR REPLACE INTO days (date, study_seconds, sessions) VALUES (?1, ?2, ?3)",
+            )?;
+            for (date, (seconds, sessions)) in &rows.days {
+                if written.days.get(date) != Some(&(*seconds, *sessions)) {
+                    upsert.execute(params![date.to_string(), seconds, sessions])?;
+                }
+            }
+            let mut delete = transaction.prepare("DELETE FROM days WHERE date = ?1")?;
+            for date in written.days.keys() {
+                if !rows.days.contains_key(date) {
+                    delete.execute(params![date.to_string()])?;
+                }
+            }
+
+            let mut upsert =
+                transaction.prepare("INSERT OR REPLACE INTO meta (name, value) VALUES (?1, ?2)")?;
+            for (name, value) in &rows.meta {
+                if written.meta.get(name) != Some(value) {
+                    upsert.execute(params![name, value])?;
+                }
+            }
+        }
+
+        transaction.commit()?;
+        self.written = rows;
+        Ok(())
+    }
+
+    fn daily_totals(
+        &mut self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<BTreeMap<NaiveDate, DayTotals>, Box<dyn Error>> {
+        let mut statement = self.connection.prepare(
+            "SELECT date, study_seconds, sessions FROM days WHERE date BETWEEN ?1 AND ?2",
+        )?;
+        let mut days = BTreeMap::new();
+        let query = params![from.to_string(), to.to_string()];
+        for row in statement.query_map(query, |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))? {
+            let (date, seconds, sessions): (String, Option<u64>, Option<u32>) = row?;
+            let totals = DayTotals {
+                study_seconds: seconds.unwrap_or(0),
+                sessions: sessions.unwrap_or(0),
+            };
+            days.insert(date.parse()?, totals);
+        }
+        Ok(days)
+    }
+
+    fn sessions_between(
+        &mut self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<FocusSession>, Box<dyn Error>> {
+        let mut statement = self.connection.prepare(
+            "SELECT start, seconds, task, project FROM sessions
+             WHERE start BETWEEN ?1 AND ?2 ORDER BY start, position",
+        )?;
+        // Every start on `to` sorts before "<to>U", since its time begins with "T".
+        let query = params![from.to_string(), format!("{to}U")];
+        statement
+            .query_map(query, session_columns)?
+            .map(|row| parse_session(row?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_data::TodoItem;
+    use crate::profiles;
+
+    fn session(day: NaiveDate, hour: u32, seconds: u64) -> FocusSession {
+        FocusSession {
+            start: day.and_hms_opt(hour, 0, 0).unwrap(),
+            seconds,
+            task: None,
+            project: None,
+        }
+    }
+
+    // What a fresh open of the database reads back, as JSON to compare.
+    fn reread() -> serde_json::Value {
+        let data = SqliteStorage::open().unwrap().load().unwrap();
+        serde_json::to_value(data).unwrap()
+    }
+
+    #[test]
+    fn saves_read_back_after_every_kind_of_change() {
+        let _turn = profiles::test_profile("test-sqlite");
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let next = day.succ_opt().unwrap();
+        let mut storage = SqliteStorage::open().unwrap();
+        let mut data = AppData::default();
+        let task = |text: &str| TodoItem::new(text.to_owned(), None);
+
+        data.todos_by_date
+            .insert(day, vec![task("one"), task("two"), task("three")]);
+        data.stats.sessions = vec![session(day, 9, 1500), session(day, 10, 1500)];
+        data.stats.daily_study_seconds.insert(day, 3000);
+        data.stats.daily_sessions.insert(day, 2);
+        data.gif_path = Some("focus.gif".to_owned());
+        storage.save(&data).unwrap();
+        assert_eq!(reread(), serde_json::to_value(&data).unwrap());
+
+        // Inserted, moved and deleted tasks.
+        let tasks = data.todos_by_date.get_mut(&day).unwrap();
+        tasks.swap(0, 2);
+        tasks.insert(1, task("between"));
+        tasks.pop();
+        data.todos_by_date.insert(next, vec![task("tomorrow")]);
+        // A shorter log that then grows again.
+        data.stats.sessions.truncate(1);
+        data.stats.sessions.push(session(next, 8, 60));
+        data.stats.daily_study_seconds.insert(day, 1500);
+        data.stats.daily_sessions.remove(&day);
+        data.stats.daily_study_seconds.insert(next, 60);
+        data.gif_path = None;
+        storage.save(&data).unwrap();
+        assert_eq!(reread(), serde_json::to_value(&data).unwrap());
+
+        // Emptied altogether.
+        data.todos_by_date.clear();
+        data.stats.sessions.clear();
+        data.stats.daily_study_seconds.clear();
+        storage.save(&data).unwrap();
+        assert_eq!(reread(), serde_json::to_value(&data).unwrap());
+        assert!(storage.sessions_between(day, next).unwrap().is_empty());
+    }
+
+    #[test]
+    fn ranges_come_from_the_tables() {
+        let _turn = profiles::test_profile("test-sqlite-ranges");
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let next = day.succ_opt().unwrap();
+        let mut storage = SqliteStorage::open().unwrap();
+        let mut data = AppData::default();
+        data.stats.sessions = vec![session(day, 23, 600), session(next, 0, 300)];
+        data.stats.daily_study_seconds.insert(day, 600);
+        data.stats.daily_study_seconds.insert(next, 300);
+        data.stats.daily_sessions.insert(next, 1);
+        storage.save(&data).unwrap();
+
+        assert_eq!(
+            storage.sessions_between(next, next).unwrap(),
+            [session(next, 0, 300)]
+        );
+        assert_eq!(
+            storage.daily_totals(day, next).unwrap(),
+            DayTotals::from_stats(&data.stats, day, next)
+        );
+    }
+}
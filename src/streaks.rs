@@ -0,0 +1,222 @@
+use crate::app_data::{Settings, Stats, StreakGoal};
+use chrono::{Datelike, Days, NaiveDate};
+
+// A freeze is earned for every this many qualifying days in a row...
+const FREEZE_EVERY_DAYS: u32 = 7;
+// ...but no more than this many can be saved up.
+const MAX_FREEZES: u32 = 2;
+
+pub struct StreakSummary {
+    /// Consecutive qualifying days up to today, or up to yesterday while today is still open.
+    pub current: u32,
+    pub longest: u32,
+    pub freezes: u32,
+}
+
+#[derive(PartialEq)]
+enum Day {
+    Kept,
+    // Rest days and frozen days neither extend nor break a streak.
+    Neutral,
+    Missed,
+}
+
+pub fn day_qualifies(stats: &Stats, goal: StreakGoal, date: NaiveDate) -> bool {
+    match goal {
+        StreakGoal::Minutes(minutes) => {
+            let seconds = stats.daily_study_seconds.get(&date).cloned().unwrap_or(0);
+            seconds >= minutes as u64 * 60
+        }
+        StreakGoal::Sessions(sessions) => {
+            stats.daily_sessions.get(&date).cloned().unwrap_or(0) >= sessions
+        }
+    }
+}
+
+fn classify(stats: &Stats, settings: &Settings, date: NaiveDate) -> Day {
+    if day_qualifies(stats, settings.streak_goal, date) {
+        Day::Kept
+    } else if settings.rest_days[date.weekday().num_days_from_monday() as usize]
+        || stats.frozen_days.contains(&date)
+    {
+        Day::Neutral
+    } else {
+        Day::Missed
+    }
+}
+
+fn first_recorded_day(stats: &Stats) -> Option<NaiveDate> {
+    stats.daily_study_seconds.keys().min().copied()
+}
+
+/// Settles every finished day since the last check: qualifying runs earn
+/// freezes, and a missed day spends one (if available) instead of breaking
+/// the streak.
+pub fn update_freezes(stats: &mut Stats, settings: &Settings, today: NaiveDate) {
+    let Some(first) = first_recorded_day(stats) else {
+        return;
+    };
+    let start = stats
+        .streak_checked_through
+        .map_or(first, |d| d + Days::new(1));
+    let yesterday = today - Days::new(1);
+
+    // Rebuild the run length leading into `start` so freezes are earned at the right time.
+    let mut run = run_ending(stats, settings, start - Days::new(1), first);
+    for date in start.iter_days().take_while(|d| *d <= yesterday) {
+        match classify(stats, settings, date) {
+            Day::Kept => {
+                run += 1;
+                if run.is_multiple_of(FREEZE_EVERY_DAYS) {
+                    stats.streak_freezes = (stats.streak_freezes + 1).min(MAX_FREEZES);
+                }
+            }
+            Day::Neutral => {}
+            Day::Missed if run > 0 && stats.streak_freezes > 0 => {
+                stats.streak_freezes -= 1;
+                stats.frozen_days.insert(date);
+            }
+            Day::Missed => run = 0,
+        }
+        stats.streak_checked_through = Some(date);
+    }
+}
+
+// Qualifying days in the streak that ends on `last`, looking no further back than `first`.
+fn run_ending(stats: &Stats, settings: &Settings, last: NaiveDate, first: NaiveDate) -> u32 {
+    let mut run = 0;
+    let mut date = last;
+    while date >= first {
+        match classify(stats, settings, date) {
+            Day::Kept => run += 1,
+            Day::Neutral => {}
+            Day::Missed => break,
+        }
+        date = date - Days::new(1);
+    }
+    run
+}
+
+pub fn summary(stats: &Stats, settings: &Settings, today: NaiveDate) -> StreakSummary {
+    let Some(first) = first_recorded_day(stats) else {
+        return StreakSummary {
+            current: 0,
+            longest: 0,
+            freezes: stats.streak_freezes,
+        };
+    };
+
+    // Today only counts once it qualifies; until then it doesn't break the streak.
+    let current = if classify(stats, settings, today) == Day::Kept {
+        run_ending(stats, settings, today, first)
+    } else {
+        run_ending(stats, settings, today - Days::new(1), first)
+    };
+
+    let mut longest = 0;
+    let mut run = 0;
+    for date in first.iter_days().take_while(|d| *d <= today) {
+        match classify(stats, settings, date) {
+            Day::Kept => {
+                run += 1;
+                longest = longest.max(run);
+            }
+            Day::Neutral => {}
+            Day::Missed if date == today => {}
+            Day::Missed => run = 0,
+        }
+    }
+
+    StreakSummary {
+        current,
+        longest,
+        freezes: stats.streak_freezes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    // 30 minutes of study on each of `days`, against a 25 minute goal.
+    fn studied(days: impl IntoIterator<Item = u32>) -> Stats {
+        Stats {
+            daily_study_seconds: days.into_iter().map(|d| (day(d), 1800)).collect(),
+            ..Stats::default()
+        }
+    }
+
+    #[test]
+    fn rest_days_and_an_open_today_keep_the_streak() {
+        // 2025-03-02 and 03-09 are Sundays, taken as rest days.
+        let mut settings = Settings::default();
+        settings.rest_days[6] = true;
+        let stats = studied([3, 4, 5, 6, 7, 8, 10]);
+
+        let streak = summary(&stats, &settings, day(11));
+        assert_eq!((streak.current, streak.longest), (7, 7));
+
+        // A missed weekday breaks it, but the longest run is remembered.
+        let streak = summary(&stats, &settings, day(12));
+        assert_eq!((streak.current, streak.longest), (0, 7));
+    }
+
+    #[test]
+    fn a_week_earns_a_freeze_that_covers_a_missed_day() {
+        let settings = Settings::default();
+        let mut stats = studied((1..=7).chain(9..=10));
+
+        update_freezes(&mut stats, &settings, day(8));
+        assert_eq!(stats.streak_freezes, 1);
+        assert_eq!(stats.streak_checked_through, Some(day(7)));
+
+        // Day 8 was missed: the freeze is spent on it.
+        update_freezes(&mut stats, &settings, day(11));
+        assert_eq!(stats.streak_freezes, 0);
+        assert!(stats.frozen_days.contains(&day(8)));
+        let streak = summary(&stats, &settings, day(11));
+        assert_eq!(streak.current, 9);
+
+        // Without a freeze left, the next missed day breaks the streak.
+        update_freezes(&mut stats, &settings, day(13));
+        assert_eq!(summary(&stats, &settings, day(13)).current, 0);
+    }
+
+    #[test]
+    fn rest_days_neither_earn_nor_spend_freezes() {
+        let mut settings = Settings::default();
+        settings.rest_days[6] = true;
+        let mut stats = studied([3, 4, 5, 6, 7, 8]);
+        update_freezes(&mut stats, &settings, day(10));
+        assert_eq!(stats.streak_freezes, 0);
+        assert!(stats.frozen_days.is_empty());
+
+        // The seventh qualifying day comes after the Sunday.
+        stats.daily_study_seconds.insert(day(10), 1800);
+        update_freezes(&mut stats, &settings, day(11));
+        assert_eq!(stats.streak_freezes, 1);
+    }
+
+    #[test]
+    fn freezes_are_capped() {
+        let settings = Settings::default();
+        let mut stats = studied(1..=28);
+        update_freezes(&mut stats, &settings, day(29));
+        assert_eq!(stats.streak_freezes, MAX_FREEZES);
+    }
+
+    #[test]
+    fn a_session_goal_counts_pomodoros() {
+        let stats = Stats {
+            daily_sessions: [(day(1), 3)].into(),
+            ..Stats::default()
+        };
+        assert!(day_qualifies(&stats, StreakGoal::Sessions(3), day(1)));
+        assert!(!day_qualifies(&stats, StreakGoal::Sessions(4), day(1)));
+        assert!(!day_qualifies(&stats, StreakGoal::Minutes(1), day(1)));
+    }
+}
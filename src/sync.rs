@@ -0,0 +1,1015 @@
+//! Syncing devices through a shared folder, e.g. one kept by Dropbox or Syncthing.
+//!
+//! Every device writes only its own file, `focushub-sync/<device>.json`, so
+//! two machines never overwrite each other's data. Each sync merges the other
+//! devices' files into the local data:
+//!
+//! - Tasks, projects and rewards are merged three-way against the copy of the
+//!   other device's file seen at the previous sync, so additions, edits and
+//!   deletions made on either side carry over. When both sides changed the
+//!   same item differently the local version is kept and a [`Conflict`] is
+//!   raised for the user to review. Merged changes can't be undone, as the
+//!   undo would then be shared as a change of this device's.
+//! - Study time and pomodoro counts are kept per device and only ever grow,
+//!   so each device's totals travel in its own file and the largest value
+//!   seen for a device wins. Nothing is counted twice however the files
+//!   travel between devices. Totals from before a device joined the folder
+//!   stay on that device.
+//! - Focus sessions, points transactions and reward claims are merged as
+//!   sets.
+//!
+//! What was last seen of each device is kept next to the data file, in
+//! `focushub_sync_base.json`, along with the conflicts not yet reviewed. It
+//! starts over when another folder is chosen.
+
+use crate::app_data::{self, AppData, FocusSession, Project, Reward, Stats, TodoItem, Transaction};
+use crate::history::{Command, History};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+const SYNC_DIR: &str = "focushub-sync";
+const BASE_FILE: &str = "focushub_sync_base.json";
+const FORMAT_VERSION: u32 = 1;
+// How often other devices' files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+// How often this device's file is rewritten while the app runs, if anything changed.
+const WRITE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Study time and completed pomodoros per day, as recorded on one device.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Counters {
+    pub study_seconds: BTreeMap<NaiveDate, u64>,
+    pub sessions: BTreeMap<NaiveDate, u32>,
+}
+
+impl Counters {
+    // The larger value of each day from either side.
+    fn max(&mut self, other: &Counters) {
+        for (day, seconds) in &other.study_seconds {
+            let value = self.study_seconds.entry(*day).or_insert(0);
+            *value = (*value).max(*seconds);
+        }
+        for (day, sessions) in &other.sessions {
+            let value = self.sessions.entry(*day).or_insert(0);
+            *value = (*value).max(*sessions);
+        }
+    }
+}
+
+/// The file one device writes into the sync folder.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeviceFile {
+    pub version: u32,
+    pub device: String,
+    /// The machine the device runs on, to notice two machines using one name.
+    pub host: String,
+    #[serde(default)]
+    pub todos_by_date: HashMap<NaiveDate, Vec<TodoItem>>,
+    #[serde(default)]
+    pub projects: Vec<Project>,
+    #[serde(default)]
+    pub rewards: Vec<Reward>,
+    #[serde(default)]
+    pub sessions: Vec<FocusSession>,
+    #[serde(default)]
+    pub ledger: Vec<Transaction>,
+    /// Totals by device name, this device's own included.
+    #[serde(default)]
+    pub counters: BTreeMap<String, Counters>,
+}
+
+// What this device remembers between syncs.
+#[derive(Default, Serialize, Deserialize)]
+struct SyncBase {
+    /// The folder it was built up in; empty in bases from before this was kept.
+    #[serde(default)]
+    folder: PathBuf,
+    /// Each other device's file as it was last merged.
+    peers: BTreeMap<String, DeviceFile>,
+    /// Other devices' counters that have been added into the local totals.
+    applied: BTreeMap<String, Counters>,
+    /// The local totals when this device joined the folder. They aren't
+    /// shared, as other devices often start from a copy of the same history.
+    baseline: Counters,
+    /// Conflicts not reviewed yet. The peers above already include the other
+    /// side of them, so they would not be raised again if lost.
+    #[serde(default)]
+    conflicts: Vec<Conflict>,
+}
+
+/// Something both this device and another one changed since they last synced.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ConflictItem {
+    /// A dated task with the day it is on; `None` if that side deleted it.
+    Task {
+        ours: Option<(NaiveDate, TodoItem)>,
+        theirs: Option<(NaiveDate, TodoItem)>,
+    },
+    Project {
+        ours: Option<Project>,
+        theirs: Option<Project>,
+    },
+    Reward {
+        ours: Option<Reward>,
+        theirs: Option<Reward>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Conflict {
+    /// The other device involved.
+    pub device: String,
+    pub item: ConflictItem,
+}
+
+impl Conflict {
+    /// A short label for the item on each side, "(deleted)" if it is gone.
+    pub fn describe(&self) -> (String, String) {
+        fn side<T>(value: &Option<T>, describe: impl Fn(&T) -> String) -> String {
+            value.as_ref().map_or("(deleted)".to_owned(), describe)
+        }
+        let task = |(date, task): &(NaiveDate, TodoItem)| {
+            let mark = if task.is_done() { "☑" } else { "☐" };
+            format!("{date}  {mark} {}", task.text)
+        };
+        let project = |p: &Project| format!("📁 {} ({} tasks)", p.name, p.tasks.len());
+        let reward = |r: &Reward| format!("{} {} ({})", r.icon, r.name, r.cost.describe());
+        match &self.item {
+            ConflictItem::Task { ours, theirs } => (side(ours, task), side(theirs, task)),
+            ConflictItem::Project { ours, theirs } => (side(ours, project), side(theirs, project)),
+            ConflictItem::Reward { ours, theirs } => (side(ours, reward), side(theirs, reward)),
+        }
+    }
+
+    // Puts the other device's version into the given copies of the data.
+    fn apply_theirs(
+        &self,
+        schedule: &mut HashMap<NaiveDate, Vec<TodoItem>>,
+        projects: &mut Vec<Project>,
+        rewards: &mut Vec<Reward>,
+    ) {
+        match &self.item {
+            ConflictItem::Task { ours, theirs } => {
+                let id = ours.as_ref().or(theirs.as_ref()).map(|(_, t)| t.id);
+                // Keep the task where it was if it stays on the same day.
+                let mut position = None;
+                for (date, tasks) in schedule.iter_mut() {
+                    if let Some(index) = tasks.iter().position(|t| Some(t.id) == id) {
+                        tasks.remove(index);
+                        position = Some((*date, index));
+                    }
+                }
+                if let Some((date, task)) = theirs {
+                    let tasks = schedule.entry(*date).or_default();
+                    let index = position
+                        .filter(|(day, _)| day == date)
+                        .map_or(tasks.len(), |(_, index)| index);
+                    tasks.insert(index.min(tasks.len()), task.clone());
+                }
+            }
+            ConflictItem::Project { ours, theirs } => {
+                let id = ours.as_ref().or(theirs.as_ref()).map(|p| p.id);
+                *projects = replace_by_id(projects, id, theirs.clone(), |p| p.id);
+            }
+            ConflictItem::Reward { ours, theirs } => {
+                let id = ours.as_ref().or(theirs.as_ref()).map(|r| r.id);
+                // Claims were already merged, so keep the ones the reward has now.
+                let theirs = theirs.clone().map(|mut reward| {
+                    if let Some(current) = rewards.iter().find(|r| r.id == reward.id) {
+                        reward.claims = current.claims.clone();
+                    }
+                    reward
+                });
+                *rewards = replace_by_id(rewards, id, theirs, |r| r.id);
+            }
+        }
+    }
+}
+
+/// The command that swaps the local versions of `conflicts` for the other devices'.
+pub fn take_theirs(conflicts: &[Conflict], data: &AppData) -> Command {
+    let mut schedule = data.todos_by_date.clone();
+    let mut projects = data.projects.clone();
+    let mut rewards = data.rewards.clone();
+    for conflict in conflicts {
+        conflict.apply_theirs(&mut schedule, &mut projects, &mut rewards);
+    }
+    let mut commands = Vec::new();
+    if schedule != data.todos_by_date {
+        commands.push(Command::ReplaceSchedule {
+            old: data.todos_by_date.clone(),
+            new: schedule,
+        });
+    }
+    if projects != data.projects {
+        commands.push(Command::ReplaceProjects {
+            old: data.projects.clone(),
+            new: projects,
+        });
+    }
+    if rewards != data.rewards {
+        commands.push(Command::ReplaceRewards {
+            old: data.rewards.clone(),
+            new: rewards,
+        });
+    }
+    Command::Batch(commands)
+}
+
+// Swaps the item with the given id for `new`, or drops it if `new` is `None`.
+fn replace_by_id<T: Clone>(
+    items: &[T],
+    id: Option<u64>,
+    new: Option<T>,
+    key: impl Fn(&T) -> u64,
+) -> Vec<T> {
+    let mut out: Vec<T> = items.to_vec();
+    match out.iter().position(|item| Some(key(item)) == id) {
+        Some(index) => match new {
+            Some(new) => out[index] = new,
+            None => {
+                out.remove(index);
+            }
+        },
+        None => out.extend(new),
+    }
+    out
+}
+
+/// The outcome of one three-way merge of keyed items.
+struct Merged<V> {
+    /// Merged items in order: local ones first, in their order, then new ones.
+    items: Vec<(u64, V)>,
+    conflicts: Vec<(Option<V>, Option<V>)>,
+}
+
+/// Merges `theirs` into `ours`. With a `base` (the other side as of the last
+/// merge), a change on just one side wins and changes on both sides
+/// conflict. Without one, items only one side has are kept and differing
+/// items conflict.
+fn merge_keyed<V: Clone + PartialEq>(
+    base: Option<&[(u64, V)]>,
+    ours: &[(u64, V)],
+    theirs: &[(u64, V)],
+) -> Merged<V> {
+    let find = |items: &[(u64, V)], key: u64| {
+        items
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.clone())
+    };
+    let mut merged = Merged {
+        items: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    let mut keys: Vec<u64> = ours.iter().map(|(k, _)| *k).collect();
+    for (key, _) in theirs.iter().chain(base.unwrap_or_default()) {
+        if !keys.contains(key) {
+            keys.push(*key);
+        }
+    }
+    for key in keys {
+        let ours = find(ours, key);
+        let theirs = find(theirs, key);
+        let result = if ours == theirs {
+            ours
+        } else {
+            match base.map(|base| find(base, key)) {
+                Some(base) if base == theirs => ours,
+                Some(base) if base == ours => theirs,
+                None if ours.is_none() => theirs,
+                None if theirs.is_none() => ours,
+                _ => {
+                    merged.conflicts.push((ours.clone(), theirs));
+                    ours
+                }
+            }
+        };
+        if let Some(value) = result {
+            merged.items.push((key, value));
+        }
+    }
+    merged
+}
+
+// Dated tasks keyed by id, each with its day. Days are visited in date order
+// so the merged schedule doesn't depend on hash map order.
+fn schedule_items(
+    schedule: &HashMap<NaiveDate, Vec<TodoItem>>,
+) -> Vec<(u64, (NaiveDate, TodoItem))> {
+    let mut dates: Vec<&NaiveDate> = schedule.keys().collect();
+    dates.sort();
+    dates
+        .into_iter()
+        .flat_map(|date| schedule[date].iter().map(|t| (t.id, (*date, t.clone()))))
+        .collect()
+}
+
+// Rewards keyed by id, with their claims left out: claims are merged as a set.
+fn reward_items(rewards: &[Reward]) -> Vec<(u64, Reward)> {
+    rewards
+        .iter()
+        .map(|r| {
+            let mut reward = r.clone();
+            reward.claims.clear();
+            (r.id, reward)
+        })
+        .collect()
+}
+
+// Applies the other side's additions and removals since `base` to `ours`.
+fn merge_set<T: Clone + PartialEq>(
+    base: Option<&[T]>,
+    ours: &[T],
+    theirs: &[T],
+) -> (Vec<T>, Vec<T>) {
+    let base = base.unwrap_or_default();
+    let added = theirs
+        .iter()
+        .filter(|t| !base.contains(t) && !ours.contains(t))
+        .cloned()
+        .collect();
+    let removed = base
+        .iter()
+        .filter(|t| !theirs.contains(t) && ours.contains(t))
+        .cloned()
+        .collect();
+    (added, removed)
+}
+
+/// What a sync brought in.
+#[derive(Default)]
+pub struct SyncSummary {
+    pub devices: usize,
+    pub conflicts: usize,
+    pub study_seconds: u64,
+}
+
+/// The shared folder and what this device knows about the others in it.
+pub struct FolderSync {
+    folder: PathBuf,
+    device: String,
+    host: String,
+    base: SyncBase,
+    /// Modification times of the other devices' files when they were last merged.
+    stamps: HashMap<PathBuf, SystemTime>,
+    last_check: Option<Instant>,
+    last_write: Option<Instant>,
+    /// This device's file as last written, to skip writes that change nothing.
+    written: Option<String>,
+    /// Conflicts waiting for the user; the local version is kept until then.
+    /// Saved with the base, so they survive a restart.
+    pub conflicts: Vec<Conflict>,
+    pub last_sync: Option<NaiveDateTime>,
+}
+
+fn host_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "device".to_owned())
+}
+
+/// The folder inside `folder` that holds one file per device.
+pub fn device_folder(folder: &Path) -> PathBuf {
+    folder.join(SYNC_DIR)
+}
+
+// A name that is unique to this machine and safe to use as a file name.
+fn new_device_name(host: &str) -> String {
+    let host: String = host
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("{host}-{:x}", app_data::new_id() % 0x10000)
+}
+
+impl FolderSync {
+    /// Opens `folder` for syncing as `device`. An empty device name, or one
+    /// already used by another machine, is replaced by a new one; read it back
+    /// with [`FolderSync::device`].
+    pub fn open(folder: PathBuf, device: &str, stats: &Stats) -> Result<Self, Box<dyn Error>> {
+        fs::create_dir_all(device_folder(&folder))?;
+        let host = host_name();
+        let mut device = device.to_owned();
+        let taken = read_device_file(&device_folder(&folder).join(format!("{device}.json")))
+            .is_ok_and(|file| file.host != host);
+        if device.is_empty() || taken {
+            device = new_device_name(&host);
+        }
+        let mut base = fs::read_to_string(app_data::local_path(BASE_FILE)?)
+            .ok()
+            .and_then(|text| serde_json::from_str::<SyncBase>(&text).ok())
+            // Another folder's devices are no base for this one's.
+            .filter(|base| base.folder.as_os_str().is_empty() || base.folder == folder)
+            .unwrap_or_else(|| SyncBase {
+                baseline: Counters {
+                    study_seconds: stats.daily_study_seconds.clone().into_iter().collect(),
+                    sessions: stats.daily_sessions.clone().into_iter().collect(),
+                },
+                ..SyncBase::default()
+            });
+        base.folder = folder.clone();
+        Ok(Self {
+            folder,
+            device,
+            host,
+            conflicts: base.conflicts.clone(),
+            base,
+            stamps: HashMap::new(),
+            last_check: None,
+            last_write: None,
+            written: None,
+            last_sync: None,
+        })
+    }
+
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    pub fn device(&self) -> &str {
+        &self.device
+    }
+
+    // Other devices' files with their modification times.
+    fn peer_files(&self) -> Result<Vec<(PathBuf, SystemTime)>, Box<dyn Error>> {
+        let mine = format!("{}.json", self.device);
+        let mut files = Vec::new();
+        for entry in fs::read_dir(device_folder(&self.folder))? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.ends_with(".json") && name != mine {
+                files.push((entry.path(), entry.metadata()?.modified()?));
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Whether another device's file changed since it was last merged.
+    /// Checks at most every few seconds; the first call always looks.
+    pub fn poll(&mut self) -> Result<bool, Box<dyn Error>> {
+        if self.last_check.is_some_and(|t| t.elapsed() < POLL_INTERVAL) {
+            return Ok(false);
+        }
+        self.last_check = Some(Instant::now());
+        Ok(self
+            .peer_files()?
+            .iter()
+            .any(|(path, modified)| self.stamps.get(path) != Some(modified)))
+    }
+
+    /// Merges every other device's file into `data` and `stats`. Task,
+    /// project and reward changes are applied through `history`, which can't
+    /// undo them or anything before them.
+    pub fn merge(
+        &mut self,
+        data: &mut AppData,
+        stats: &mut Stats,
+        history: &mut History,
+    ) -> Result<SyncSummary, Box<dyn Error>> {
+        let mut summary = SyncSummary::default();
+        let mut counters = self.base.applied.clone();
+        for (path, modified) in self.peer_files()? {
+            let theirs = match read_device_file(&path) {
+                Ok(file) if file.version <= FORMAT_VERSION && file.device != self.device => file,
+                // Half-written or from a newer version: try again next time.
+                _ => continue,
+            };
+            let base = self.base.peers.get(&theirs.device).cloned();
+            self.merge_device(data, stats, history, base, &theirs);
+            for (device, theirs) in &theirs.counters {
+                if *device != self.device {
+                    counters.entry(device.clone()).or_default().max(theirs);
+                }
+            }
+            summary.devices += 1;
+            self.stamps.insert(path, modified);
+            self.base.peers.insert(theirs.device.clone(), theirs);
+        }
+        summary.study_seconds = self.apply_counters(stats, counters);
+        summary.conflicts = self.conflicts.len();
+        self.last_sync = Some(Local::now().naive_local());
+        self.save_base()?;
+        Ok(summary)
+    }
+
+    fn merge_device(
+        &mut self,
+        data: &mut AppData,
+        stats: &mut Stats,
+        history: &mut History,
+        base: Option<DeviceFile>,
+        theirs: &DeviceFile,
+    ) {
+        let device = theirs.device.clone();
+        let mut commands = Vec::new();
+
+        // Dated tasks.
+        let base_tasks = base.as_ref().map(|b| schedule_items(&b.todos_by_date));
+        let merged = merge_keyed(
+            base_tasks.as_deref(),
+            &schedule_items(&data.todos_by_date),
+            &schedule_items(&theirs.todos_by_date),
+        );
+        let mut schedule: HashMap<NaiveDate, Vec<TodoItem>> = HashMap::new();
+        for (_, (date, task)) in merged.items {
+            schedule.entry(date).or_default().push(task);
+        }
+        // Days that ended up empty stay, like they do after deleting their last task.
+        for date in data.todos_by_date.keys() {
+            schedule.entry(*date).or_default();
+        }
+        if schedule != data.todos_by_date {
+            commands.push(Command::ReplaceSchedule {
+                old: data.todos_by_date.clone(),
+                new: schedule,
+            });
+        }
+        self.conflicts
+            .extend(merged.conflicts.into_iter().map(|(ours, theirs)| Conflict {
+                device: device.clone(),
+                item: ConflictItem::Task { ours, theirs },
+            }));
+
+        // Projects, each merged as a whole.
+        let key_projects = |projects: &[Project]| -> Vec<(u64, Project)> {
+            projects.iter().map(|p| (p.id, p.clone())).collect()
+        };
+        let base_projects = base.as_ref().map(|b| key_projects(&b.projects));
+        let merged = merge_keyed(
+            base_projects.as_deref(),
+            &key_projects(&data.projects),
+            &key_projects(&theirs.projects),
+        );
+        let projects: Vec<Project> = merged.items.into_iter().map(|(_, p)| p).collect();
+        if projects != data.projects {
+            commands.push(Command::ReplaceProjects {
+                old: data.projects.clone(),
+                new: projects,
+            });
+        }
+        self.conflicts
+            .extend(merged.conflicts.into_iter().map(|(ours, theirs)| Conflict {
+                device: device.clone(),
+                item: ConflictItem::Project { ours, theirs },
+            }));
+
+        // Rewards, with claims from both sides.
+        let base_rewards = base.as_ref().map(|b| reward_items(&b.rewards));
+        let merged = merge_keyed(
+            base_rewards.as_deref(),
+            &reward_items(&data.rewards),
+            &reward_items(&theirs.rewards),
+        );
+        let rewards: Vec<Reward> = merged
+            .items
+            .into_iter()
+            .map(|(id, mut reward)| {
+                let all_claims = data
+                    .rewards
+                    .iter()
+                    .chain(&theirs.rewards)
+                    .filter(|r| r.id == id)
+                    .flat_map(|r| r.claims.iter().copied());
+                let mut claims: Vec<NaiveDateTime> = all_claims.collect();
+                claims.sort();
+                claims.dedup();
+                if !reward.repeatable && !claims.is_empty() {
+                    reward.completed = true;
+                }
+                reward.claims = claims;
+                reward
+            })
+            .collect();
+        if rewards != data.rewards {
+            commands.push(Command::ReplaceRewards {
+                old: data.rewards.clone(),
+                new: rewards,
+            });
+        }
+        self.conflicts
+            .extend(merged.conflicts.into_iter().map(|(ours, theirs)| Conflict {
+                device: device.clone(),
+                item: ConflictItem::Reward { ours, theirs },
+            }));
+
+        if !commands.is_empty() {
+            history.apply_external(Command::Batch(commands), data);
+        }
+
+        // Sessions and points are sets.
+        let (added, removed) = merge_set(
+            base.as_ref().map(|b| b.sessions.as_slice()),
+            &stats.sessions,
+            &theirs.sessions,
+        );
+        stats.sessions.retain(|s| !removed.contains(s));
+        stats.sessions.extend(added);
+        stats.sessions.sort_by_key(|s| s.start);
+
+        let (added, removed) = merge_set(
+            base.as_ref().map(|b| b.ledger.as_slice()),
+            &data.wallet.ledger,
+            &theirs.ledger,
+        );
+        for transaction in &removed {
+            data.wallet.revert(transaction);
+        }
+        for transaction in added {
+            data.wallet.record(transaction);
+        }
+        data.wallet.ledger.sort_by_key(|t| t.time);
+    }
+
+    // Adds whatever the other devices recorded since the last sync to the
+    // local totals. Returns the study seconds added.
+    fn apply_counters(&mut self, stats: &mut Stats, counters: BTreeMap<String, Counters>) -> u64 {
+        let mut added = 0;
+        for (device, new) in &counters {
+            let old = self.base.applied.get(device).cloned().unwrap_or_default();
+            for (day, seconds) in &new.study_seconds {
+                let before = old.study_seconds.get(day).copied().unwrap_or(0);
+                let delta = seconds.saturating_sub(before);
+                *stats.daily_study_seconds.entry(*day).or_insert(0) += delta;
+                added += delta;
+            }
+            for (day, sessions) in &new.sessions {
+                let before = old.sessions.get(day).copied().unwrap_or(0);
+                let delta = sessions.saturating_sub(before);
+                if delta > 0 {
+                    *stats.daily_sessions.entry(*day).or_insert(0) += delta;
+                    let month_key = format!("{}-{}", day.year(), day.month());
+                    *stats.monthly_sessions.entry(month_key).or_insert(0) += delta;
+                }
+            }
+        }
+        self.base.applied = counters;
+        added
+    }
+
+    // This device's own totals: everything recorded since it joined the
+    // folder that wasn't merged in from other devices.
+    fn own_counters(&self, stats: &Stats) -> Counters {
+        let merged_in = |day: &NaiveDate| {
+            let counters = self.base.applied.values().chain([&self.base.baseline]);
+            counters.fold((0, 0), |(seconds, sessions), c| {
+                (
+                    seconds + c.study_seconds.get(day).copied().unwrap_or(0),
+                    sessions + c.sessions.get(day).copied().unwrap_or(0),
+                )
+            })
+        };
+        let mut own = Counters::default();
+        for (day, seconds) in &stats.daily_study_seconds {
+            let own_seconds = seconds.saturating_sub(merged_in(day).0);
+            if own_seconds > 0 {
+                own.study_seconds.insert(*day, own_seconds);
+            }
+        }
+        for (day, sessions) in &stats.daily_sessions {
+            let own_sessions = sessions.saturating_sub(merged_in(day).1);
+            if own_sessions > 0 {
+                own.sessions.insert(*day, own_sessions);
+            }
+        }
+        own
+    }
+
+    /// Writes this device's file if its contents changed. Unless `now` is set,
+    /// writes happen at most every half a minute.
+    pub fn write(
+        &mut self,
+        data: &AppData,
+        stats: &Stats,
+        now: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        if !now
+            && self
+                .last_write
+                .is_some_and(|t| t.elapsed() < WRITE_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.last_write = Some(Instant::now());
+        // Conflicts resolved since the last merge.
+        if self.base.conflicts != self.conflicts {
+            self.save_base()?;
+        }
+        let mut counters = self.base.applied.clone();
+        counters.insert(self.device.clone(), self.own_counters(stats));
+        let file = DeviceFile {
+            version: FORMAT_VERSION,
+            device: self.device.clone(),
+            host: self.host.clone(),
+            todos_by_date: data.todos_by_date.clone(),
+            projects: data.projects.clone(),
+            rewards: data.rewards.clone(),
+            sessions: stats.sessions.clone(),
+            ledger: data.wallet.ledger.clone(),
+            counters,
+        };
+        let text = serde_json::to_string_pretty(&file)?;
+        if self.written.as_ref() == Some(&text) {
+            return Ok(());
+        }
+        // Write next to the target and rename, so other devices never read half a file.
+        let path = device_folder(&self.folder).join(format!("{}.json", self.device));
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, &text)?;
+        fs::rename(&partial, &path)?;
+        self.written = Some(text);
+        Ok(())
+    }
+
+    fn save_base(&mut self) -> Result<(), Box<dyn Error>> {
+        self.base.conflicts = self.conflicts.clone();
+        fs::write(
+            app_data::local_path(BASE_FILE)?,
+            serde_json::to_string(&self.base)?,
+        )?;
+        Ok(())
+    }
+}
+
+/// Deletes the merge base kept next to the data file, a plaintext copy of
+/// the data as last synced.
+pub fn remove_base() -> Result<(), std::io::Error> {
+    let path = app_data::local_path(BASE_FILE)?;
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn read_device_file(path: &Path) -> Result<DeviceFile, Box<dyn Error>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_data::{TaskList, TaskRef};
+    use crate::profiles;
+
+    fn keyed(items: &[(u64, &'static str)]) -> Vec<(u64, &'static str)> {
+        items.to_vec()
+    }
+
+    fn merged_values(merged: &Merged<&'static str>) -> Vec<&'static str> {
+        merged.items.iter().map(|(_, v)| *v).collect()
+    }
+
+    #[test]
+    fn additions_on_either_side_are_kept() {
+        let base = keyed(&[(1, "a")]);
+        let merged = merge_keyed(
+            Some(&base),
+            &keyed(&[(1, "a"), (2, "ours")]),
+            &keyed(&[(1, "a"), (3, "theirs")]),
+        );
+        assert_eq!(merged_values(&merged), ["a", "ours", "theirs"]);
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn an_edit_on_one_side_wins() {
+        let base = keyed(&[(1, "a"), (2, "b")]);
+        let ours = keyed(&[(1, "a, edited here"), (2, "b")]);
+        let theirs = keyed(&[(1, "a"), (2, "b, edited there")]);
+        let merged = merge_keyed(Some(&base), &ours, &theirs);
+        assert_eq!(
+            merged_values(&merged),
+            ["a, edited here", "b, edited there"]
+        );
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn a_deletion_on_one_side_wins() {
+        let base = keyed(&[(1, "a"), (2, "b")]);
+        let merged = merge_keyed(Some(&base), &keyed(&[(2, "b")]), &keyed(&[(1, "a")]));
+        assert!(merged.items.is_empty());
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn changes_on_both_sides_conflict_and_keep_ours() {
+        let base = keyed(&[(1, "a"), (2, "b"), (3, "c")]);
+        let ours = keyed(&[(1, "a, ours"), (2, "b, ours")]);
+        let theirs = keyed(&[(1, "a, theirs"), (3, "c, theirs")]);
+        let merged = merge_keyed(Some(&base), &ours, &theirs);
+        assert_eq!(merged_values(&merged), ["a, ours", "b, ours"]);
+        assert_eq!(
+            merged.conflicts,
+            [
+                (Some("a, ours"), Some("a, theirs")),
+                (Some("b, ours"), None),
+                (None, Some("c, theirs")),
+            ]
+        );
+    }
+
+    #[test]
+    fn without_a_base_differences_conflict() {
+        let merged = merge_keyed(
+            None,
+            &keyed(&[(1, "a"), (2, "b")]),
+            &keyed(&[(1, "x"), (3, "c")]),
+        );
+        assert_eq!(merged_values(&merged), ["a", "b", "c"]);
+        assert_eq!(merged.conflicts, [(Some("a"), Some("x"))]);
+    }
+
+    #[test]
+    fn sets_take_the_other_sides_additions_and_removals() {
+        let (added, removed) = merge_set(Some(&[1, 2, 3]), &[1, 2, 3, 4], &[2, 3, 5]);
+        assert_eq!(added, [5]);
+        assert_eq!(removed, [1]);
+        // Without a base nothing is removed.
+        let (added, removed) = merge_set(None, &[1], &[2]);
+        assert_eq!((added, removed), (vec![2], vec![]));
+    }
+
+    // A device with its own stats, syncing through `folder`.
+    struct Device {
+        sync: FolderSync,
+        data: AppData,
+        stats: Stats,
+        history: History,
+    }
+
+    impl Device {
+        fn open(folder: &Path, name: &str) -> Self {
+            let stats = Stats::default();
+            Self {
+                sync: FolderSync::open(folder.to_owned(), name, &stats).unwrap(),
+                data: AppData::default(),
+                stats,
+                history: History::default(),
+            }
+        }
+
+        fn study(&mut self, day: NaiveDate, seconds: u64) {
+            *self.stats.daily_study_seconds.entry(day).or_insert(0) += seconds;
+            *self.stats.daily_sessions.entry(day).or_insert(0) += 1;
+        }
+
+        fn sync(&mut self) -> SyncSummary {
+            let summary = self
+                .sync
+                .merge(&mut self.data, &mut self.stats, &mut self.history)
+                .unwrap();
+            self.sync.write(&self.data, &self.stats, true).unwrap();
+            summary
+        }
+
+        fn seconds(&self, day: NaiveDate) -> u64 {
+            self.stats
+                .daily_study_seconds
+                .get(&day)
+                .copied()
+                .unwrap_or(0)
+        }
+    }
+
+    fn test_folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(name);
+        fs::remove_dir_all(&folder).ok();
+        fs::create_dir_all(device_folder(&folder)).unwrap();
+        folder
+    }
+
+    #[test]
+    fn counters_relayed_through_a_third_device_count_once() {
+        let _turn = profiles::test_profile("test-sync-counters");
+        let shared = test_folder("focushub-test-sync-shared");
+        let late = test_folder("focushub-test-sync-late");
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let mut a = Device::open(&shared, "a");
+        let mut b = Device::open(&shared, "b");
+        let mut c = Device::open(&late, "c");
+
+        a.study(day, 600);
+        a.sync();
+        b.study(day, 60);
+        b.sync();
+        assert_eq!(b.seconds(day), 660);
+
+        // C first hears of A's time through B's file only...
+        let file = |device: &str| device_folder(&shared).join(format!("{device}.json"));
+        fs::copy(file("b"), device_folder(&late).join("b.json")).unwrap();
+        c.sync();
+        assert_eq!(c.seconds(day), 660);
+        // ...then gets A's own file, which must not add it again.
+        fs::copy(file("a"), device_folder(&late).join("a.json")).unwrap();
+        c.sync();
+        assert_eq!(c.seconds(day), 660);
+
+        // Later study on A arrives as the difference only.
+        a.study(day, 300);
+        a.sync();
+        fs::copy(file("a"), device_folder(&late).join("a.json")).unwrap();
+        c.sync();
+        assert_eq!(c.seconds(day), 960);
+        assert_eq!(c.stats.daily_sessions[&day], 3);
+
+        // Nothing merged in is passed off as a device's own time.
+        assert_eq!(b.sync.own_counters(&b.stats).study_seconds[&day], 60);
+        assert!(c.sync.own_counters(&c.stats).study_seconds.is_empty());
+    }
+
+    #[test]
+    fn conflicts_survive_a_restart() {
+        let _turn = profiles::test_profile("test-sync-conflicts");
+        let folder = test_folder("focushub-test-sync-conflicts");
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let mut a = Device::open(&folder, "a");
+        let mut b = Device::open(&folder, "b");
+
+        let task = TodoItem::new("Read".to_owned(), None);
+        let id = task.id;
+        a.data.todos_by_date.insert(day, vec![task]);
+        a.sync();
+        b.sync();
+        assert_eq!(b.data.todos_by_date[&day][0].id, id);
+
+        a.data.todos_by_date.get_mut(&day).unwrap()[0].text = "Read, on a".to_owned();
+        b.data.todos_by_date.get_mut(&day).unwrap()[0].text = "Read, on b".to_owned();
+        a.sync();
+        assert_eq!(b.sync().conflicts, 1);
+
+        // B restarts before the conflict is reviewed.
+        let mut b = Device {
+            sync: FolderSync::open(folder.clone(), "b", &b.stats).unwrap(),
+            ..b
+        };
+        assert_eq!(b.sync.conflicts.len(), 1);
+        assert_eq!(b.sync().conflicts, 1);
+        let command = take_theirs(&b.sync.conflicts, &b.data);
+        b.history.execute(command, &mut b.data);
+        assert_eq!(b.data.todos_by_date[&day][0].text, "Read, on a");
+
+        // Once resolved, it stays resolved.
+        b.sync.conflicts.clear();
+        b.sync.write(&b.data, &b.stats, true).unwrap();
+        let b = FolderSync::open(folder, "b", &b.stats).unwrap();
+        assert!(b.conflicts.is_empty());
+    }
+
+    #[test]
+    fn merged_changes_cant_be_undone() {
+        let _turn = profiles::test_profile("test-sync-undo");
+        let folder = test_folder("focushub-test-sync-undo");
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let mut a = Device::open(&folder, "a");
+        let mut b = Device::open(&folder, "b");
+
+        a.data
+            .todos_by_date
+            .insert(day, vec![TodoItem::new("Read".to_owned(), None)]);
+        a.sync();
+        let other_day = day.succ_opt().unwrap();
+        let own = Command::AddTodo {
+            task: TaskRef {
+                list: TaskList::Date(other_day),
+                index: 0,
+            },
+            item: TodoItem::new("Write".to_owned(), None),
+        };
+        b.history.execute(own, &mut b.data);
+        b.sync();
+        assert_eq!(b.data.todos_by_date[&day].len(), 1);
+        assert_eq!(b.data.todos_by_date[&other_day].len(), 1);
+        assert_eq!(b.history.undo_label(), None);
+    }
+
+    #[test]
+    fn another_folder_starts_a_new_base() {
+        let _turn = profiles::test_profile("test-sync-folders");
+        let first = test_folder("focushub-test-sync-first");
+        let second = test_folder("focushub-test-sync-second");
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let mut a = Device::open(&first, "a");
+        let mut b = Device::open(&first, "b");
+
+        a.data
+            .todos_by_date
+            .insert(day, vec![TodoItem::new("Read".to_owned(), None)]);
+        a.sync();
+        b.sync();
+
+        let b = FolderSync::open(second, "b", &b.stats).unwrap();
+        assert!(b.base.peers.is_empty());
+    }
+}
@@ -1,5 +1,5 @@
-use crate::app_data::Stats;
-use chrono::{Datelike, Local};
+use crate::app_data::{FocusSession, Stats};
+use chrono::{Datelike, Local, TimeDelta};
 use rodio::{OutputStreamHandle, Sink, Source, source::SineWave};
 use std::time::{Duration, Instant};
 
@@ -15,6 +15,13 @@ pub enum TimerState {
     Running,
 }
 
+/// The task that work time is currently being attributed to.
+#[derive(PartialEq, Clone, Copy)]
+pub struct FocusTarget {
+    pub task: u64,
+    pub project: Option<u64>,
+}
+
 pub struct StudyTimer {
     pub work_duration: Duration,
     pub break_duration: Duration,
@@ -24,8 +31,10 @@ pub struct StudyTimer {
     pub time_remaining: Duration,
     pub current_loop: u32,
     pub stats: Stats,
+    pub focus: Option<FocusTarget>,
     last_tick: Option<Instant>,
     pending_study_time: Duration,
+    session: Option<FocusSession>,
 }
 
 impl StudyTimer {
@@ -44,8 +53,10 @@ impl StudyTimer {
             timer_state: TimerState::Paused,
             time_remaining: work_duration,
             current_loop: 1,
+            focus: None,
             last_tick: None,
             pending_study_time: Duration::ZERO,
+            session: None,
         }
     }
 
@@ -79,6 +90,7 @@ impl StudyTimer {
                 let today = Local::now().date_naive();
                 *self.stats.daily_study_seconds.entry(today).or_insert(0) += whole_seconds;
                 self.pending_study_time -= Duration::from_secs(whole_seconds);
+                self.record_focus(whole_seconds);
             }
         }
 
@@ -92,6 +104,7 @@ impl StudyTimer {
                     .daily_study_seconds
                     .entry(Local::now().date_naive())
                     .or_insert(0) += self.time_remaining.as_secs();
+                self.record_focus(self.time_remaining.as_secs());
             }
             self.time_remaining = Duration::ZERO;
             self.switch_session();
@@ -107,12 +120,14 @@ impl StudyTimer {
             }
             TimerState::Running => {
                 self.last_tick = None;
+                self.finish_session();
                 TimerState::Paused
             }
         };
     }
 
     pub fn reset(&mut self) {
+        self.finish_session();
         self.timer_state = TimerState::Paused;
         self.timer_mode = TimerMode::Work;
         self.time_remaining = self.work_duration;
@@ -123,11 +138,12 @@ impl StudyTimer {
     fn switch_session(&mut self) {
         match self.timer_mode {
             TimerMode::Work => {
+                self.finish_session();
                 self.timer_mode = TimerMode::Break;
                 self.time_remaining = self.break_duration;
             }
             TimerMode::Break => {
-                self.log_streak();
+                self.log_session();
                 if self.current_loop >= self.total_loops {
                     self.reset();
                     return;
@@ -140,11 +156,35 @@ impl StudyTimer {
         self.last_tick = Some(Instant::now());
     }
 
-    fn log_streak(&mut self) {
+    // Adds work time to the open focus session, starting a new one if the
+    // focused task changed since the session began.
+    fn record_focus(&mut self, seconds: u64) {
+        let task = self.focus.map(|f| f.task);
+        if self.session.as_ref().is_some_and(|s| s.task != task) {
+            self.finish_session();
+        }
+        let project = self.focus.and_then(|f| f.project);
+        let session = self.session.get_or_insert_with(|| FocusSession {
+            start: Local::now().naive_local() - TimeDelta::seconds(seconds as i64),
+            seconds: 0,
+            task,
+            project,
+        });
+        session.seconds += seconds;
+    }
+
+    /// Closes the open focus session, if any, and appends it to the session log.
+    pub fn finish_session(&mut self) {
+        if let Some(session) = self.session.take().filter(|s| s.seconds > 0) {
+            self.stats.sessions.push(session);
+        }
+    }
+
+    fn log_session(&mut self) {
         let today = Local::now().date_naive();
-        *self.stats.daily_streaks.entry(today).or_insert(0) += 1;
+        *self.stats.daily_sessions.entry(today).or_insert(0) += 1;
         let month_key = format!("{}-{}", today.year(), today.month());
-        *self.stats.monthly_streaks.entry(month_key).or_insert(0) += 1;
+        *self.stats.monthly_sessions.entry(month_key).or_insert(0) += 1;
     }
 
     pub fn get_session_switch_messages(&self) -> (&'static str, &'static str) {
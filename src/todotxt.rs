@@ -0,0 +1,516 @@
+//! Reading and writing todo.txt files (<http://todotxt.org>).
+//!
+//! Every dated task becomes one line:
+//!
+//! ```text
+//! x 2025-01-03 2025-01-01 Read chapter 3 @library +Thesis due:2025-01-02 id:1735812345678901
+//! (A) 2025-01-01 Email the supervisor +Thesis due:2025-01-02 id:1735812345678902
+//! ```
+//!
+//! `due:` is the day the task is scheduled on and `id:` ties the line back to
+//! its task, so importing a file this app wrote updates tasks instead of
+//! duplicating them. Contexts and any other tags stay part of the task's text.
+
+use crate::app_data::{AppData, FIRST_LEGACY_ID, STATUS_DONE, STATUS_TODO, TodoItem};
+use crate::history::Command;
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// How often a synced file is checked for changes made by other programs.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// One line of a todo.txt file.
+#[derive(Debug, Default)]
+pub struct TxtTask {
+    pub id: Option<u64>,
+    pub text: String,
+    pub done: bool,
+    pub priority: Option<char>,
+    pub completed_on: Option<NaiveDate>,
+    pub created_on: Option<NaiveDate>,
+    pub due: Option<NaiveDate>,
+    /// `+project` names, without the `+`.
+    pub projects: Vec<String>,
+}
+
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+fn parse_priority(word: &str) -> Option<char> {
+    let mut chars = word.strip_prefix('(')?.strip_suffix(')')?.chars();
+    let priority = chars.next().filter(char::is_ascii_uppercase)?;
+    chars.next().is_none().then_some(priority)
+}
+
+/// Parses one line, or returns `None` for blank lines.
+pub fn parse_line(line: &str) -> Option<TxtTask> {
+    let mut words = line.split_whitespace().peekable();
+    words.peek()?;
+    let mut task = TxtTask::default();
+
+    if words.peek() == Some(&"x") {
+        words.next();
+        task.done = true;
+        task.completed_on = words.peek().and_then(|w| parse_date(w));
+        if task.completed_on.is_some() {
+            words.next();
+        }
+    } else if let Some(priority) = words.peek().and_then(|w| parse_priority(w)) {
+        task.priority = Some(priority);
+        words.next();
+    }
+    task.created_on = words.peek().and_then(|w| parse_date(w));
+    if task.created_on.is_some() {
+        words.next();
+    }
+
+    let mut text = Vec::new();
+    for word in words {
+        match word.split_once(':') {
+            Some(("due", value)) if parse_date(value).is_some() => task.due = parse_date(value),
+            Some(("id", value)) if value.parse::<u64>().is_ok() => task.id = value.parse().ok(),
+            Some(("pri", value)) if parse_priority(&format!("({value})")).is_some() => {
+                task.priority = value.chars().next();
+            }
+            _ => {
+                if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+                    task.projects.push(project.to_owned());
+                }
+                text.push(word);
+            }
+        }
+    }
+    task.text = text.join(" ");
+    Some(task)
+}
+
+pub fn parse(text: &str) -> Vec<TxtTask> {
+    text.lines().filter_map(parse_line).collect()
+}
+
+// Project names can hold spaces, `+project` tags can't.
+fn project_tag(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+// Tasks don't store a creation date, but their ids are creation timestamps,
+// except for the ids given to tasks saved before there were ids.
+fn created_on(task: &TodoItem) -> Option<NaiveDate> {
+    if task.id >= FIRST_LEGACY_ID {
+        return None;
+    }
+    DateTime::from_timestamp_micros(task.id as i64)
+        .filter(|time| time.timestamp() > 0)
+        .map(|time| time.with_timezone(&Local).date_naive())
+        // Only four-digit years are read back as a date.
+        .filter(|date| (1000..=9999).contains(&date.year()))
+}
+
+fn format_line(data: &AppData, date: NaiveDate, task: &TodoItem) -> String {
+    let mut words = Vec::new();
+    if task.is_done() {
+        words.push("x".to_owned());
+        words.push(task.completed_on.unwrap_or(date).to_string());
+    } else if let Some(priority) = task.priority {
+        words.push(format!("({priority})"));
+    }
+    if let Some(created) = created_on(task) {
+        words.push(created.to_string());
+    }
+    words.push(task.text.replace('\n', " "));
+    if let Some(project) = task.project.and_then(|id| data.project(id)) {
+        let tag = format!("+{}", project_tag(&project.name));
+        if !task.text.split_whitespace().any(|w| w == tag) {
+            words.push(tag);
+        }
+    }
+    if task.is_done()
+        && let Some(priority) = task.priority
+    {
+        words.push(format!("pri:{priority}"));
+    }
+    words.push(format!("due:{date}"));
+    words.push(format!("id:{}", task.id));
+    words.join(" ")
+}
+
+/// Writes every dated task, earliest day first.
+pub fn export(data: &AppData) -> String {
+    let mut dates: Vec<&NaiveDate> = data.todos_by_date.keys().collect();
+    dates.sort();
+    let mut out = String::new();
+    for date in dates {
+        for task in &data.todos_by_date[date] {
+            out += &format_line(data, *date, task);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Counts of what an import changed.
+#[derive(Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl ImportSummary {
+    pub fn is_empty(&self) -> bool {
+        self.added + self.updated + self.removed == 0
+    }
+}
+
+// Applies one line's fields to a task, returning whether anything changed.
+fn update_task(item: &mut TodoItem, line: &TxtTask, project: Option<u64>) -> bool {
+    let before = item.clone();
+    item.text = line.text.clone();
+    item.priority = line.priority;
+    if line.done != item.is_done() {
+        // Reopened tasks go back to "To Do"; other columns are kept as they are.
+        item.status = if line.done { STATUS_DONE } else { STATUS_TODO };
+    }
+    item.completed_on = if line.done {
+        line.completed_on.or(item.completed_on)
+    } else {
+        None
+    };
+    if project.is_some() {
+        item.project = project;
+    }
+    item.text != before.text
+        || item.priority != before.priority
+        || item.status != before.status
+        || item.completed_on != before.completed_on
+        || item.project != before.project
+}
+
+/// Works out the task lists that result from reading `lines` into `data`.
+///
+/// Lines are matched to tasks by `id:`; the rest become new tasks on their due
+/// date, else their creation date, else `today`. Tasks whose ids are in
+/// `synced` but have no line any more were deleted from the file and are
+/// removed. Returns `None` when nothing would change.
+pub fn merge(
+    data: &AppData,
+    lines: Vec<TxtTask>,
+    synced: &HashSet<u64>,
+    today: NaiveDate,
+) -> Option<(Command, ImportSummary)> {
+    let mut schedule = data.todos_by_date.clone();
+    let mut summary = ImportSummary::default();
+    let project_id = |line: &TxtTask| {
+        data.projects
+            .iter()
+            .find(|p| {
+                line.projects
+                    .iter()
+                    .any(|tag| tag.eq_ignore_ascii_case(&project_tag(&p.name)))
+            })
+            .map(|p| p.id)
+    };
+
+    let mut seen = HashSet::new();
+    for mut line in lines {
+        let project = project_id(&line);
+        // The tag naming the task's project is stored as the project, not as text.
+        if let Some(project) = project.and_then(|id| data.project(id)) {
+            let tag = project_tag(&project.name);
+            line.text = line
+                .text
+                .split(' ')
+                .filter(|w| {
+                    !w.strip_prefix('+')
+                        .is_some_and(|t| t.eq_ignore_ascii_case(&tag))
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+        }
+
+        let found = line.id.filter(|id| seen.insert(*id)).and_then(|id| {
+            schedule.iter().find_map(|(date, tasks)| {
+                let index = tasks.iter().position(|t| t.id == id)?;
+                Some((*date, index))
+            })
+        });
+        if let Some((date, index)) = found {
+            let item = &mut schedule.get_mut(&date).unwrap()[index];
+            let mut changed = update_task(item, &line, project);
+            if let Some(due) = line.due
+                && due != date
+            {
+                let item = schedule.get_mut(&date).unwrap().remove(index);
+                schedule.entry(due).or_default().push(item);
+                changed = true;
+            }
+            if changed {
+                summary.updated += 1;
+            }
+            continue;
+        }
+
+        let mut item = TodoItem::new(String::new(), None);
+        update_task(&mut item, &line, project);
+        let date = line.due.or(line.created_on).unwrap_or(today);
+        if item.is_done() {
+            item.completed_on.get_or_insert(date);
+        }
+        schedule.entry(date).or_default().push(item);
+        summary.added += 1;
+    }
+
+    for tasks in schedule.values_mut() {
+        let before = tasks.len();
+        tasks.retain(|t| !synced.contains(&t.id) || seen.contains(&t.id));
+        summary.removed += before - tasks.len();
+    }
+
+    (!summary.is_empty()).then(|| {
+        let command = Command::ReplaceSchedule {
+            old: data.todos_by_date.clone(),
+            new: schedule,
+        };
+        (command, summary)
+    })
+}
+
+/// Keeps a todo.txt file and the dated task lists in step.
+///
+/// Edits made in the app rewrite the file; edits made to the file by other
+/// programs are read back in, including deleted lines.
+pub struct LiveSync {
+    path: PathBuf,
+    // Modification time and size of the file when it was last read or written.
+    stamp: Option<(SystemTime, u64)>,
+    // Ids of the tasks the file held after the last read or write.
+    synced: HashSet<u64>,
+    // The history revision the file was last written at.
+    revision: Option<u64>,
+    last_check: Option<Instant>,
+}
+
+impl LiveSync {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            stamp: None,
+            synced: HashSet::new(),
+            revision: None,
+            last_check: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn current_stamp(&self) -> io::Result<(SystemTime, u64)> {
+        let metadata = fs::metadata(&self.path)?;
+        Ok((metadata.modified()?, metadata.len()))
+    }
+
+    /// Reads the file back in if another program changed it since it was last
+    /// seen. A missing file is left alone; the next write recreates it.
+    pub fn poll(
+        &mut self,
+        data: &AppData,
+        today: NaiveDate,
+    ) -> io::Result<Option<(Command, ImportSummary)>> {
+        if self.last_check.is_some_and(|t| t.elapsed() < POLL_INTERVAL) {
+            return Ok(None);
+        }
+        self.last_check = Some(Instant::now());
+        let stamp = match self.current_stamp() {
+            Ok(stamp) => stamp,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if self.stamp == Some(stamp) {
+            return Ok(None);
+        }
+        let lines = parse(&fs::read_to_string(&self.path)?);
+        self.stamp = Some(stamp);
+        let ids: HashSet<u64> = lines.iter().filter_map(|l| l.id).collect();
+        let merged = merge(data, lines, &self.synced, today);
+        self.synced = ids;
+        // Rewrite the file so new lines get their ids.
+        self.revision = None;
+        Ok(merged)
+    }
+
+    /// Rewrites the file if the tasks changed since it was last written.
+    pub fn write(&mut self, data: &AppData, revision: u64) -> io::Result<()> {
+        if self.revision == Some(revision) {
+            return Ok(());
+        }
+        fs::write(&self.path, export(data))?;
+        self.stamp = Some(self.current_stamp()?);
+        self.synced = data
+            .todos_by_date
+            .values()
+            .flatten()
+            .map(|t| t.id)
+            .collect();
+        self.revision = Some(revision);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_data::Project;
+    use crate::history::History;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    fn apply(data: &mut AppData, text: &str, synced: &HashSet<u64>) -> ImportSummary {
+        let (command, summary) = merge(data, parse(text), synced, day(1)).unwrap();
+        History::default().execute(command, data);
+        summary
+    }
+
+    #[test]
+    fn lines_are_read_field_by_field() {
+        let task = parse_line(
+            "x 2025-01-03 2025-01-01 Read chapter 3 @library +Thesis due:2025-01-02 pri:A id:42",
+        )
+        .unwrap();
+        assert!(task.done);
+        assert_eq!(task.completed_on, NaiveDate::from_ymd_opt(2025, 1, 3));
+        assert_eq!(task.created_on, NaiveDate::from_ymd_opt(2025, 1, 1));
+        assert_eq!(task.due, NaiveDate::from_ymd_opt(2025, 1, 2));
+        assert_eq!((task.priority, task.id), (Some('A'), Some(42)));
+        assert_eq!(task.text, "Read chapter 3 @library +Thesis");
+        assert_eq!(task.projects, ["Thesis"]);
+
+        let task = parse_line("(B) Call home due:soon id:x").unwrap();
+        assert_eq!(task.priority, Some('B'));
+        assert_eq!((task.due, task.id), (None, None));
+        assert_eq!(task.text, "Call home due:soon id:x");
+
+        // Only a leading "(A)" is a priority, and "x" only marks done first.
+        let task = parse_line("Buy (A) x-ray film").unwrap();
+        assert_eq!((task.priority, task.done), (None, false));
+        assert!(parse_line("   ").is_none());
+    }
+
+    #[test]
+    fn exported_lines_read_back_as_the_same_tasks() {
+        let mut data = AppData::default();
+        data.projects.push(Project {
+            id: 7,
+            name: "Big Thesis".to_owned(),
+            tasks: Vec::new(),
+        });
+        let mut done = TodoItem::new("Read chapter 3".to_owned(), Some(7));
+        done.status = STATUS_DONE;
+        done.completed_on = Some(day(3));
+        done.priority = Some('A');
+        let mut open = TodoItem::new("Email the supervisor".to_owned(), None);
+        open.priority = Some('C');
+        data.todos_by_date.insert(day(2), vec![done, open]);
+
+        let exported = export(&data);
+        assert!(exported.contains("x 2025-03-03 "));
+        assert!(exported.contains("+Big-Thesis pri:A due:2025-03-02"));
+        assert!(exported.contains("(C) "));
+        assert!(merge(&data, parse(&exported), &HashSet::new(), day(1)).is_none());
+
+        let mut other = AppData {
+            projects: data.projects.clone(),
+            ..AppData::default()
+        };
+        apply(&mut other, &exported, &HashSet::new());
+        // Everything but the ids, which are new in another profile.
+        let without_ids = |data: &AppData| {
+            let mut schedule = data.todos_by_date.clone();
+            schedule.values_mut().flatten().for_each(|t| t.id = 0);
+            serde_json::to_value(schedule).unwrap()
+        };
+        assert_eq!(without_ids(&other), without_ids(&data));
+    }
+
+    #[test]
+    fn tasks_from_before_ids_round_trip_without_a_creation_date() {
+        let mut data: AppData = serde_json::from_str(
+            r#"{"todos_by_date": {"2024-05-01": [{"text": "Read chapter 3", "completed": false}]},
+                "stats": {}, "rewards": []}"#,
+        )
+        .unwrap();
+        data.migrate_tasks();
+        let task = &data.todos_by_date[&NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()][0];
+        assert!(task.id >= FIRST_LEGACY_ID);
+
+        let exported = export(&data);
+        assert_eq!(
+            exported,
+            format!("Read chapter 3 due:2024-05-01 id:{}\n", task.id)
+        );
+        let line = parse_line(exported.trim_end()).unwrap();
+        assert_eq!(line.text, "Read chapter 3");
+        assert!(line.projects.is_empty());
+        // Read back, as live sync does, nothing changes.
+        let synced = HashSet::from([task.id]);
+        assert!(merge(&data, parse(&exported), &synced, day(1)).is_none());
+    }
+
+    #[test]
+    fn edits_in_the_file_update_and_move_tasks() {
+        let mut data = AppData::default();
+        let task = TodoItem::new("Write".to_owned(), None);
+        let id = task.id;
+        data.todos_by_date.insert(day(2), vec![task]);
+
+        let summary = apply(
+            &mut data,
+            &format!("x 2025-03-05 Write more pri:B due:2025-03-04 id:{id}"),
+            &HashSet::new(),
+        );
+        assert_eq!((summary.added, summary.updated), (0, 1));
+        assert!(data.todos_by_date[&day(2)].is_empty());
+        let task = &data.todos_by_date[&day(4)][0];
+        assert_eq!(
+            (task.text.as_str(), task.priority),
+            ("Write more", Some('B'))
+        );
+        assert!(task.is_done());
+        assert_eq!(task.completed_on, Some(day(5)));
+
+        // New lines without a date land on today.
+        let text = format!("Plan\n{}", export(&data));
+        let summary = apply(&mut data, &text, &HashSet::new());
+        assert_eq!(summary.added, 1);
+        assert_eq!(data.todos_by_date[&day(1)][0].text, "Plan");
+    }
+
+    #[test]
+    fn lines_deleted_from_a_synced_file_remove_their_tasks() {
+        let mut data = AppData::default();
+        let (kept, deleted, never_synced) = (
+            TodoItem::new("Kept".to_owned(), None),
+            TodoItem::new("Deleted".to_owned(), None),
+            TodoItem::new("Added since".to_owned(), None),
+        );
+        let synced = HashSet::from([kept.id, deleted.id]);
+        let line = format_line(&data, day(2), &kept);
+        data.todos_by_date
+            .insert(day(2), vec![kept, deleted, never_synced]);
+
+        let summary = apply(&mut data, &line, &synced);
+        assert_eq!(summary.removed, 1);
+        let texts: Vec<&str> = data.todos_by_date[&day(2)]
+            .iter()
+            .map(|t| t.text.as_str())
+            .collect();
+        assert_eq!(texts, ["Kept", "Added since"]);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::achievements::ACHIEVEMENTS;
+use crate::app_data::{AppData, Stats};
+use crate::streaks::StreakSummary;
+use eframe::egui;
+
+pub fn draw_achievements_window(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    app_data: &AppData,
+    stats: &Stats,
+    streak: &StreakSummary,
+) {
+    let mut open = *is_open;
+    egui::Window::new("Achievements")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .default_width(360.0)
+        .show(ctx, |ui| {
+            ui.heading(format!(
+                "🏅 {} of {} unlocked",
+                app_data.achievements.len(),
+                ACHIEVEMENTS.len()
+            ));
+            ui.separator();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for achievement in ACHIEVEMENTS {
+                    let unlocked = app_data.achievements.get(achievement.id);
+                    egui::Frame::group(ui.style()).show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        ui.horizontal(|ui| {
+                            let icon = egui::RichText::new(achievement.icon).size(28.0);
+                            ui.label(if unlocked.is_some() {
+                                icon
+                            } else {
+                                icon.color(ui.visuals().weak_text_color())
+                            });
+                            ui.vertical(|ui| {
+                                ui.label(egui::RichText::new(achievement.name).strong());
+                                ui.label(achievement.description);
+                                match unlocked {
+                                    Some(time) => {
+                                        ui.weak(format!(
+                                            "Unlocked {}",
+                                            time.format("%B %-d, %Y at %H:%M")
+                                        ));
+                                    }
+                                    None => {
+                                        let target = achievement.rule.target();
+                                        let current = achievement
+                                            .rule
+                                            .measure(app_data, stats, streak)
+                                            .min(target);
+                                        ui.add(
+                                            egui::ProgressBar::new(current as f32 / target as f32)
+                                                .desired_width(200.0)
+                                                .text(format!("{current} / {target}")),
+                                        );
+                                    }
+                                }
+                            });
+                        });
+                    });
+                }
+            });
+        });
+    *is_open = open;
+}
@@ -0,0 +1,210 @@
+use crate::app_data::{
+    self, AppData, BoardColumn, STATUS_DONE, STATUS_TODO, Stats, TaskList, TaskRef,
+};
+use crate::history::Command;
+use crate::ui::format_duration;
+use crate::ui::inline_edit::{self, InlineEdit};
+use chrono::NaiveDate;
+use eframe::egui;
+
+/// Which list the board shows, plus column editing inputs.
+#[derive(Default)]
+pub struct BoardState {
+    /// The project whose backlog is shown, or `None` for the selected day.
+    pub project: Option<u64>,
+    pub new_column_input: String,
+    pub inline_edit: Option<InlineEdit>,
+}
+
+pub fn draw_board_window(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    app_data: &AppData,
+    stats: &Stats,
+    selected_date: NaiveDate,
+    state: &mut BoardState,
+    commands: &mut Vec<Command>,
+) {
+    let mut open = *is_open;
+    egui::Window::new("Task Board")
+        .open(&mut open)
+        .default_width(600.0)
+        .default_height(400.0)
+        .resizable(true)
+        .show(ctx, |ui| {
+            if state
+                .project
+                .is_some_and(|id| app_data.project(id).is_none())
+            {
+                state.project = None;
+            }
+            let day_label = selected_date.format("%A, %B %-d").to_string();
+
+            ui.horizontal(|ui| {
+                ui.label("Show:");
+                let selected_text = match state.project.and_then(|id| app_data.project(id)) {
+                    Some(project) => format!("📁 {}", project.name),
+                    None => format!("📅 {day_label}"),
+                };
+                egui::ComboBox::from_id_salt("board_scope")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut state.project, None, format!("📅 {day_label}"));
+                        for project in &app_data.projects {
+                            ui.selectable_value(
+                                &mut state.project,
+                                Some(project.id),
+                                format!("📁 {}", project.name),
+                            );
+                        }
+                    });
+
+                ui.separator();
+                let response = ui
+                    .text_edit_singleline(&mut state.new_column_input)
+                    .on_hover_text("New column name (Press Enter to add)");
+                if response.lost_focus()
+                    && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                    && !state.new_column_input.trim().is_empty()
+                {
+                    // Keep "Done" as the last column.
+                    let index = app_data
+                        .board_columns
+                        .iter()
+                        .position(|c| c.id == STATUS_DONE)
+                        .unwrap_or(app_data.board_columns.len());
+                    commands.push(Command::AddColumn {
+                        index,
+                        column: BoardColumn {
+                            id: app_data::new_id(),
+                            name: state.new_column_input.trim().to_owned(),
+                        },
+                    });
+                    state.new_column_input.clear();
+                }
+            });
+            ui.separator();
+
+            let list = match state.project {
+                Some(id) => TaskList::Project(id),
+                None => TaskList::Date(selected_date),
+            };
+            let tasks = app_data.tasks(list);
+            let task_seconds = stats.task_seconds();
+
+            // Tasks whose column was deleted fall back to "To Do".
+            let column_of = |status: u64| {
+                if app_data.board_columns.iter().any(|c| c.id == status) {
+                    status
+                } else {
+                    STATUS_TODO
+                }
+            };
+
+            let columns = &app_data.board_columns;
+            ui.columns(columns.len(), |uis| {
+                for (index, (ui, column)) in uis.iter_mut().zip(columns).enumerate() {
+                    let cards: Vec<_> = tasks
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, t)| column_of(t.status) == column.id)
+                        .collect();
+
+                    ui.horizontal(|ui| {
+                        let id = egui::Id::new(("board_column_edit", column.id));
+                        let title =
+                            egui::RichText::new(format!("{} ({})", column.name, cards.len()))
+                                .strong();
+                        if let Some(new) = inline_edit::editable_label(
+                            ui,
+                            id,
+                            title,
+                            &column.name,
+                            &mut state.inline_edit,
+                        ) {
+                            commands.push(Command::RenameColumn {
+                                index,
+                                old: column.name.clone(),
+                                new,
+                            });
+                        }
+
+                        // Only empty custom columns can be removed, so no task loses its status.
+                        let removable = column.id != STATUS_TODO
+                            && column.id != STATUS_DONE
+                            && !app_data.all_tasks().any(|t| t.status == column.id);
+                        if removable
+                            && ui
+                                .small_button("❌")
+                                .on_hover_text("Remove column")
+                                .clicked()
+                        {
+                            commands.push(Command::DeleteColumn {
+                                index,
+                                column: column.clone(),
+                            });
+                        }
+                    });
+
+                    let frame = egui::Frame::default().inner_margin(4.0).rounding(4.0);
+                    let (_, dropped) = ui.dnd_drop_zone::<TaskRef, ()>(frame, |ui| {
+                        ui.set_min_size(egui::vec2(ui.available_width(), 120.0));
+                        for (i, todo) in cards {
+                            let task = TaskRef { list, index: i };
+                            let drag_id = egui::Id::new(("board_card", todo.id));
+                            ui.dnd_drag_source(drag_id, task, |ui| {
+                                egui::Frame::group(ui.style()).show(ui, |ui| {
+                                    ui.set_width(ui.available_width());
+                                    let blockers = app_data.open_blockers(todo);
+                                    if blockers.is_empty() || todo.is_done() {
+                                        ui.label(&todo.text);
+                                    } else {
+                                        let names: Vec<_> =
+                                            blockers.iter().map(|b| b.text.as_str()).collect();
+                                        ui.label(
+                                            egui::RichText::new(format!("⛔ {}", todo.text))
+                                                .color(ui.visuals().weak_text_color()),
+                                        )
+                                        .on_hover_text(format!("Blocked by: {}", names.join(", ")));
+                                    }
+                                    if let (TaskList::Date(_), Some(project)) =
+                                        (list, todo.project.and_then(|id| app_data.project(id)))
+                                    {
+                                        ui.label(
+                                            egui::RichText::new(format!("📁 {}", project.name))
+                                                .weak(),
+                                        );
+                                    }
+                                    let seconds = task_seconds.get(&todo.id).cloned().unwrap_or(0);
+                                    if seconds > 0 {
+                                        ui.label(
+                                            egui::RichText::new(format!(
+                                                "⏱ {}",
+                                                format_duration(seconds)
+                                            ))
+                                            .small(),
+                                        );
+                                    }
+                                });
+                            });
+                        }
+                    });
+
+                    if let Some(task) = dropped
+                        && let Some(todo) = app_data.tasks(task.list).get(task.index)
+                        // As in the task list, blocked tasks can't be finished.
+                        && (column.id != STATUS_DONE || app_data.open_blockers(todo).is_empty())
+                        && column_of(todo.status) != column.id
+                    {
+                        commands.push(Command::SetStatus {
+                            task: *task,
+                            old: todo.status,
+                            new: column.id,
+                            old_completed_on: todo.completed_on,
+                        });
+                    }
+                }
+            });
+        });
+    *is_open = open;
+}
@@ -1,117 +1,608 @@
-use crate::app_data::TodoItem;
-use chrono::{Datelike, Local, Month, NaiveDate};
+use crate::app_data::{self, AppData, CalendarEvent, Stats, TaskList, TaskRef};
+use crate::goals;
+use crate::history::Command;
+use crate::ui::format_duration;
+use chrono::{Datelike, Days, Local, Month, NaiveDate};
 use eframe::egui;
 use num_traits::FromPrimitive;
-use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CalendarView {
+    Month,
+    Week,
+    Agenda,
+    Year,
+}
+
+/// Which period the calendar shows and how.
+pub struct CalendarState {
+    /// A day inside the shown month or week, or the first day of the agenda.
+    pub date: NaiveDate,
+    pub view: CalendarView,
+    pub agenda_days: u32,
+}
+
+impl CalendarState {
+    pub fn new(today: NaiveDate) -> Self {
+        Self {
+            date: today,
+            view: CalendarView::Month,
+            agenda_days: 14,
+        }
+    }
+
+    // Moves to the next or previous month, week, agenda page or year.
+    fn step(&mut self, forward: bool) {
+        let date = self.date;
+        self.date = match self.view {
+            CalendarView::Month => {
+                let (year, month) = match (forward, date.month()) {
+                    (true, 12) => (date.year() + 1, 1),
+                    (true, month) => (date.year(), month + 1),
+                    (false, 1) => (date.year() - 1, 12),
+                    (false, month) => (date.year(), month - 1),
+                };
+                NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+            }
+            CalendarView::Week | CalendarView::Agenda => {
+                let days = match self.view {
+                    CalendarView::Week => Days::new(7),
+                    _ => Days::new(self.agenda_days as u64),
+                };
+                if forward { date + days } else { date - days }
+            }
+            CalendarView::Year => {
+                let year = if forward {
+                    date.year() + 1
+                } else {
+                    date.year() - 1
+                };
+                NaiveDate::from_ymd_opt(year, 1, 1).unwrap()
+            }
+        };
+    }
+}
 
 pub fn draw_calendar_window(
     ctx: &egui::Context,
     is_open: &mut bool,
-    calendar_date: &mut NaiveDate,
+    state: &mut CalendarState,
     selected_date: &mut NaiveDate,
-    todos_by_date: &HashMap<NaiveDate, Vec<TodoItem>>,
+    app_data: &AppData,
+    stats: &Stats,
+    commands: &mut Vec<Command>,
 ) {
     let mut open = *is_open;
-    let month_str = Month::from_u32(calendar_date.month()).unwrap().name();
-    let window_title = format!("{} {}", month_str, calendar_date.year());
+    let mut dropped = None;
+    let window_title = match state.view {
+        CalendarView::Month => {
+            let month_str = Month::from_u32(state.date.month()).unwrap().name();
+            format!("{} {}", month_str, state.date.year())
+        }
+        CalendarView::Week => {
+            format!("Week of {}", week_start(state.date).format("%B %-d, %Y"))
+        }
+        CalendarView::Agenda => "Agenda".to_owned(),
+        CalendarView::Year => format!("{} Study Heatmap", state.date.year()),
+    };
 
     egui::Window::new(window_title)
+        .id(egui::Id::new("calendar_window"))
         .open(&mut open)
         .collapsible(false)
-        .resizable(false)
+        .resizable(state.view != CalendarView::Month)
         .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut state.view, CalendarView::Month, "Month");
+                ui.selectable_value(&mut state.view, CalendarView::Week, "Week");
+                ui.selectable_value(&mut state.view, CalendarView::Agenda, "Agenda");
+                ui.selectable_value(&mut state.view, CalendarView::Year, "Year");
+                if state.view == CalendarView::Agenda {
+                    ui.separator();
+                    ui.label("Days:");
+                    ui.add(egui::DragValue::new(&mut state.agenda_days).range(1..=90));
+                }
+            });
             ui.horizontal(|ui| {
                 if ui.button("◀").clicked() {
-                    let (year, month) = if calendar_date.month() == 1 {
-                        (calendar_date.year() - 1, 12)
-                    } else {
-                        (calendar_date.year(), calendar_date.month() - 1)
-                    };
-                    *calendar_date = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+                    state.step(false);
                 }
                 if ui.button("Today").clicked() {
                     let today = Local::now().date_naive();
-                    *calendar_date = today;
+                    state.date = today;
                     *selected_date = today;
                 }
                 if ui.button("▶").clicked() {
-                    let (year, month) = if calendar_date.month() == 12 {
-                        (calendar_date.year() + 1, 1)
-                    } else {
-                        (calendar_date.year(), calendar_date.month() + 1)
-                    };
-                    *calendar_date = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+                    state.step(true);
                 }
             });
             ui.separator();
 
-            egui::Grid::new("calendar_grid")
-                .spacing([4.0, 4.0])
-                .show(ui, |ui| {
-                    for day in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"] {
-                        ui.label(egui::RichText::new(day).strong());
-                    }
-                    ui.end_row();
+            dropped = match state.view {
+                CalendarView::Month => draw_month(ui, state.date, selected_date, app_data, stats),
+                CalendarView::Week => draw_week(ui, state.date, selected_date, app_data, stats),
+                CalendarView::Agenda => {
+                    draw_agenda(ui, state.date, state.agenda_days, selected_date, app_data)
+                }
+                CalendarView::Year => {
+                    draw_year(ui, state.date.year(), selected_date, app_data, stats);
+                    None
+                }
+            };
+            if matches!(state.view, CalendarView::Month | CalendarView::Year) {
+                ui.separator();
+                draw_heat_legend(ui, &app_data.settings.heatmap_thresholds);
+            }
+        });
 
-                    let first_day = calendar_date.with_day(1).unwrap();
-                    let weekday_offset = first_day.weekday().num_days_from_monday();
-                    for _ in 0..weekday_offset {
-                        ui.label("");
-                    }
+    if let Some((from, date)) = dropped {
+        let target_len = app_data.tasks(TaskList::Date(date)).len();
+        let to = app_data::drop_position(from, TaskList::Date(date), usize::MAX, target_len);
+        if from != to {
+            commands.push(Command::MoveTodo { from, to });
+        }
+    }
+    *is_open = open;
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday() as u64)
+}
+
+// Highlights a day area while a task is dragged over it and reports a drop.
+fn accept_task_drop(
+    ui: &egui::Ui,
+    response: &egui::Response,
+    date: NaiveDate,
+) -> Option<(TaskRef, NaiveDate)> {
+    if response.dnd_hover_payload::<TaskRef>().is_some() {
+        ui.painter()
+            .rect_stroke(response.rect, 2.0, ui.visuals().selection.stroke);
+    }
+    response
+        .dnd_release_payload::<TaskRef>()
+        .map(|source| (*source, date))
+}
+
+// GitHub-style shades, from "no study" to the highest threshold.
+const HEAT_COLORS: [egui::Color32; 5] = [
+    egui::Color32::from_rgb(22, 27, 34),
+    egui::Color32::from_rgb(14, 68, 41),
+    egui::Color32::from_rgb(0, 109, 50),
+    egui::Color32::from_rgb(38, 166, 65),
+    egui::Color32::from_rgb(57, 211, 83),
+];
+
+// How many of the minute thresholds a day's study time reaches (0-4).
+fn heat_level(seconds: u64, thresholds: &[u32; 4]) -> usize {
+    thresholds
+        .iter()
+        .filter(|&&minutes| seconds >= minutes as u64 * 60)
+        .count()
+}
+
+// Tasks finished and scheduled on `date`.
+fn completion(app_data: &AppData, date: NaiveDate) -> (usize, usize) {
+    let tasks = app_data.tasks(TaskList::Date(date));
+    (tasks.iter().filter(|t| t.is_done()).count(), tasks.len())
+}
+
+fn day_tooltip(ui: &mut egui::Ui, date: NaiveDate, app_data: &AppData, stats: &Stats) {
+    let seconds = stats.daily_study_seconds.get(&date).cloned().unwrap_or(0);
+    let sessions = stats.daily_sessions.get(&date).cloned().unwrap_or(0);
+    let (done, total) = completion(app_data, date);
+    ui.label(egui::RichText::new(date.format("%A, %B %-d, %Y").to_string()).strong());
+    ui.label(format!("Studied: {}", format_duration(seconds)));
+    ui.label(format!("Sessions: {sessions}"));
+    ui.label(format!("Tasks done: {done}/{total}"));
+    if let Some(progress) = goals::daily_progress(stats, &app_data.settings.goals, date) {
+        ui.label(format!("Goal: {}", progress.describe()));
+    }
+    for event in app_data.events_on(date) {
+        ui.label(event_label(event));
+    }
+}
+
+// An imported event as one line, with its start time unless it lasts all day.
+fn event_label(event: &CalendarEvent) -> String {
+    if event.all_day {
+        format!("📌 {}", event.summary)
+    } else {
+        format!("📌 {} {}", event.start.format("%H:%M"), event.summary)
+    }
+}
+
+// A small ring that fills clockwise from the top as the day's goal is approached.
+fn draw_goal_ring(painter: &egui::Painter, center: egui::Pos2, fraction: f32) {
+    const RADIUS: f32 = 4.0;
+    let track = egui::Stroke::new(1.5, egui::Color32::from_gray(90));
+    painter.circle_stroke(center, RADIUS, track);
+    if fraction <= 0.0 {
+        return;
+    }
+    let color = if fraction >= 1.0 {
+        egui::Color32::GOLD
+    } else {
+        egui::Color32::LIGHT_BLUE
+    };
+    let steps = (24.0 * fraction).ceil().max(2.0) as usize;
+    let points = (0..=steps)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * fraction * i as f32 / steps as f32
+                - std::f32::consts::FRAC_PI_2;
+            center + RADIUS * egui::vec2(angle.cos(), angle.sin())
+        })
+        .collect();
+    painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, color)));
+}
+
+fn draw_heat_legend(ui: &mut egui::Ui, thresholds: &[u32; 4]) {
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new("Less").small());
+        for (level, color) in HEAT_COLORS.iter().enumerate() {
+            let (rect, response) =
+                ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+            ui.painter().rect_filled(rect, 2.0, *color);
+            let text = match level {
+                0 => format!("Under {} min", thresholds[0]),
+                _ => format!("{}+ min", thresholds[level - 1]),
+            };
+            response.on_hover_text(text);
+        }
+        ui.label(egui::RichText::new("More").small());
+    });
+}
+
+fn draw_month(
+    ui: &mut egui::Ui,
+    calendar_date: NaiveDate,
+    selected_date: &mut NaiveDate,
+    app_data: &AppData,
+    stats: &Stats,
+) -> Option<(TaskRef, NaiveDate)> {
+    let mut dropped = None;
+    egui::Grid::new("calendar_grid")
+        .spacing([4.0, 4.0])
+        .show(ui, |ui| {
+            for day in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"] {
+                ui.label(egui::RichText::new(day).strong());
+            }
+            ui.end_row();
+
+            let first_day = calendar_date.with_day(1).unwrap();
+            let weekday_offset = first_day.weekday().num_days_from_monday();
+            for _ in 0..weekday_offset {
+                ui.label("");
+            }
+
+            let days_in_month =
+                NaiveDate::from_ymd_opt(calendar_date.year(), calendar_date.month() + 1, 1)
+                    .unwrap_or_else(|| {
+                        NaiveDate::from_ymd_opt(calendar_date.year() + 1, 1, 1).unwrap()
+                    })
+                    .signed_duration_since(first_day)
+                    .num_days() as u32;
+
+            for day in 1..=days_in_month {
+                let current_cell_date = calendar_date.with_day(day).unwrap();
+                let is_today = current_cell_date == Local::now().date_naive();
+                let is_selected = current_cell_date == *selected_date;
+                let (done, total) = completion(app_data, current_cell_date);
+                let studied = stats
+                    .daily_study_seconds
+                    .get(&current_cell_date)
+                    .cloned()
+                    .unwrap_or(0);
+                let level = heat_level(studied, &app_data.settings.heatmap_thresholds);
+
+                let mut frame =
+                    egui::Frame::central_panel(ui.style()).inner_margin(egui::Margin::same(4.0));
+                if level > 0 {
+                    frame = frame.fill(HEAT_COLORS[level]);
+                } else if is_today {
+                    frame = frame.fill(ui.visuals().selection.bg_fill);
+                }
+                if is_selected {
+                    frame = frame.stroke(ui.visuals().widgets.active.bg_stroke);
+                }
 
-                    let days_in_month =
-                        NaiveDate::from_ymd_opt(calendar_date.year(), calendar_date.month() + 1, 1)
-                            .unwrap_or_else(|| {
-                                NaiveDate::from_ymd_opt(calendar_date.year() + 1, 1, 1).unwrap()
-                            })
-                            .signed_duration_since(first_day)
-                            .num_days() as u32;
-
-                    for day in 1..=days_in_month {
-                        let current_cell_date = calendar_date.with_day(day).unwrap();
-                        let is_today = current_cell_date == Local::now().date_naive();
-                        let is_selected = current_cell_date == *selected_date;
-                        let has_todos = todos_by_date
-                            .get(&current_cell_date)
-                            .is_some_and(|v| !v.is_empty());
-
-                        let mut frame = egui::Frame::central_panel(ui.style())
-                            .inner_margin(egui::Margin::same(4.0));
+                let cell = frame.show(ui, |ui| {
+                    ui.centered_and_justified(|ui| {
+                        let mut label = egui::RichText::new(day.to_string());
                         if is_today {
-                            frame = frame.fill(ui.visuals().selection.bg_fill);
+                            label = label.color(ui.visuals().selection.stroke.color).strong();
                         }
-                        if is_selected {
-                            frame = frame.stroke(ui.visuals().widgets.active.bg_stroke);
+                        let response = ui.add(egui::Label::new(label).sense(egui::Sense::click()));
+                        if response.clicked() {
+                            *selected_date = current_cell_date;
                         }
-
-                        frame.show(ui, |ui| {
-                            ui.centered_and_justified(|ui| {
-                                let mut label = egui::RichText::new(day.to_string());
-                                if is_today {
-                                    label =
-                                        label.color(ui.visuals().selection.stroke.color).strong();
-                                }
-                                let response =
-                                    ui.add(egui::Label::new(label).sense(egui::Sense::click()));
-                                if response.clicked() {
-                                    *selected_date = current_cell_date;
-                                }
-                                if has_todos {
-                                    let dot_pos =
-                                        response.rect.center_bottom() + egui::vec2(0.0, -2.0);
-                                    ui.painter().circle_filled(
-                                        dot_pos,
-                                        2.0,
-                                        ui.visuals().text_color(),
-                                    );
-                                }
-                            });
-                        });
-                        if (day + weekday_offset) % 7 == 0 {
-                            ui.end_row();
+                        // A thin bar under the number shows the share of tasks done.
+                        if total > 0 {
+                            let bar = egui::Rect::from_min_size(
+                                response.rect.left_bottom() + egui::vec2(0.0, -2.0),
+                                egui::vec2(response.rect.width(), 2.0),
+                            );
+                            ui.painter()
+                                .rect_filled(bar, 0.0, ui.visuals().weak_text_color());
+                            let mut filled = bar;
+                            filled.set_width(bar.width() * done as f32 / total as f32);
+                            ui.painter().rect_filled(filled, 0.0, HEAT_COLORS[4]);
                         }
-                    }
+                    });
                 });
+                if let Some(progress) =
+                    goals::daily_progress(stats, &app_data.settings.goals, current_cell_date)
+                {
+                    let center = cell.response.rect.right_top() + egui::vec2(-6.0, 6.0);
+                    draw_goal_ring(ui.painter(), center, progress.fraction());
+                }
+                // A dot in the other corner marks days with imported events.
+                if !app_data.events_on(current_cell_date).is_empty() {
+                    let center = cell.response.rect.left_top() + egui::vec2(6.0, 6.0);
+                    ui.painter()
+                        .circle_filled(center, 2.5, egui::Color32::LIGHT_RED);
+                }
+                cell.response
+                    .clone()
+                    .on_hover_ui(|ui| day_tooltip(ui, current_cell_date, app_data, stats));
+
+                // Tasks dragged from the to-do list can be dropped on a day to reschedule.
+                if let Some(drop) = accept_task_drop(ui, &cell.response, current_cell_date) {
+                    dropped = Some(drop);
+                }
+                if (day + weekday_offset) % 7 == 0 {
+                    ui.end_row();
+                }
+            }
         });
-    *is_open = open;
+    dropped
+}
+
+// One column per weekday, listing the day's tasks and focus sessions.
+fn draw_week(
+    ui: &mut egui::Ui,
+    calendar_date: NaiveDate,
+    selected_date: &mut NaiveDate,
+    app_data: &AppData,
+    stats: &Stats,
+) -> Option<(TaskRef, NaiveDate)> {
+    let mut dropped = None;
+    let monday = week_start(calendar_date);
+    let today = Local::now().date_naive();
+
+    ui.columns(7, |columns| {
+        for (offset, ui) in columns.iter_mut().enumerate() {
+            let date = monday + Days::new(offset as u64);
+            let frame = egui::Frame::group(ui.style()).fill(if date == today {
+                ui.visuals().selection.bg_fill
+            } else {
+                egui::Color32::TRANSPARENT
+            });
+            let column = frame.show(ui, |ui| {
+                ui.set_min_size(egui::vec2(ui.available_width(), 160.0));
+                let heading = egui::RichText::new(date.format("%a %-d").to_string()).strong();
+                if ui
+                    .selectable_label(date == *selected_date, heading)
+                    .clicked()
+                {
+                    *selected_date = date;
+                }
+                ui.separator();
+
+                for event in app_data.events_on(date) {
+                    ui.label(egui::RichText::new(event_label(event)).small());
+                }
+                for task in app_data.tasks(TaskList::Date(date)) {
+                    let mark = if task.is_done() { "✔" } else { "•" };
+                    ui.label(format!("{mark} {}", task.text));
+                }
+
+                let sessions: Vec<_> = stats
+                    .sessions
+                    .iter()
+                    .filter(|s| s.start.date() == date)
+                    .collect();
+                if !sessions.is_empty() {
+                    ui.separator();
+                    for session in sessions {
+                        let task = session.task.and_then(|id| app_data.task_by_id(id));
+                        let label = egui::RichText::new(format!(
+                            "🕑 {} · {}m",
+                            session.start.format("%H:%M"),
+                            session.seconds / 60
+                        ))
+                        .small();
+                        let response = ui.label(label);
+                        if let Some(task) = task {
+                            response.on_hover_text(&task.text);
+                        }
+                    }
+                }
+            });
+            if let Some(drop) = accept_task_drop(ui, &column.response, date) {
+                dropped = Some(drop);
+            }
+        }
+    });
+    dropped
+}
+
+// The tasks of the next `days` days, skipping days with nothing scheduled.
+fn draw_agenda(
+    ui: &mut egui::Ui,
+    start: NaiveDate,
+    days: u32,
+    selected_date: &mut NaiveDate,
+    app_data: &AppData,
+) -> Option<(TaskRef, NaiveDate)> {
+    let mut dropped = None;
+    let mut any_tasks = false;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for offset in 0..days {
+            let date = start + Days::new(offset as u64);
+            let tasks = app_data.tasks(TaskList::Date(date));
+            let events = app_data.events_on(date);
+            if tasks.is_empty() && events.is_empty() {
+                continue;
+            }
+            any_tasks = true;
+
+            let day = ui.vertical(|ui| {
+                let heading = egui::RichText::new(date.format("%A, %B %-d").to_string()).strong();
+                if ui
+                    .selectable_label(date == *selected_date, heading)
+                    .clicked()
+                {
+                    *selected_date = date;
+                }
+                for event in events {
+                    ui.label(format!("  {}", event_label(event)));
+                }
+                for task in tasks {
+                    let mark = if task.is_done() { "✔" } else { "•" };
+                    ui.label(format!("  {mark} {}", task.text));
+                }
+            });
+            if let Some(drop) = accept_task_drop(ui, &day.response, date) {
+                dropped = Some(drop);
+            }
+            ui.separator();
+        }
+        if !any_tasks {
+            ui.label(format!("Nothing scheduled in the next {days} days."));
+        }
+    });
+    dropped
+}
+
+// A GitHub-contribution style grid of every day in `year`, one column per week.
+fn draw_year(
+    ui: &mut egui::Ui,
+    year: i32,
+    selected_date: &mut NaiveDate,
+    app_data: &AppData,
+    stats: &Stats,
+) {
+    const CELL: f32 = 11.0;
+    const GAP: f32 = 2.0;
+    let label_width = 30.0;
+
+    let first = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+    let last = NaiveDate::from_ymd_opt(year, 12, 31).unwrap();
+    let grid_start = week_start(first);
+    let weeks = (last - grid_start).num_days() / 7 + 1;
+
+    let size = egui::vec2(
+        label_width + weeks as f32 * (CELL + GAP),
+        14.0 + 7.0 * (CELL + GAP),
+    );
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let text_color = ui.visuals().text_color();
+    let font = egui::FontId::proportional(9.0);
+
+    for (row, name) in [(0, "Mon"), (2, "Wed"), (4, "Fri")] {
+        let pos = rect.min + egui::vec2(0.0, 14.0 + row as f32 * (CELL + GAP));
+        painter.text(pos, egui::Align2::LEFT_TOP, name, font.clone(), text_color);
+    }
+
+    for date in first.iter_days().take_while(|d| *d <= last) {
+        let week = (date - grid_start).num_days() / 7;
+        let row = date.weekday().num_days_from_monday();
+        let min = rect.min
+            + egui::vec2(
+                label_width + week as f32 * (CELL + GAP),
+                14.0 + row as f32 * (CELL + GAP),
+            );
+        let cell = egui::Rect::from_min_size(min, egui::vec2(CELL, CELL));
+
+        if date.day() == 1 {
+            let month = Month::from_u32(date.month()).unwrap().name();
+            painter.text(
+                egui::pos2(cell.min.x, rect.min.y),
+                egui::Align2::LEFT_TOP,
+                &month[..3],
+                font.clone(),
+                text_color,
+            );
+        }
+
+        let studied = stats.daily_study_seconds.get(&date).cloned().unwrap_or(0);
+        let level = heat_level(studied, &app_data.settings.heatmap_thresholds);
+        painter.rect_filled(cell, 2.0, HEAT_COLORS[level]);
+        if date == *selected_date {
+            painter.rect_stroke(cell, 2.0, ui.visuals().selection.stroke);
+        }
+
+        let response = ui.interact(
+            cell,
+            ui.id().with(("year_cell", date)),
+            egui::Sense::click(),
+        );
+        if response.clicked() {
+            *selected_date = date;
+        }
+        response.on_hover_ui(|ui| day_tooltip(ui, date, app_data, stats));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // 2025-03-05 is a Wednesday.
+        assert_eq!(week_start(day(2025, 3, 5)), day(2025, 3, 3));
+        assert_eq!(week_start(day(2025, 3, 3)), day(2025, 3, 3));
+        assert_eq!(week_start(day(2025, 3, 2)), day(2025, 2, 24));
+    }
+
+    #[test]
+    fn each_view_steps_by_its_own_period() {
+        let mut state = CalendarState::new(day(2025, 12, 31));
+        state.step(true);
+        assert_eq!(state.date, day(2026, 1, 1));
+        state.step(false);
+        state.step(false);
+        assert_eq!(state.date, day(2025, 11, 1));
+
+        state.view = CalendarView::Week;
+        state.step(true);
+        assert_eq!(state.date, day(2025, 11, 8));
+
+        state.view = CalendarView::Agenda;
+        state.agenda_days = 30;
+        state.step(false);
+        assert_eq!(state.date, day(2025, 10, 9));
+
+        state.view = CalendarView::Year;
+        state.step(true);
+        assert_eq!(state.date, day(2026, 1, 1));
+    }
+
+    #[test]
+    fn each_threshold_reached_darkens_the_day() {
+        let thresholds = [15, 60, 120, 240];
+        assert_eq!(heat_level(0, &thresholds), 0);
+        assert_eq!(heat_level(15 * 60 - 1, &thresholds), 0);
+        assert_eq!(heat_level(15 * 60, &thresholds), 1);
+        assert_eq!(heat_level(2 * 3600, &thresholds), 3);
+        assert_eq!(heat_level(10 * 3600, &thresholds), HEAT_COLORS.len() - 1);
+    }
+
+    #[test]
+    fn completion_counts_the_days_tasks() {
+        let mut data = AppData::default();
+        let mut done = app_data::TodoItem::new("Read".to_owned(), None);
+        done.status = app_data::STATUS_DONE;
+        let open = app_data::TodoItem::new("Write".to_owned(), None);
+        data.todos_by_date.insert(day(2025, 3, 1), vec![done, open]);
+        assert_eq!(completion(&data, day(2025, 3, 1)), (1, 2));
+        assert_eq!(completion(&data, day(2025, 3, 2)), (0, 0));
+    }
 }
@@ -1,8 +1,18 @@
+use crate::app_data::Goals;
+use crate::goals;
+use crate::streaks::StreakSummary;
 use crate::timer::{StudyTimer, TimerMode, TimerState};
+use chrono::Local;
 use eframe::egui;
 use std::time::Duration;
 
-pub fn draw_central_panel(ctx: &egui::Context, timer: &mut StudyTimer, current_time: &str) {
+pub fn draw_central_panel(
+    ctx: &egui::Context,
+    timer: &mut StudyTimer,
+    current_time: &str,
+    streak: &StreakSummary,
+    goals: &Goals,
+) {
     let panel_frame = egui::Frame {
         inner_margin: egui::Margin::same(10.0),
         fill: egui::Color32::from_rgba_unmultiplied(20, 20, 20, 180),
@@ -55,6 +65,27 @@ pub fn draw_central_panel(ctx: &egui::Context, timer: &mut StudyTimer, current_t
                         timer.reset();
                     }
                 });
+                if streak.current > 0 {
+                    ui.label(format!("🔥 {}-day streak", streak.current));
+                }
+
+                let today = Local::now().date_naive();
+                let daily = goals::daily_progress(&timer.stats, goals, today);
+                let weekly = goals::weekly_progress(&timer.stats, goals, today);
+                for (name, progress) in [("Today", daily), ("This week", weekly)] {
+                    if let Some(progress) = progress {
+                        let text = if progress.met() {
+                            format!("🎯 {name}: {} ✔", progress.describe())
+                        } else {
+                            format!("🎯 {name}: {}", progress.describe())
+                        };
+                        ui.add(
+                            egui::ProgressBar::new(progress.fraction())
+                                .desired_width(200.0)
+                                .text(text),
+                        );
+                    }
+                }
                 ui.add_space(20.0);
             });
 
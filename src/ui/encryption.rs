@@ -0,0 +1,183 @@
+use crate::app_data::AppData;
+use crate::crypto::Key;
+use crate::profiles;
+use crate::storage::Storage;
+use eframe::egui;
+
+// Shorter passphrases are refused outright.
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// The passphrase typed into the unlock screen.
+#[derive(Default)]
+pub struct UnlockState {
+    passphrase: String,
+    error: Option<String>,
+}
+
+/// Asks for the passphrase of an encrypted data file. Returns the data once
+/// the passphrase opens it, leaving `storage` holding the key.
+pub fn draw_unlock_screen(
+    ctx: &egui::Context,
+    state: &mut UnlockState,
+    storage: &mut dyn Storage,
+) -> Option<AppData> {
+    let mut unlocked = None;
+    egui::CentralPanel::default().show(ctx, |ui| {
+        ui.vertical_centered(|ui| {
+            ui.add_space(ui.available_height() / 3.0);
+            ui.heading("🔒 Focus Hub is locked");
+            let profile = profiles::active();
+            if profile == profiles::DEFAULT_PROFILE {
+                ui.label("Enter your passphrase to open your data.");
+            } else {
+                ui.label(format!("Enter the passphrase for {profile}."));
+            }
+            ui.add_space(8.0);
+            let field = ui.add(
+                egui::TextEdit::singleline(&mut state.passphrase)
+                    .password(true)
+                    .desired_width(220.0),
+            );
+            if ui.memory(|m| m.focused().is_none()) {
+                field.request_focus();
+            }
+            let submitted = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Unlock").clicked() || submitted {
+                match storage.unlock(&state.passphrase) {
+                    Ok(result) => unlocked = Some(result),
+                    Err(e) => {
+                        state.error = Some(e.to_string());
+                        state.passphrase.clear();
+                    }
+                }
+            }
+            if let Some(error) = &state.error {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("Could not unlock: {error}"),
+                );
+            }
+        });
+    });
+    unlocked
+}
+
+/// The passphrase fields of the encryption window.
+#[derive(Default)]
+pub struct EncryptionState {
+    current: String,
+    new: String,
+    confirm: String,
+    error: Option<String>,
+}
+
+impl EncryptionState {
+    /// Shows why the last change was refused.
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// What the user asked for in the encryption window.
+pub enum EncryptionAction {
+    /// Encrypt from now on, or switch to a new passphrase.
+    SetPassphrase(Key),
+    TurnOff,
+}
+
+/// Draws the window for turning encryption on or off and changing the
+/// passphrase. `key` is the current one, if the data file is encrypted.
+pub fn draw_encryption_window(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    state: &mut EncryptionState,
+    key: Option<&Key>,
+) -> Option<EncryptionAction> {
+    let mut action = None;
+    egui::Window::new("Encryption")
+        .open(is_open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            match key {
+                Some(_) => ui.label("🔒 Your data file and its backup are encrypted."),
+                None => ui.label("Your data file is stored as plain text."),
+            };
+            ui.weak("Exports and todo.txt files are not encrypted.");
+            ui.weak(
+                "Sync keeps unencrypted copies of your data, so it can't be used while \
+                 encrypted. Encrypting removes the copies it left next to the data file, \
+                 and the WebDAV settings with their password; files already in a sync \
+                 folder stay there.",
+            );
+            ui.weak("Without the passphrase, encrypted data can't be recovered.");
+            ui.separator();
+
+            egui::Grid::new("encryption_form")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    if key.is_some() {
+                        ui.label("Current passphrase:");
+                        ui.add(egui::TextEdit::singleline(&mut state.current).password(true));
+                        ui.end_row();
+                    }
+                    ui.label("New passphrase:");
+                    ui.add(egui::TextEdit::singleline(&mut state.new).password(true));
+                    ui.end_row();
+                    ui.label("Repeat it:");
+                    ui.add(egui::TextEdit::singleline(&mut state.confirm).password(true));
+                    ui.end_row();
+                });
+
+            ui.horizontal(|ui| {
+                let label = if key.is_some() {
+                    "Change Passphrase"
+                } else {
+                    "Encrypt"
+                };
+                if ui.button(label).clicked() {
+                    match new_key(state, key) {
+                        Ok(new) => {
+                            action = Some(EncryptionAction::SetPassphrase(new));
+                            state.clear();
+                        }
+                        Err(e) => state.error = Some(e),
+                    }
+                }
+                if let Some(key) = key
+                    && ui.button("Turn Off Encryption").clicked()
+                {
+                    if key.matches(&state.current) {
+                        action = Some(EncryptionAction::TurnOff);
+                        state.clear();
+                    } else {
+                        state.error = Some("The current passphrase is wrong.".to_owned());
+                    }
+                }
+            });
+            if let Some(error) = &state.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+    action
+}
+
+// Checks the form and stretches the new passphrase into a key.
+fn new_key(state: &EncryptionState, current: Option<&Key>) -> Result<Key, String> {
+    if current.is_some_and(|key| !key.matches(&state.current)) {
+        return Err("The current passphrase is wrong.".to_owned());
+    }
+    if state.new.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Use at least {MIN_PASSPHRASE_LEN} characters for the passphrase."
+        ));
+    }
+    if state.new != state.confirm {
+        return Err("The new passphrases don't match.".to_owned());
+    }
+    Key::new(&state.new).map_err(|e| e.to_string())
+}
@@ -0,0 +1,111 @@
+use crate::app_data::{AppData, Stats};
+use crate::export::{self, ExportFormat};
+use crate::ui::save_in_background;
+use chrono::{Local, NaiveDate};
+use eframe::egui;
+
+/// The choices made in the export window.
+pub struct ExportState {
+    pub format: ExportFormat,
+    pub all_time: bool,
+    pub from: String,
+    pub to: String,
+}
+
+impl Default for ExportState {
+    fn default() -> Self {
+        let today = Local::now().date_naive();
+        Self {
+            format: ExportFormat::Csv,
+            all_time: true,
+            from: today.format("%Y-%m-01").to_string(),
+            to: today.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+impl ExportState {
+    // The inclusive date range to export, or `None` if the dates don't parse.
+    fn dates(&self, app_data: &AppData, stats: &Stats) -> Option<(NaiveDate, NaiveDate)> {
+        if self.all_time {
+            return export::all_time(app_data, stats);
+        }
+        let from = NaiveDate::parse_from_str(self.from.trim(), "%Y-%m-%d").ok()?;
+        let to = NaiveDate::parse_from_str(self.to.trim(), "%Y-%m-%d").ok()?;
+        (from <= to).then_some((from, to))
+    }
+}
+
+pub fn draw_export_window(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    state: &mut ExportState,
+    app_data: &AppData,
+    stats: &Stats,
+) {
+    let mut open = *is_open;
+    egui::Window::new("Export")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label("Exports tasks, focus sessions and daily study totals.");
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("Format:");
+                ui.selectable_value(&mut state.format, ExportFormat::Csv, "CSV");
+                ui.selectable_value(&mut state.format, ExportFormat::Json, "JSON");
+                ui.selectable_value(
+                    &mut state.format,
+                    ExportFormat::Markdown,
+                    "Markdown journal",
+                );
+            });
+            if state.format == ExportFormat::Csv {
+                ui.weak("Writes separate files for tasks, sessions and daily totals.");
+            }
+
+            ui.checkbox(&mut state.all_time, "All time");
+            ui.add_enabled_ui(!state.all_time, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("From");
+                    ui.add(egui::TextEdit::singleline(&mut state.from).desired_width(80.0));
+                    ui.label("to");
+                    ui.add(egui::TextEdit::singleline(&mut state.to).desired_width(80.0));
+                });
+            });
+            ui.weak("Project backlogs have no dates, so they are always included.");
+
+            let dates = state.dates(app_data, stats);
+            if dates.is_none() {
+                let message = if state.all_time {
+                    "There is nothing to export yet."
+                } else {
+                    "Enter dates as YYYY-MM-DD, with the start on or before the end."
+                };
+                ui.colored_label(ui.visuals().error_fg_color, message);
+            }
+
+            ui.separator();
+            if ui
+                .add_enabled(dates.is_some(), egui::Button::new("Export..."))
+                .clicked()
+                && let Some((from, to)) = dates
+            {
+                let data = export::collect(app_data, stats, from, to);
+                let format = state.format;
+                let extension = format.extension();
+                let filter = match format {
+                    ExportFormat::Csv => "CSV",
+                    ExportFormat::Json => "JSON",
+                    ExportFormat::Markdown => "Markdown",
+                };
+                let file_name = format!("focushub_{from}_{to}.{extension}");
+                save_in_background(filter, extension, file_name, move |path| {
+                    data.write(format, path)
+                });
+            }
+        });
+    *is_open = open;
+}
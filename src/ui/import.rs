@@ -0,0 +1,253 @@
+use crate::app_data::{AppData, Stats};
+use crate::import::{self, CsvMapping, DurationUnit, ImportFormat, Preview};
+use crate::ui::format_duration;
+use chrono::Local;
+use eframe::egui;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+// Rows listed in the preview; the counts above it cover the rest.
+const PREVIEW_ROWS: usize = 200;
+
+/// The file being imported and how to read it.
+pub struct ImportState {
+    pub format: ImportFormat,
+    pub mapping: CsvMapping,
+    /// Name and contents of the chosen file.
+    file: Option<(String, String)>,
+    headers: Vec<String>,
+    /// Worked out again whenever the file, format or mapping changes.
+    preview: Option<Preview>,
+    pick: Option<Receiver<(String, String)>>,
+}
+
+impl Default for ImportState {
+    fn default() -> Self {
+        Self {
+            format: ImportFormat::Csv,
+            mapping: CsvMapping::default(),
+            file: None,
+            headers: Vec::new(),
+            preview: None,
+            pick: None,
+        }
+    }
+}
+
+/// Draws the import window. Returns the preview once the user confirms it,
+/// for the caller to apply.
+pub fn draw_import_window(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    state: &mut ImportState,
+    app_data: &AppData,
+    stats: &Stats,
+) -> Option<Preview> {
+    if let Some(rx) = &state.pick
+        && let Ok((name, text)) = rx.try_recv()
+    {
+        state.headers = import::csv_headers(&text);
+        state.mapping = CsvMapping::guess(&state.headers);
+        state.file = Some((name, text));
+        state.preview = None;
+        state.pick = None;
+    }
+
+    let mut confirmed = None;
+    let mut open = *is_open;
+    egui::Window::new("Import")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .default_width(420.0)
+        .show(ctx, |ui| {
+            ui.label("Brings in tasks, and focus sessions where the file has them.");
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("Format:");
+                for format in ImportFormat::ALL {
+                    if ui
+                        .selectable_value(&mut state.format, format, format.name())
+                        .changed()
+                    {
+                        state.preview = None;
+                    }
+                }
+            });
+            match state.format {
+                ImportFormat::Csv => {
+                    ui.weak("Rows with text become tasks; rows with a duration become sessions.")
+                }
+                ImportFormat::Taskwarrior => ui.weak("The output of `task export`."),
+                ImportFormat::Markdown => ui.weak(
+                    "`- [ ]` and `- [x]` items, filed under the date in the heading above them.",
+                ),
+            };
+
+            ui.horizontal(|ui| {
+                if ui.button("Choose File...").clicked() {
+                    pick_file(state);
+                }
+                match &state.file {
+                    Some((name, _)) => ui.label(name),
+                    None => ui.weak("No file chosen"),
+                };
+            });
+
+            if state.format == ImportFormat::Csv && state.file.is_some() {
+                ui.separator();
+                if mapping_grid(ui, &mut state.mapping, &state.headers) {
+                    state.preview = None;
+                }
+            }
+
+            let Some((_, text)) = &state.file else {
+                return;
+            };
+            let today = Local::now().date_naive();
+            let preview = state.preview.get_or_insert_with(|| {
+                import::preview(state.format, text, &state.mapping, app_data, stats, today)
+            });
+
+            ui.separator();
+            ui.heading("Preview");
+            ui.label(format!(
+                "{} new tasks, {} new sessions ({}).",
+                preview.tasks.len(),
+                preview.sessions.len(),
+                format_duration(preview.session_seconds())
+            ));
+            if preview.duplicates > 0 {
+                ui.weak(format!(
+                    "{} already in Focus Hub and skipped.",
+                    preview.duplicates
+                ));
+            }
+            if !preview.sessions.is_empty() {
+                ui.weak("Undo removes the imported tasks, but the sessions stay in your stats.");
+            }
+            draw_preview(ui, preview, app_data);
+
+            ui.separator();
+            if ui
+                .add_enabled(!preview.is_empty(), egui::Button::new("Import"))
+                .clicked()
+            {
+                confirmed = state.preview.take();
+                state.file = None;
+            }
+        });
+    *is_open = open && confirmed.is_none();
+    confirmed
+}
+
+fn pick_file(state: &mut ImportState) {
+    let (tx, rx) = mpsc::channel();
+    state.pick = Some(rx);
+    let format = state.format;
+    thread::spawn(move || {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.name(), format.extensions())
+            .pick_file()
+        else {
+            return;
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let name = path
+                    .file_name()
+                    .map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+                tx.send((name, text)).ok();
+            }
+            Err(e) => {
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Import Error")
+                    .set_description(format!("Could not read {}: {e}", path.display()))
+                    .show();
+            }
+        }
+    });
+}
+
+// One column picker per field. Returns whether the mapping changed.
+fn mapping_grid(ui: &mut egui::Ui, mapping: &mut CsvMapping, headers: &[String]) -> bool {
+    let before = mapping.clone();
+    egui::Grid::new("import_mapping")
+        .num_columns(2)
+        .show(ui, |ui| {
+            let fields = [
+                ("Task text", &mut mapping.text),
+                ("Date", &mut mapping.date),
+                ("Done", &mut mapping.done),
+                ("Project", &mut mapping.project),
+                ("Session start", &mut mapping.start),
+                ("Session length", &mut mapping.duration),
+            ];
+            for (label, column) in fields {
+                ui.label(label);
+                let selected = column
+                    .and_then(|c| headers.get(c))
+                    .map_or("(none)", String::as_str);
+                egui::ComboBox::from_id_salt(("import_column", label))
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(column, None, "(none)");
+                        for (index, header) in headers.iter().enumerate() {
+                            ui.selectable_value(column, Some(index), header);
+                        }
+                    });
+                ui.end_row();
+            }
+            ui.label("Length in");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut mapping.unit, DurationUnit::Minutes, "minutes");
+                ui.selectable_value(&mut mapping.unit, DurationUnit::Seconds, "seconds");
+            });
+            ui.end_row();
+        });
+    *mapping != before
+}
+
+fn draw_preview(ui: &mut egui::Ui, preview: &Preview, app_data: &AppData) {
+    egui::ScrollArea::vertical()
+        .max_height(220.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            for task in preview.tasks.iter().take(PREVIEW_ROWS) {
+                let mark = if task.item.is_done() { "☑" } else { "☐" };
+                let mut line = format!("{}  {mark} {}", task.date, task.item.text);
+                if let Some(project) = task.item.project.and_then(|id| app_data.project(id)) {
+                    line += &format!("  📁 {}", project.name);
+                }
+                ui.label(line);
+            }
+            for session in preview.sessions.iter().take(PREVIEW_ROWS) {
+                let task = session
+                    .task
+                    .and_then(|id| preview.tasks.iter().find(|t| t.item.id == id))
+                    .map_or("Unassigned", |t| t.item.text.as_str());
+                ui.label(format!(
+                    "{}  ⏱ {}  {task}",
+                    session.start.format("%Y-%m-%d %H:%M"),
+                    format_duration(session.seconds)
+                ));
+            }
+            let hidden = preview.tasks.len().saturating_sub(PREVIEW_ROWS)
+                + preview.sessions.len().saturating_sub(PREVIEW_ROWS);
+            if hidden > 0 {
+                ui.weak(format!("…and {hidden} more."));
+            }
+        });
+
+    if !preview.problems.is_empty() {
+        egui::CollapsingHeader::new(format!("⚠ {} skipped", preview.problems.len()))
+            .id_salt("import_problems")
+            .show(ui, |ui| {
+                for problem in &preview.problems {
+                    ui.label(problem);
+                }
+            });
+    }
+}
@@ -0,0 +1,54 @@
+use eframe::egui;
+
+/// The text field currently being edited in place, if any.
+pub struct InlineEdit {
+    id: egui::Id,
+    buffer: String,
+}
+
+pub fn is_editing(edit: &Option<InlineEdit>, id: egui::Id) -> bool {
+    edit.as_ref().is_some_and(|state| state.id == id)
+}
+
+/// Starts editing `original` in the text field identified by `id`.
+pub fn begin(ui: &egui::Ui, edit: &mut Option<InlineEdit>, id: egui::Id, original: &str) {
+    *edit = Some(InlineEdit {
+        id,
+        buffer: original.to_owned(),
+    });
+    ui.memory_mut(|m| m.request_focus(id));
+}
+
+/// Shows `text` as a label that turns into a text field when double-clicked.
+///
+/// Returns the new text once the edit is committed with Enter or by clicking
+/// away. Escape, or committing an empty or unchanged value, cancels the edit.
+pub fn editable_label(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    text: impl Into<egui::WidgetText>,
+    original: &str,
+    edit: &mut Option<InlineEdit>,
+) -> Option<String> {
+    match edit {
+        Some(state) if state.id == id => {
+            let response = ui.add(egui::TextEdit::singleline(&mut state.buffer).id(id));
+            if !response.lost_focus() {
+                return None;
+            }
+            let cancelled = ui.input(|i| i.key_pressed(egui::Key::Escape));
+            let new_text = state.buffer.trim().to_owned();
+            *edit = None;
+            (!cancelled && !new_text.is_empty() && new_text != original).then_some(new_text)
+        }
+        _ => {
+            let response = ui
+                .add(egui::Label::new(text).sense(egui::Sense::click()))
+                .on_hover_text("Double-click to edit");
+            if response.double_clicked() {
+                begin(ui, edit, id, original);
+            }
+            None
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::xp::LevelInfo;
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+// How long the level-up banner stays on screen.
+const LEVEL_UP_DURATION: Duration = Duration::from_millis(2500);
+
+/// A compact level badge with a progress bar towards the next level.
+pub fn draw_level_badge(ui: &mut egui::Ui, info: &LevelInfo) {
+    ui.label(egui::RichText::new(format!("⭐ Lv {}", info.level)).strong())
+        .on_hover_text(format!("{} XP in total", info.xp));
+    ui.add(
+        egui::ProgressBar::new(info.fraction())
+            .desired_width(80.0)
+            .text(format!("{}/{}", info.into_level, info.level_span)),
+    );
+}
+
+/// Draws the level-up banner for a level reached at `since`. Returns false
+/// once the animation has finished.
+pub fn draw_level_up(ctx: &egui::Context, level: u32, since: Instant) -> bool {
+    let t = since.elapsed().as_secs_f32() / LEVEL_UP_DURATION.as_secs_f32();
+    if t >= 1.0 {
+        return false;
+    }
+    // Pop in over the first fifth, then fade out over the last half.
+    let scale = 1.0 + 0.4 * (1.0 - (t / 0.2).min(1.0));
+    let alpha = if t < 0.5 { 1.0 } else { 2.0 * (1.0 - t) };
+    let color = egui::Color32::GOLD.gamma_multiply(alpha);
+
+    egui::Area::new(egui::Id::new("level_up"))
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, -40.0))
+        .interactable(false)
+        .order(egui::Order::Foreground)
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(
+                    egui::RichText::new("LEVEL UP!")
+                        .size(48.0 * scale)
+                        .strong()
+                        .color(color),
+                );
+                ui.label(
+                    egui::RichText::new(format!("⭐ Level {level}"))
+                        .size(28.0 * scale)
+                        .color(color),
+                );
+            });
+        });
+    ctx.request_repaint();
+    true
+}
@@ -1,11 +1,68 @@
+pub mod achievements;
+pub mod board;
 pub mod calendar;
 pub mod central_panel;
+pub mod encryption;
+pub mod export;
+pub mod import;
+pub mod inline_edit;
+pub mod level;
+pub mod notification;
+pub mod profiles;
+pub mod review;
 pub mod rewards;
 pub mod stats;
+pub mod storage;
+pub mod sync;
 pub mod todo_window;
+pub mod webdav;
 
+pub use achievements::draw_achievements_window;
+pub use board::draw_board_window;
 pub use calendar::draw_calendar_window;
 pub use central_panel::draw_central_panel;
+pub use encryption::draw_encryption_window;
+pub use export::draw_export_window;
+pub use import::draw_import_window;
+pub use notification::draw_notification;
+pub use profiles::draw_profiles_window;
+pub use review::draw_review_window;
 pub use rewards::draw_rewards_window;
 pub use stats::draw_stats_window;
+pub use storage::draw_storage_window;
+pub use sync::draw_sync_conflicts;
 pub use todo_window::draw_todo_window;
+pub use webdav::draw_webdav_window;
+
+/// Formats a number of seconds as hours and minutes, e.g. "2h 05m".
+pub fn format_duration(seconds: u64) -> String {
+    format!("{}h {:02}m", seconds / 3600, (seconds % 3600) / 60)
+}
+
+/// Asks where to save off the UI thread, like the GIF picker does, then runs
+/// `write` with the chosen path and reports any error in a dialog.
+pub fn save_in_background<F>(
+    filter: &'static str,
+    extension: &'static str,
+    file_name: String,
+    write: F,
+) where
+    F: FnOnce(&std::path::Path) -> Result<(), Box<dyn std::error::Error>> + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(filter, &[extension])
+            .set_file_name(file_name)
+            .save_file()
+        else {
+            return;
+        };
+        if let Err(e) = write(&path) {
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Error)
+                .set_title("Export Error")
+                .set_description(format!("Could not write {}: {e}", path.display()))
+                .show();
+        }
+    });
+}
@@ -0,0 +1,19 @@
+use eframe::egui;
+
+/// A small dismissable message in the top-right corner of the window.
+pub fn draw_notification(ctx: &egui::Context, is_open: &mut bool, title: &str, message: &str) {
+    if !*is_open {
+        return;
+    }
+    egui::Window::new(title)
+        .id(egui::Id::new("notification"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 40.0))
+        .show(ctx, |ui| {
+            ui.label(message);
+            if ui.button("OK").clicked() {
+                *is_open = false;
+            }
+        });
+}
@@ -0,0 +1,161 @@
+use crate::profiles;
+use crate::ui::inline_edit::{self, InlineEdit};
+use eframe::egui;
+
+/// The profile picker's and profiles window's inputs.
+#[derive(Default)]
+pub struct ProfilesState {
+    new_name: String,
+    inline_edit: Option<InlineEdit>,
+    /// The profile waiting for a second click on Delete.
+    deleting: Option<String>,
+    error: Option<String>,
+}
+
+impl ProfilesState {
+    /// Shows why the last action failed.
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+}
+
+/// What the user asked for in the profiles window.
+pub enum ProfileAction {
+    Switch(String),
+    /// Copy the active profile into a new one with this name.
+    Duplicate(String),
+}
+
+/// Lets the user pick a profile before anything is loaded. Returns its name.
+pub fn draw_profile_picker(ctx: &egui::Context, state: &mut ProfilesState) -> Option<String> {
+    let mut chosen = None;
+    let last = profiles::last_used();
+    egui::CentralPanel::default().show(ctx, |ui| {
+        ui.vertical_centered(|ui| {
+            ui.add_space(ui.available_height() / 4.0);
+            ui.heading("Who's focusing?");
+            ui.add_space(8.0);
+            for name in profiles::list() {
+                let button = egui::Button::new(&name).min_size(egui::vec2(200.0, 0.0));
+                let response = ui.add(button);
+                if last.as_ref() == Some(&name) && ui.memory(|m| m.focused().is_none()) {
+                    response.request_focus();
+                }
+                if response.clicked() {
+                    chosen = Some(name);
+                }
+            }
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut state.new_name)
+                        .hint_text("New profile")
+                        .desired_width(140.0),
+                );
+                if ui.button("Create").clicked() {
+                    let name = state.new_name.trim().to_owned();
+                    match profiles::create(&name) {
+                        Ok(()) => chosen = Some(name),
+                        Err(e) => state.error = Some(e.to_string()),
+                    }
+                }
+            });
+            if let Some(error) = &state.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+    });
+    chosen
+}
+
+/// Lists the profiles for switching between them and managing them.
+pub fn draw_profiles_window(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    state: &mut ProfilesState,
+) -> Option<ProfileAction> {
+    let mut action = None;
+    let active = profiles::active();
+    egui::Window::new("Profiles")
+        .open(is_open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label("Each profile has its own tasks, stats, settings and background.");
+            ui.weak("Double-click a name to rename it.");
+            ui.separator();
+
+            egui::Grid::new("profiles").num_columns(3).show(ui, |ui| {
+                for name in profiles::list() {
+                    let fixed = name == profiles::DEFAULT_PROFILE || name == active;
+                    let text = if name == active {
+                        egui::RichText::new(format!("{name} (in use)")).strong()
+                    } else {
+                        egui::RichText::new(&name)
+                    };
+                    if fixed {
+                        ui.label(text);
+                    } else if let Some(new_name) = inline_edit::editable_label(
+                        ui,
+                        egui::Id::new(("profile_name", &name)),
+                        text,
+                        &name,
+                        &mut state.inline_edit,
+                    ) {
+                        state.error = profiles::rename(&name, &new_name)
+                            .err()
+                            .map(|e| e.to_string());
+                    }
+
+                    if name != active && ui.button("Switch").clicked() {
+                        action = Some(ProfileAction::Switch(name.clone()));
+                    }
+                    if !fixed {
+                        let really = state.deleting.as_ref() == Some(&name);
+                        let label = if really { "Really delete?" } else { "Delete" };
+                        if ui.button(label).clicked() {
+                            if really {
+                                state.deleting = None;
+                                state.error = profiles::delete(&name).err().map(|e| e.to_string());
+                            } else {
+                                state.deleting = Some(name.clone());
+                            }
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut state.new_name)
+                        .hint_text("Profile name")
+                        .desired_width(140.0),
+                );
+                let name = state.new_name.trim().to_owned();
+                if ui.button("Create").clicked() {
+                    match profiles::create(&name) {
+                        Ok(()) => {
+                            state.new_name.clear();
+                            state.error = None;
+                        }
+                        Err(e) => state.error = Some(e.to_string()),
+                    }
+                }
+                if ui
+                    .button("Duplicate")
+                    .on_hover_text(format!("Copy {active}'s data into a new profile"))
+                    .clicked()
+                {
+                    state.new_name.clear();
+                    state.error = None;
+                    action = Some(ProfileAction::Duplicate(name));
+                }
+            });
+            if let Some(error) = &state.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+    action
+}
@@ -0,0 +1,97 @@
+use crate::app_data::{AppData, Stats};
+use crate::review::{self, ReviewPeriod};
+use crate::streaks::StreakSummary;
+use crate::ui::{format_duration, save_in_background};
+use chrono::NaiveDate;
+use eframe::egui;
+
+/// Which week or month the review window shows.
+pub struct ReviewState {
+    pub period: ReviewPeriod,
+    /// A day inside the reviewed period.
+    pub date: NaiveDate,
+}
+
+impl ReviewState {
+    pub fn new(today: NaiveDate) -> Self {
+        Self {
+            period: ReviewPeriod::Week,
+            date: today,
+        }
+    }
+}
+
+pub fn draw_review_window(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    state: &mut ReviewState,
+    app_data: &AppData,
+    stats: &Stats,
+    streak: &StreakSummary,
+) {
+    let mut open = *is_open;
+    egui::Window::new("Review")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .default_width(340.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut state.period, ReviewPeriod::Week, "Week");
+                ui.selectable_value(&mut state.period, ReviewPeriod::Month, "Month");
+                ui.separator();
+                if ui.button("◀").clicked() {
+                    state.date = state.period.previous(state.date);
+                }
+                if ui.button("▶").clicked() {
+                    state.date = state.period.next(state.date);
+                }
+            });
+            ui.separator();
+
+            let review = review::build(app_data, stats, streak, state.period, state.date);
+            ui.heading(&review.title);
+            egui::Grid::new("review_summary")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (label, value) in review.summary_rows() {
+                        ui.label(label);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+
+            if !review.top_projects.is_empty() {
+                ui.separator();
+                ui.heading("📁 Top Projects");
+                for (name, seconds) in &review.top_projects {
+                    ui.label(format!("- {name}: {}", format_duration(*seconds)));
+                }
+            }
+            if !review.top_tags.is_empty() {
+                ui.separator();
+                ui.heading("🏷 Top Tags");
+                for (tag, count) in &review.top_tags {
+                    ui.label(format!("- #{tag}: {count} tasks"));
+                }
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Export Markdown...").clicked() {
+                    let contents = review.to_markdown();
+                    save_in_background("Markdown", "md", "review.md".to_owned(), |path| {
+                        Ok(std::fs::write(path, contents)?)
+                    });
+                }
+                if ui.button("Export HTML...").clicked() {
+                    let contents = review.to_html();
+                    save_in_background("HTML", "html", "review.html".to_owned(), |path| {
+                        Ok(std::fs::write(path, contents)?)
+                    });
+                }
+            });
+        });
+    *is_open = open;
+}
@@ -1,50 +1,496 @@
-use crate::app_data::Reward;
+use crate::app_data::{Cost, Reward, Wallet};
+use crate::history::Command;
+use crate::points;
+use crate::ui::format_duration;
+use crate::ui::inline_edit::{self, InlineEdit};
+use chrono::Local;
 use eframe::egui;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+// How many of the most recent transactions the points history lists.
+const LEDGER_ROWS: usize = 50;
+// Levels offered when locking a reward behind a level.
+const LEVEL_CHOICES: [u32; 6] = [5, 10, 15, 20, 30, 50];
+// How many past claims the settings window lists.
+const CLAIM_ROWS: usize = 10;
+const IMAGE_SIZE: f32 = 24.0;
+
+// Drag payload for reordering rewards, kept distinct from dragged tasks.
+struct RewardDrag(usize);
+
+#[derive(Default)]
+pub struct RewardsState {
+    pub new_reward_input: String,
+    pub new_reward_cost: u32,
+    pub inline_edit: Option<InlineEdit>,
+    /// Only rewards in this category are listed, when set.
+    pub category_filter: Option<String>,
+    /// The id of the reward whose settings window is open, and the edited
+    /// copy. Not its index, which moves when rewards are reordered.
+    settings: Option<(u64, Reward)>,
+    image_pick: Option<Receiver<PathBuf>>,
+    /// Loaded reward pictures by path; `None` for files that failed to load.
+    images: HashMap<String, Option<egui::TextureHandle>>,
+}
 
 pub fn draw_rewards_window(
     ctx: &egui::Context,
     is_open: &mut bool,
-    rewards: &mut Vec<Reward>,
-    new_reward_input: &mut String,
+    rewards: &[Reward],
+    wallet: &Wallet,
+    level: u32,
+    state: &mut RewardsState,
+    commands: &mut Vec<Command>,
 ) {
     let mut open = *is_open;
     egui::Window::new("Rewards")
         .open(&mut open)
         .collapsible(false)
         .resizable(true)
-        .default_width(300.0)
+        .default_width(340.0)
         .show(ctx, |ui| {
+            ui.heading(format!("🪙 {} points", wallet.balance));
+            ui.separator();
+
             ui.heading("Add a New Reward");
-            if ui
-                .text_edit_singleline(new_reward_input)
-                .on_hover_text("Enter a new reward...")
-                .lost_focus()
-                && ui.input(|i| i.key_pressed(egui::Key::Enter))
-                && !new_reward_input.trim().is_empty()
-            {
-                rewards.push(Reward {
-                    name: new_reward_input.trim().to_owned(),
-                    completed: false,
+            ui.horizontal(|ui| {
+                let input = ui
+                    .text_edit_singleline(&mut state.new_reward_input)
+                    .on_hover_text("Enter a new reward...");
+                ui.add(
+                    egui::DragValue::new(&mut state.new_reward_cost)
+                        .prefix("🪙 ")
+                        .range(0..=100_000),
+                )
+                .on_hover_text("Cost in points");
+                if input.lost_focus()
+                    && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                    && !state.new_reward_input.trim().is_empty()
+                {
+                    let mut reward = Reward::new(
+                        state.new_reward_input.trim().to_owned(),
+                        Cost::Points(state.new_reward_cost),
+                    );
+                    reward.category = state.category_filter.clone().unwrap_or_default();
+                    commands.push(Command::AddReward {
+                        index: rewards.len(),
+                        reward,
+                    });
+                    state.new_reward_input.clear();
+                }
+            });
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.heading("Your Rewards");
+                let categories: BTreeSet<&str> = rewards
+                    .iter()
+                    .map(|r| r.category.as_str())
+                    .filter(|c| !c.is_empty())
+                    .collect();
+                if !categories.is_empty() {
+                    egui::ComboBox::from_id_salt("reward_category")
+                        .selected_text(state.category_filter.as_deref().unwrap_or("All"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut state.category_filter, None, "All");
+                            for category in categories {
+                                ui.selectable_value(
+                                    &mut state.category_filter,
+                                    Some(category.to_owned()),
+                                    category,
+                                );
+                            }
+                        });
+                }
+            });
+
+            // Rewards keep the order the user dragged them into.
+            let shown: Vec<usize> = (0..rewards.len())
+                .filter(|&i| {
+                    state
+                        .category_filter
+                        .as_ref()
+                        .is_none_or(|c| rewards[i].category == *c)
+                })
+                .collect();
+            let mut dropped = None;
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for (position, &index) in shown.iter().enumerate() {
+                        let row = ui
+                            .horizontal(|ui| {
+                                reward_row(
+                                    ui,
+                                    index,
+                                    &rewards[index],
+                                    wallet,
+                                    level,
+                                    state,
+                                    commands,
+                                );
+                            })
+                            .response;
+
+                        // Show where the dragged reward would land and accept the drop.
+                        if let (Some(pointer), Some(_)) = (
+                            ui.input(|i| i.pointer.interact_pos()),
+                            row.dnd_hover_payload::<RewardDrag>(),
+                        ) {
+                            let (y, insert_at) = if pointer.y < row.rect.center().y {
+                                (row.rect.top(), index)
+                            } else {
+                                let next = shown.get(position + 1);
+                                (row.rect.bottom(), next.map_or(rewards.len(), |n| *n))
+                            };
+                            ui.painter().hline(
+                                row.rect.x_range(),
+                                y,
+                                ui.visuals().selection.stroke,
+                            );
+                            if let Some(source) = row.dnd_release_payload::<RewardDrag>() {
+                                dropped = Some((source.0, insert_at));
+                            }
+                        }
+                    }
                 });
-                new_reward_input.clear();
+            if let Some((from, insert_at)) = dropped {
+                let to = if from < insert_at {
+                    insert_at - 1
+                } else {
+                    insert_at
+                };
+                if from != to {
+                    commands.push(Command::MoveReward { from, to });
+                }
             }
+
             ui.separator();
-            ui.heading("Your Rewards");
-            rewards.sort_by_key(|r| r.completed);
-            let mut to_delete = None;
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                for (i, reward) in rewards.iter_mut().enumerate() {
-                    ui.horizontal(|ui| {
-                        ui.checkbox(&mut reward.completed, &reward.name);
-                        if ui.button("❌").on_hover_text("Remove reward").clicked() {
-                            to_delete = Some(i);
+            egui::CollapsingHeader::new("🧾 Points History").show(ui, |ui| {
+                if wallet.ledger.is_empty() {
+                    ui.weak("Finish sessions, tasks and goals to earn points.");
+                }
+                egui::Grid::new("points_ledger")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for entry in wallet.ledger.iter().rev().take(LEDGER_ROWS) {
+                            ui.label(entry.time.format("%b %-d %H:%M").to_string());
+                            ui.label(format!("{:+}", entry.amount));
+                            ui.label(&entry.reason);
+                            ui.end_row();
                         }
                     });
+            });
+        });
+    *is_open = open;
+
+    if open {
+        draw_settings_window(ctx, rewards, state, commands);
+    }
+}
+
+fn reward_row(
+    ui: &mut egui::Ui,
+    index: usize,
+    reward: &Reward,
+    wallet: &Wallet,
+    level: u32,
+    state: &mut RewardsState,
+    commands: &mut Vec<Command>,
+) {
+    let now = Local::now().naive_local();
+    let locked = !reward.completed && level < reward.min_level;
+    let price = reward.cost.points() as i64;
+
+    let id = egui::Id::new(("reward_drag", index));
+    ui.dnd_drag_source(id, RewardDrag(index), |ui| {
+        ui.label("☰");
+    })
+    .response
+    .on_hover_text("Drag to reorder");
+
+    if let Some(path) = &reward.image
+        && let Some(texture) = load_image(ui.ctx(), &mut state.images, path)
+    {
+        ui.add(egui::Image::new(&texture).fit_to_exact_size(egui::vec2(IMAGE_SIZE, IMAGE_SIZE)));
+    } else if !reward.icon.is_empty() {
+        ui.label(&reward.icon);
+    }
+
+    let id = egui::Id::new(("reward_edit", index));
+    let mut name = egui::RichText::new(&reward.name);
+    if reward.completed {
+        name = name.strikethrough().weak();
+    }
+    if let Some(new) =
+        inline_edit::editable_label(ui, id, name, &reward.name, &mut state.inline_edit)
+    {
+        commands.push(Command::EditReward {
+            index,
+            old: reward.name.clone(),
+            new,
+        });
+    }
+    if !reward.category.is_empty() {
+        ui.weak(format!("[{}]", reward.category));
+    }
+
+    // The cost is edited like the name: double-click and type a number.
+    let (amount, unit): (u32, fn(u32) -> Cost) = match reward.cost {
+        Cost::Points(points) => (points, Cost::Points),
+        Cost::Pomodoros(sessions) => (sessions, Cost::Pomodoros),
+    };
+    let cost_id = egui::Id::new(("reward_cost_edit", index));
+    let edited = inline_edit::editable_label(
+        ui,
+        cost_id,
+        reward.cost.describe(),
+        &amount.to_string(),
+        &mut state.inline_edit,
+    );
+    if let Some(new) = edited.and_then(|text| text.trim().parse().ok()) {
+        commands.push(Command::SetRewardCost {
+            index,
+            old: reward.cost,
+            new: unit(new),
+        });
+    }
+    if ui
+        .small_button("⇄")
+        .on_hover_text("Switch the cost between points and pomodoros")
+        .clicked()
+    {
+        let new = match reward.cost {
+            Cost::Points(_) => Cost::Pomodoros(amount),
+            Cost::Pomodoros(_) => Cost::Points(amount),
+        };
+        commands.push(Command::SetRewardCost {
+            index,
+            old: reward.cost,
+            new,
+        });
+    }
+
+    let lock_icon = if reward.min_level == 0 {
+        "🔓".to_owned()
+    } else {
+        format!("🔒 {}", reward.min_level)
+    };
+    ui.menu_button(lock_icon, |ui| {
+        ui.label("Unlock at level:");
+        for new in std::iter::once(0).chain(LEVEL_CHOICES) {
+            let label = if new == 0 {
+                "Any level".to_owned()
+            } else {
+                format!("Level {new}")
+            };
+            if ui
+                .selectable_label(reward.min_level == new, label)
+                .clicked()
+            {
+                if new != reward.min_level {
+                    commands.push(Command::SetRewardLevel {
+                        index,
+                        old: reward.min_level,
+                        new,
+                    });
                 }
+                ui.close_menu();
+            }
+        }
+    });
+
+    if reward.completed {
+        // Taking back a one-off claim refunds what it cost.
+        if ui
+            .small_button("↺")
+            .on_hover_text("Unclaim and refund")
+            .clicked()
+        {
+            let mut batch = vec![match reward.claims.last() {
+                Some(time) => Command::UnclaimReward { index, time: *time },
+                // Claimed before claims were recorded.
+                None => Command::ToggleReward { index },
+            }];
+            if price > 0 {
+                let entry = points::transaction(price, format!("Refunded \"{}\"", reward.name));
+                batch.push(Command::AddTransaction { entry });
+            }
+            commands.push(Command::Batch(batch));
+        }
+    } else if let Some(left) = reward.cooldown_left(now) {
+        ui.weak(format!("⏳ {}", format_duration(left.num_seconds() as u64)))
+            .on_hover_text("Cooling down");
+    } else {
+        let claim = ui
+            .add_enabled(
+                !locked && wallet.balance >= price,
+                egui::Button::new("Claim"),
+            )
+            .on_disabled_hover_text(if locked {
+                format!("Unlocks at level {}", reward.min_level)
+            } else {
+                format!("You need {} more points", price - wallet.balance)
             });
-            if let Some(index) = to_delete {
-                rewards.remove(index);
+        if claim.clicked() {
+            let mut batch = vec![Command::ClaimReward { index, time: now }];
+            if price > 0 {
+                let entry = points::transaction(-price, format!("Redeemed \"{}\"", reward.name));
+                batch.push(Command::AddTransaction { entry });
             }
+            commands.push(Command::Batch(batch));
+        }
+    }
+
+    if ui.button("⚙").on_hover_text("Reward settings").clicked() {
+        state.settings = Some((reward.id, reward.clone()));
+    }
+    if ui.button("❌").on_hover_text("Remove reward").clicked() {
+        commands.push(Command::DeleteReward {
+            index,
+            reward: reward.clone(),
         });
-    *is_open = open;
+    }
+}
+
+// Edits a copy of the reward; saving emits a single `UpdateReward` command.
+fn draw_settings_window(
+    ctx: &egui::Context,
+    rewards: &[Reward],
+    state: &mut RewardsState,
+    commands: &mut Vec<Command>,
+) {
+    if let Some(rx) = &state.image_pick
+        && let Ok(path) = rx.try_recv()
+    {
+        if let Some((_, edited)) = &mut state.settings {
+            edited.image = Some(path.to_string_lossy().into_owned());
+        }
+        state.image_pick = None;
+    }
+
+    let Some((id, edited)) = &mut state.settings else {
+        return;
+    };
+    let Some(index) = rewards.iter().position(|r| r.id == *id) else {
+        state.settings = None;
+        return;
+    };
+    let current = &rewards[index];
+
+    let mut keep_open = true;
+    let mut save = false;
+    egui::Window::new(format!("{} Settings", current.name))
+        .id(egui::Id::new("reward_settings"))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("reward_settings_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Emoji:");
+                    ui.add(egui::TextEdit::singleline(&mut edited.icon).desired_width(40.0));
+                    ui.end_row();
+
+                    ui.label("Category:");
+                    ui.text_edit_singleline(&mut edited.category);
+                    ui.end_row();
+
+                    ui.label("Image:");
+                    ui.horizontal(|ui| {
+                        let name = edited
+                            .image
+                            .as_deref()
+                            .and_then(|p| PathBuf::from(p).file_name().map(|n| n.to_owned()));
+                        match name {
+                            Some(name) => ui.label(name.to_string_lossy()),
+                            None => ui.weak("None"),
+                        };
+                        if ui.button("Choose...").clicked() {
+                            let (tx, rx) = mpsc::channel();
+                            state.image_pick = Some(rx);
+                            thread::spawn(move || {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter("Image", &["png", "jpg", "jpeg"])
+                                    .pick_file()
+                                {
+                                    tx.send(path).ok();
+                                }
+                            });
+                        }
+                        if edited.image.is_some() && ui.button("Remove").clicked() {
+                            edited.image = None;
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Repeatable:");
+                    ui.checkbox(&mut edited.repeatable, "Can be claimed again");
+                    ui.end_row();
+
+                    ui.label("Cooldown:");
+                    ui.add_enabled(
+                        edited.repeatable,
+                        egui::DragValue::new(&mut edited.cooldown_minutes)
+                            .suffix(" min")
+                            .range(0..=60 * 24 * 30),
+                    );
+                    ui.end_row();
+                });
+
+            ui.separator();
+            ui.label(format!("Claimed {} times", current.claims.len()));
+            for time in current.claims.iter().rev().take(CLAIM_ROWS) {
+                ui.weak(time.format("%B %-d, %Y at %H:%M").to_string());
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    save = true;
+                    keep_open = false;
+                }
+                if ui.button("Cancel").clicked() {
+                    keep_open = false;
+                }
+            });
+        });
+
+    if save {
+        // Only the settings come from the copy, so claims made meanwhile are kept.
+        let mut new = current.clone();
+        new.icon = edited.icon.trim().to_owned();
+        new.category = edited.category.trim().to_owned();
+        new.image = edited.image.clone();
+        new.repeatable = edited.repeatable;
+        new.cooldown_minutes = edited.cooldown_minutes;
+        new.completed = !new.repeatable && (current.completed || !new.claims.is_empty());
+        commands.push(Command::UpdateReward {
+            index,
+            old: current.clone(),
+            new,
+        });
+    }
+    if !keep_open {
+        state.settings = None;
+    }
+}
+
+fn load_image(
+    ctx: &egui::Context,
+    cache: &mut HashMap<String, Option<egui::TextureHandle>>,
+    path: &str,
+) -> Option<egui::TextureHandle> {
+    cache
+        .entry(path.to_owned())
+        .or_insert_with(|| {
+            let image = image::open(path).ok()?.to_rgba8();
+            let size = [image.width() as usize, image.height() as usize];
+            let pixels = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+            Some(ctx.load_texture(path, pixels, egui::TextureOptions::LINEAR))
+        })
+        .clone()
 }
@@ -1,14 +1,73 @@
-use crate::app_data::Stats;
-use chrono::{Datelike, Local};
+use crate::analytics;
+use crate::app_data::{Project, Stats};
+use crate::goals;
+use crate::streaks::StreakSummary;
+use crate::ui::format_duration;
+use chrono::{Datelike, Days, Local, NaiveDate};
 use eframe::egui;
+use egui_plot::{Bar, BarChart, Legend, Line, Plot, PlotPoints};
 
-pub fn draw_stats_window(ctx: &egui::Context, is_open: &mut bool, stats: &Stats) {
+#[derive(Clone, Copy, PartialEq)]
+pub enum StatsRange {
+    Last7Days,
+    Last30Days,
+    Last90Days,
+    ThisYear,
+    Custom,
+}
+
+/// The date range and grouping chosen for the trend charts.
+pub struct StatsState {
+    pub range: StatsRange,
+    pub weekly: bool,
+    pub custom_from: String,
+    pub custom_to: String,
+}
+
+impl Default for StatsState {
+    fn default() -> Self {
+        let today = Local::now().date_naive();
+        Self {
+            range: StatsRange::Last30Days,
+            weekly: false,
+            custom_from: (today - Days::new(29)).format("%Y-%m-%d").to_string(),
+            custom_to: today.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+impl StatsState {
+    // The inclusive date range to chart, or `None` if the custom dates don't parse.
+    fn dates(&self, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+        let days_back = |n: u64| Some((today - Days::new(n - 1), today));
+        match self.range {
+            StatsRange::Last7Days => days_back(7),
+            StatsRange::Last30Days => days_back(30),
+            StatsRange::Last90Days => days_back(90),
+            StatsRange::ThisYear => Some((today.with_ordinal(1)?, today)),
+            StatsRange::Custom => {
+                let from = NaiveDate::parse_from_str(self.custom_from.trim(), "%Y-%m-%d").ok()?;
+                let to = NaiveDate::parse_from_str(self.custom_to.trim(), "%Y-%m-%d").ok()?;
+                (from <= to).then_some((from, to))
+            }
+        }
+    }
+}
+
+pub fn draw_stats_window(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    stats: &Stats,
+    projects: &[Project],
+    streak: &StreakSummary,
+    state: &mut StatsState,
+) {
     let mut open = *is_open;
     egui::Window::new("Your Stats")
         .open(&mut open)
         .collapsible(false)
         .resizable(true)
-        .default_width(300.0)
+        .default_width(420.0)
         .show(ctx, |ui| {
             ui.heading("📊 Lifetime Summary");
             let total_seconds: u64 = stats.daily_study_seconds.values().sum();
@@ -22,14 +81,14 @@ pub fn draw_stats_window(ctx: &egui::Context, is_open: &mut bool, stats: &Stats)
             ui.separator();
 
             let today = Local::now().date_naive();
-            let today_sessions = stats.daily_streaks.get(&today).cloned().unwrap_or(0);
+            let today_sessions = stats.daily_sessions.get(&today).cloned().unwrap_or(0);
             let today_seconds = stats.daily_study_seconds.get(&today).cloned().unwrap_or(0);
             let today_h = today_seconds / 3600;
             let today_m = (today_seconds % 3600) / 60;
             let today_s = today_seconds % 60;
 
             let month_key = format!("{}-{}", today.year(), today.month());
-            let this_month_sessions = stats.monthly_streaks.get(&month_key).cloned().unwrap_or(0);
+            let this_month_sessions = stats.monthly_sessions.get(&month_key).cloned().unwrap_or(0);
 
             ui.heading("📅 Today's Progress");
             ui.label(format!("- Sessions Completed: {today_sessions}",));
@@ -40,6 +99,166 @@ pub fn draw_stats_window(ctx: &egui::Context, is_open: &mut bool, stats: &Stats)
 
             ui.heading("📅 This Month's Progress");
             ui.label(format!("- Sessions Completed: {this_month_sessions}"));
+            ui.separator();
+
+            ui.heading("🔥 Streaks");
+            ui.label(format!("- Current Streak: {} days", streak.current));
+            ui.label(format!("- Longest Streak: {} days", streak.longest));
+            ui.label(format!("- Streak Freezes: {}", streak.freezes))
+                .on_hover_text("Earned every 7 days in a row; each one covers a missed day.");
+
+            if !stats.daily_goal_results.is_empty() || !stats.weekly_goal_results.is_empty() {
+                ui.separator();
+                ui.heading("🎯 Goals");
+                let (hits, days) =
+                    goals::hit_rate(&stats.daily_goal_results, today - Days::new(30));
+                ui.label(format!("- Daily goal met: {hits} of the last {days} days"));
+                let (hits, weeks) =
+                    goals::hit_rate(&stats.weekly_goal_results, today - Days::new(7 * 12));
+                ui.label(format!(
+                    "- Weekly goal met: {hits} of the last {weeks} weeks"
+                ));
+            }
+
+            if !projects.is_empty() {
+                ui.separator();
+                ui.heading("📁 Focus by Project");
+                let project_seconds = stats.project_seconds();
+                for project in projects {
+                    let seconds = project_seconds.get(&project.id).cloned().unwrap_or(0);
+                    ui.label(format!("- {}: {}", project.name, format_duration(seconds)));
+                }
+            }
+
+            ui.separator();
+            egui::CollapsingHeader::new("📈 Trends")
+                .default_open(true)
+                .show(ui, |ui| draw_trends(ui, stats, state, today));
         });
     *is_open = open;
 }
+
+fn draw_trends(ui: &mut egui::Ui, stats: &Stats, state: &mut StatsState, today: NaiveDate) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("stats_range")
+            .selected_text(match state.range {
+                StatsRange::Last7Days => "Last 7 days",
+                StatsRange::Last30Days => "Last 30 days",
+                StatsRange::Last90Days => "Last 90 days",
+                StatsRange::ThisYear => "This year",
+                StatsRange::Custom => "Custom",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut state.range, StatsRange::Last7Days, "Last 7 days");
+                ui.selectable_value(&mut state.range, StatsRange::Last30Days, "Last 30 days");
+                ui.selectable_value(&mut state.range, StatsRange::Last90Days, "Last 90 days");
+                ui.selectable_value(&mut state.range, StatsRange::ThisYear, "This year");
+                ui.selectable_value(&mut state.range, StatsRange::Custom, "Custom");
+            });
+        ui.selectable_value(&mut state.weekly, false, "Daily");
+        ui.selectable_value(&mut state.weekly, true, "Weekly");
+    });
+    if state.range == StatsRange::Custom {
+        ui.horizontal(|ui| {
+            ui.label("From");
+            ui.add(egui::TextEdit::singleline(&mut state.custom_from).desired_width(80.0));
+            ui.label("to");
+            ui.add(egui::TextEdit::singleline(&mut state.custom_to).desired_width(80.0));
+        });
+    }
+
+    let Some((from, to)) = state.dates(today) else {
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            "Enter dates as YYYY-MM-DD, with the start before the end.",
+        );
+        return;
+    };
+
+    let daily = analytics::daily_totals(stats, from, to);
+    let weekly = analytics::weekly_totals(stats, from, to);
+    let total: u64 = daily.iter().map(|(_, s)| s).sum();
+
+    ui.label(format!("Total: {}", format_duration(total)));
+    match analytics::best(&daily) {
+        Some((date, seconds)) => ui.label(format!(
+            "Best day: {} ({})",
+            date.format("%b %-d, %Y"),
+            format_duration(seconds)
+        )),
+        None => ui.label("Best day: -"),
+    };
+    match analytics::best(&weekly) {
+        Some((date, seconds)) => ui.label(format!(
+            "Best week: week of {} ({})",
+            date.format("%b %-d, %Y"),
+            format_duration(seconds)
+        )),
+        None => ui.label("Best week: -"),
+    };
+
+    // Bars are placed at day offsets from `from` and plotted in minutes.
+    let x_of = move |date: NaiveDate| (date - from).num_days() as f64;
+    let minutes = |seconds: f64| seconds / 60.0;
+    let (bars, width) = if state.weekly {
+        let bars = weekly
+            .iter()
+            .map(|(week, s)| Bar::new(x_of(*week) + 3.0, minutes(*s as f64)))
+            .collect();
+        (bars, 6.0)
+    } else {
+        let bars = daily
+            .iter()
+            .map(|(day, s)| Bar::new(x_of(*day), minutes(*s as f64)))
+            .collect();
+        (bars, 0.8)
+    };
+    let average_line = |window: u64, name: &str| {
+        let points: PlotPoints = analytics::rolling_average(stats, from, to, window)
+            .into_iter()
+            .map(|(day, s)| [x_of(day), minutes(s)])
+            .collect();
+        Line::new(points).name(name)
+    };
+
+    ui.label(egui::RichText::new("Study time (minutes)").strong());
+    Plot::new("stats_study_time")
+        .height(160.0)
+        .legend(Legend::default())
+        .allow_zoom(false)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .include_y(0.0)
+        .x_axis_formatter(move |mark, _| {
+            (from + Days::new(mark.value.max(0.0) as u64))
+                .format("%b %-d")
+                .to_string()
+        })
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(bars).width(width).name(if state.weekly {
+                "Weekly"
+            } else {
+                "Daily"
+            }));
+            plot_ui.line(average_line(7, "7-day average"));
+            plot_ui.line(average_line(30, "30-day average"));
+        });
+
+    ui.label(egui::RichText::new("Focus by hour of day (minutes)").strong());
+    let hours = analytics::hour_distribution(&stats.sessions, from, to);
+    let hour_bars = hours
+        .iter()
+        .enumerate()
+        .map(|(hour, s)| Bar::new(hour as f64, minutes(*s as f64)))
+        .collect();
+    Plot::new("stats_hour_of_day")
+        .height(120.0)
+        .allow_zoom(false)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .include_y(0.0)
+        .x_axis_formatter(|mark, _| format!("{:02}:00", mark.value.clamp(0.0, 23.0) as u32))
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(hour_bars).width(0.8));
+        });
+}
@@ -0,0 +1,43 @@
+use crate::storage::StorageKind;
+use eframe::egui;
+
+/// Draws the window showing where the data is kept, with a button to move it
+/// to the other kind of storage. Returns the kind asked for.
+pub fn draw_storage_window(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    kind: StorageKind,
+    encrypted: bool,
+) -> Option<StorageKind> {
+    let mut chosen = None;
+    egui::Window::new("Storage")
+        .open(is_open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label(format!("Your data is kept in a {}.", kind.name()));
+            let (other, about) = match kind {
+                StorageKind::Json => (
+                    StorageKind::Sqlite,
+                    "A database only writes what changed, which keeps saving quick with years of history.",
+                ),
+                StorageKind::Sqlite => (
+                    StorageKind::Json,
+                    "A JSON file is easy to read and can be encrypted, but is rewritten whole on every save.",
+                ),
+            };
+            ui.weak(about);
+            ui.weak("The old file is removed once the new one reads back the same history.");
+            ui.separator();
+            ui.add_enabled_ui(!encrypted, |ui| {
+                if ui
+                    .button(format!("Switch to a {}", other.name()))
+                    .on_disabled_hover_text("Turn off encryption first.")
+                    .clicked()
+                {
+                    chosen = Some(other);
+                }
+            });
+        });
+    chosen
+}
@@ -0,0 +1,77 @@
+use crate::app_data::AppData;
+use crate::history::Command;
+use crate::sync::{self, Conflict, ConflictItem};
+use eframe::egui;
+
+/// Lists items changed on this device and another one since they last
+/// synced, and lets the user pick which version to keep.
+pub fn draw_sync_conflicts(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    conflicts: &mut Vec<Conflict>,
+    app_data: &AppData,
+    commands: &mut Vec<Command>,
+) {
+    let mut open = *is_open;
+    egui::Window::new("Sync Conflicts")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(true)
+        .default_width(420.0)
+        .show(ctx, |ui| {
+            if conflicts.is_empty() {
+                ui.label("Everything merged cleanly.");
+                return;
+            }
+            ui.label("These were changed here and on another device. This device's version is in use until you choose.");
+            ui.horizontal(|ui| {
+                if ui.button("Keep All Mine").clicked() {
+                    conflicts.clear();
+                }
+                if ui.button("Use All Theirs").clicked() {
+                    commands.push(sync::take_theirs(conflicts, app_data));
+                    conflicts.clear();
+                }
+            });
+            ui.separator();
+
+            let mut resolved = None;
+            egui::ScrollArea::vertical()
+                .max_height(360.0)
+                .show(ui, |ui| {
+                    for (index, conflict) in conflicts.iter().enumerate() {
+                        let kind = match conflict.item {
+                            ConflictItem::Task { .. } => "Task",
+                            ConflictItem::Project { .. } => "Project",
+                            ConflictItem::Reward { .. } => "Reward",
+                        };
+                        let (ours, theirs) = conflict.describe();
+                        ui.label(egui::RichText::new(format!("{kind}, also changed on {}", conflict.device)).strong());
+                        egui::Grid::new(("sync_conflict", index))
+                            .num_columns(3)
+                            .show(ui, |ui| {
+                                ui.label("Mine:");
+                                ui.label(ours);
+                                if ui.button("Keep").clicked() {
+                                    resolved = Some((index, false));
+                                }
+                                ui.end_row();
+                                ui.label("Theirs:");
+                                ui.label(theirs);
+                                if ui.button("Use").clicked() {
+                                    resolved = Some((index, true));
+                                }
+                                ui.end_row();
+                            });
+                        ui.separator();
+                    }
+                });
+            if let Some((index, take_theirs)) = resolved {
+                let conflict = conflicts.remove(index);
+                if take_theirs {
+                    commands.push(sync::take_theirs(&[conflict], app_data));
+                }
+            }
+        });
+    *is_open = open;
+}
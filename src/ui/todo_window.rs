@@ -1,74 +1,209 @@
-use crate::app_data::TodoItem;
+use crate::app_data::{
+    self, AppData, Project, STATUS_DONE, STATUS_TODO, TaskList, TaskRef, TodoItem,
+};
+use crate::history::Command;
+use crate::timer::FocusTarget;
+use crate::ui::inline_edit::{self, InlineEdit};
 use chrono::NaiveDate;
 use eframe::egui;
-use std::collections::HashMap;
+
+/// Inputs and selection that persist between frames of the to-do window.
+#[derive(Default)]
+pub struct TodoWindowState {
+    pub new_todo_input: String,
+    pub new_project_input: String,
+    pub selected_project: Option<u64>,
+    pub inline_edit: Option<InlineEdit>,
+}
 
 pub fn draw_todo_window(
     ctx: &egui::Context,
     is_open: &mut bool,
-    todos_by_date: &mut HashMap<NaiveDate, Vec<TodoItem>>,
-    new_todo_input: &mut String,
+    app_data: &AppData,
     selected_date: &mut NaiveDate,
+    state: &mut TodoWindowState,
+    focus: &mut Option<FocusTarget>,
+    commands: &mut Vec<Command>,
 ) {
     let mut open = *is_open;
     egui::Window::new("To-Do List")
         .open(&mut open)
-        .default_width(320.0)
+        .default_width(420.0)
         .default_height(500.0)
         .resizable(true)
         .show(ctx, |ui| {
-            // --- Task Editor for Selected Day ---
+            egui::SidePanel::left("todo_projects")
+                .resizable(false)
+                .default_width(110.0)
+                .show_inside(ui, |ui| {
+                    draw_project_sidebar(ui, app_data, state, commands);
+                });
+
+            // A deleted project can't stay selected.
+            if state
+                .selected_project
+                .is_some_and(|id| app_data.project(id).is_none())
+            {
+                state.selected_project = None;
+            }
+
+            let list = match state.selected_project {
+                Some(id) => TaskList::Project(id),
+                None => TaskList::Date(*selected_date),
+            };
+
+            // --- Task Editor for Selected Day or Project ---
             ui.vertical_centered(|ui| {
                 ui.heading("Task Editor");
-                ui.label(selected_date.format("%A, %B %-d, %Y").to_string());
+                match state.selected_project.and_then(|id| app_data.project(id)) {
+                    Some(project) => {
+                        ui.label(&project.name);
+                    }
+                    None => {
+                        ui.label(selected_date.format("%A, %B %-d, %Y").to_string());
+                    }
+                }
             });
             ui.separator();
 
+            let tasks = app_data.tasks(list);
             let add_todo_response = ui
-                .text_edit_singleline(new_todo_input)
+                .text_edit_singleline(&mut state.new_todo_input)
                 .on_hover_text("What needs to be done? (Press Enter to add)");
             if add_todo_response.lost_focus()
                 && ui.input(|i| i.key_pressed(egui::Key::Enter))
-                && !new_todo_input.trim().is_empty()
+                && !state.new_todo_input.trim().is_empty()
             {
-                let todos_for_day = todos_by_date.entry(*selected_date).or_default();
-                todos_for_day.push(TodoItem {
-                    text: new_todo_input.trim().to_owned(),
-                    completed: false,
+                commands.push(Command::AddTodo {
+                    task: TaskRef {
+                        list,
+                        index: tasks.len(),
+                    },
+                    item: TodoItem::new(
+                        state.new_todo_input.trim().to_owned(),
+                        state.selected_project,
+                    ),
                 });
-                new_todo_input.clear();
+                state.new_todo_input.clear();
                 add_todo_response.request_focus();
             }
 
             ui.add_space(5.0);
 
-            let mut to_delete = None;
-            let top_scroll_height = ui.available_height() * 0.4;
+            if state.selected_project.is_none() {
+                for event in app_data.events_on(*selected_date) {
+                    let time = if event.all_day {
+                        "All day".to_owned()
+                    } else {
+                        event.start.format("%H:%M").to_string()
+                    };
+                    ui.horizontal(|ui| {
+                        ui.label(format!("📌 {time} · {}", event.summary))
+                            .on_hover_text("Imported calendar event");
+                        if ui
+                            .small_button("❌")
+                            .on_hover_text(
+                                "Remove event; importing its calendar again brings it back",
+                            )
+                            .clicked()
+                            && let Some(index) =
+                                app_data.events.iter().position(|e| e.uid == event.uid)
+                        {
+                            commands.push(Command::DeleteEvent {
+                                index,
+                                event: event.clone(),
+                            });
+                        }
+                    });
+                }
+            }
 
-            // Scroll area for the current day's tasks
+            let mut dropped = None;
+            let top_scroll_height = if state.selected_project.is_some() {
+                ui.available_height()
+            } else {
+                ui.available_height() * 0.4
+            };
+
+            // Scroll area for the selected list's tasks
             egui::ScrollArea::vertical()
+                .id_salt("todo_current")
                 .max_height(top_scroll_height)
                 .show(ui, |ui| {
-                    let todos_for_day = todos_by_date.entry(*selected_date).or_default();
-                    if todos_for_day.is_empty() {
-                        ui.label("No tasks for this day.");
+                    if tasks.is_empty() {
+                        let empty_text = if state.selected_project.is_some() {
+                            "No tasks in this project."
+                        } else {
+                            "No tasks for this day."
+                        };
+                        let response = ui.label(empty_text);
+                        if let Some(source) = response.dnd_release_payload::<TaskRef>() {
+                            dropped = Some((*source, 0));
+                        }
                     } else {
-                        for (i, todo) in todos_for_day.iter_mut().enumerate() {
-                            ui.horizontal(|ui| {
-                                ui.checkbox(&mut todo.completed, &todo.text);
-                                if ui.button("❌").on_hover_text("Remove task").clicked() {
-                                    to_delete = Some(i);
+                        for (i, todo) in tasks.iter().enumerate() {
+                            let task = TaskRef { list, index: i };
+                            let row = ui
+                                .horizontal(|ui| {
+                                    task_row(ui, task, todo, app_data, state, focus, commands);
+                                    if let TaskList::Project(_) = list {
+                                        let schedule = ui.button("📅").on_hover_text(format!(
+                                            "Schedule on {}",
+                                            selected_date.format("%B %-d")
+                                        ));
+                                        if schedule.clicked() {
+                                            let day = TaskList::Date(*selected_date);
+                                            let len = app_data.tasks(day).len();
+                                            commands.push(Command::MoveTodo {
+                                                from: task,
+                                                to: TaskRef {
+                                                    list: day,
+                                                    index: len,
+                                                },
+                                            });
+                                        }
+                                    }
+                                    if ui.button("❌").on_hover_text("Remove task").clicked() {
+                                        commands.push(Command::DeleteTodo {
+                                            task,
+                                            item: todo.clone(),
+                                        });
+                                    }
+                                })
+                                .response;
+
+                            // Show where the dragged task would land and accept the drop.
+                            if let (Some(pointer), Some(_)) = (
+                                ui.input(|i| i.pointer.interact_pos()),
+                                row.dnd_hover_payload::<TaskRef>(),
+                            ) {
+                                let (y, insert_at) = if pointer.y < row.rect.center().y {
+                                    (row.rect.top(), i)
+                                } else {
+                                    (row.rect.bottom(), i + 1)
+                                };
+                                ui.painter().hline(
+                                    row.rect.x_range(),
+                                    y,
+                                    ui.visuals().selection.stroke,
+                                );
+                                if let Some(source) = row.dnd_release_payload::<TaskRef>() {
+                                    dropped = Some((*source, insert_at));
                                 }
-                            });
+                            }
                         }
                     }
                 });
 
-            if let Some(index) = to_delete {
-                todos_by_date
-                    .entry(*selected_date)
-                    .or_default()
-                    .remove(index);
+            if let Some((from, insert_at)) = dropped {
+                let to = app_data::drop_position(from, list, insert_at, tasks.len());
+                if from != to {
+                    commands.push(Command::MoveTodo { from, to });
+                }
+            }
+
+            if state.selected_project.is_some() {
+                return;
             }
 
             ui.separator();
@@ -77,38 +212,274 @@ pub fn draw_todo_window(
             ui.heading("Task History");
             ui.separator();
 
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                let mut past_dates: Vec<_> = todos_by_date
-                    .iter()
-                    .filter(|(date, tasks)| !tasks.is_empty() && **date < *selected_date)
-                    .map(|(date, _)| *date)
-                    .collect();
-                past_dates.sort_unstable_by(|a, b| b.cmp(a));
-
-                if past_dates.is_empty() {
-                    ui.label("No tasks from previous days.");
-                } else {
-                    for date in past_dates {
-                        if let Some(tasks) = todos_by_date.get_mut(&date) {
-                            if tasks.is_empty() {
-                                continue;
-                            }
+            egui::ScrollArea::vertical()
+                .id_salt("todo_history")
+                .show(ui, |ui| {
+                    let mut past_dates: Vec<_> = app_data
+                        .todos_by_date
+                        .iter()
+                        .filter(|(date, tasks)| !tasks.is_empty() && **date < *selected_date)
+                        .map(|(date, _)| *date)
+                        .collect();
+                    past_dates.sort_unstable_by(|a, b| b.cmp(a));
+
+                    if past_dates.is_empty() {
+                        ui.label("No tasks from previous days.");
+                    } else {
+                        for date in past_dates {
+                            let tasks = &app_data.todos_by_date[&date];
 
                             ui.label(
                                 egui::RichText::new(date.format("%A, %B %-d").to_string()).strong(),
                             );
                             ui.add_space(2.0);
 
-                            // Iterate mutably and use a checkbox for each task
-                            for task in tasks.iter_mut() {
-                                ui.checkbox(&mut task.completed, &task.text);
+                            for (i, task) in tasks.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    task_row(
+                                        ui,
+                                        TaskRef {
+                                            list: TaskList::Date(date),
+                                            index: i,
+                                        },
+                                        task,
+                                        app_data,
+                                        state,
+                                        focus,
+                                        commands,
+                                    );
+                                });
                             }
                             ui.separator();
                         }
                     }
-                }
-            });
+                });
         });
 
     *is_open = open;
 }
+
+// Lists the "Scheduled" date view and every project, with a field to add projects.
+fn draw_project_sidebar(
+    ui: &mut egui::Ui,
+    app_data: &AppData,
+    state: &mut TodoWindowState,
+    commands: &mut Vec<Command>,
+) {
+    ui.heading("Lists");
+    if ui
+        .selectable_label(state.selected_project.is_none(), "📅 Scheduled")
+        .clicked()
+    {
+        state.selected_project = None;
+    }
+    ui.separator();
+
+    for (index, project) in app_data.projects.iter().enumerate() {
+        let id = egui::Id::new(("project_edit", project.id));
+        if inline_edit::is_editing(&state.inline_edit, id) {
+            if let Some(new) = inline_edit::editable_label(
+                ui,
+                id,
+                &project.name,
+                &project.name,
+                &mut state.inline_edit,
+            ) {
+                commands.push(Command::RenameProject {
+                    index,
+                    old: project.name.clone(),
+                    new,
+                });
+            }
+            continue;
+        }
+
+        let selected = state.selected_project == Some(project.id);
+        let response = ui
+            .selectable_label(selected, format!("📁 {}", project.name))
+            .on_hover_text("Double-click to rename, right-click for more");
+        if response.clicked() {
+            state.selected_project = Some(project.id);
+        }
+        if response.double_clicked() {
+            inline_edit::begin(ui, &mut state.inline_edit, id, &project.name);
+        }
+        response.context_menu(|ui| {
+            if ui.button("Delete project").clicked() {
+                commands.push(Command::DeleteProject {
+                    index,
+                    project: project.clone(),
+                });
+                ui.close_menu();
+            }
+        });
+    }
+
+    ui.add_space(5.0);
+    let response = ui
+        .text_edit_singleline(&mut state.new_project_input)
+        .on_hover_text("New project name (Press Enter to add)");
+    if response.lost_focus()
+        && ui.input(|i| i.key_pressed(egui::Key::Enter))
+        && !state.new_project_input.trim().is_empty()
+    {
+        let project = Project {
+            id: app_data::new_id(),
+            name: state.new_project_input.trim().to_owned(),
+            tasks: Vec::new(),
+        };
+        state.selected_project = Some(project.id);
+        commands.push(Command::AddProject {
+            index: app_data.projects.len(),
+            project,
+        });
+        state.new_project_input.clear();
+    }
+}
+
+// Drag handle, completion checkbox, editable text, focus toggle and
+// dependency menu for a single task.
+fn task_row(
+    ui: &mut egui::Ui,
+    task: TaskRef,
+    todo: &TodoItem,
+    app_data: &AppData,
+    state: &mut TodoWindowState,
+    focus: &mut Option<FocusTarget>,
+    commands: &mut Vec<Command>,
+) {
+    drag_handle(ui, task);
+
+    // Blocked tasks can't be ticked off until the tasks they wait on are done.
+    let blockers = app_data.open_blockers(todo);
+    let is_blocked = !blockers.is_empty() && !todo.is_done();
+
+    let mut completed = todo.is_done();
+    if ui
+        .add_enabled(!is_blocked, egui::Checkbox::without_text(&mut completed))
+        .changed()
+    {
+        commands.push(Command::SetStatus {
+            task,
+            old: todo.status,
+            new: if completed { STATUS_DONE } else { STATUS_TODO },
+            old_completed_on: todo.completed_on,
+        });
+    }
+
+    if let Some(priority) = todo.priority {
+        ui.label(egui::RichText::new(format!("({priority})")).strong());
+    }
+
+    let mut text = egui::RichText::new(&todo.text);
+    if is_blocked {
+        text = text.color(ui.visuals().weak_text_color());
+    }
+    let id = egui::Id::new(("todo_edit", todo.id));
+    if let Some(new) = inline_edit::editable_label(ui, id, text, &todo.text, &mut state.inline_edit)
+    {
+        commands.push(Command::EditTodo {
+            task,
+            old: todo.text.clone(),
+            new,
+        });
+    }
+
+    if is_blocked {
+        let names: Vec<_> = blockers.iter().map(|b| b.text.as_str()).collect();
+        ui.label(egui::RichText::new("⛔").weak())
+            .on_hover_text(format!("Blocked by: {}", names.join(", ")));
+    }
+
+    // Statuses other than to do/done only exist on the board, so name them here.
+    if todo.status != STATUS_TODO
+        && !todo.is_done()
+        && let Some(column) = app_data.column_name(todo.status)
+    {
+        ui.label(egui::RichText::new(format!("[{column}]")).weak());
+    }
+
+    // Dated tasks that came from a project keep a tag naming it.
+    if let (TaskList::Date(_), Some(project)) =
+        (task.list, todo.project.and_then(|id| app_data.project(id)))
+    {
+        ui.label(egui::RichText::new(format!("📁 {}", project.name)).weak());
+    }
+
+    let is_focused = focus.is_some_and(|f| f.task == todo.id);
+    let focus_button = ui
+        .selectable_label(is_focused, "🎯")
+        .on_hover_text("Attribute focus time to this task");
+    if focus_button.clicked() {
+        *focus = (!is_focused).then_some(FocusTarget {
+            task: todo.id,
+            project: todo.project,
+        });
+    }
+
+    ui.menu_button("🔗", |ui| {
+        dependency_menu(ui, task, todo, app_data, commands)
+    })
+    .response
+    .on_hover_text("Dependencies");
+}
+
+// Lists what a task waits on, and the open tasks it could be made to wait on.
+fn dependency_menu(
+    ui: &mut egui::Ui,
+    task: TaskRef,
+    todo: &TodoItem,
+    app_data: &AppData,
+    commands: &mut Vec<Command>,
+) {
+    ui.set_max_width(240.0);
+    if !todo.blocked_by.is_empty() {
+        ui.label(egui::RichText::new("Blocked by").strong());
+        for &blocker in &todo.blocked_by {
+            let name = app_data
+                .task_by_id(blocker)
+                .map_or("(deleted task)", |t| t.text.as_str());
+            if ui
+                .button(format!("❌ {name}"))
+                .on_hover_text("Remove dependency")
+                .clicked()
+            {
+                commands.push(Command::RemoveDependency { task, blocker });
+                ui.close_menu();
+            }
+        }
+        ui.separator();
+    }
+
+    ui.label(egui::RichText::new("Add blocker").strong());
+    egui::ScrollArea::vertical()
+        .max_height(200.0)
+        .show(ui, |ui| {
+            let candidates = app_data
+                .all_tasks()
+                .filter(|c| !c.is_done() && c.id != todo.id && !todo.blocked_by.contains(&c.id));
+            for candidate in candidates {
+                let creates_cycle = app_data.would_create_cycle(todo.id, candidate.id);
+                let button = ui
+                    .add_enabled(!creates_cycle, egui::Button::new(&candidate.text))
+                    .on_disabled_hover_text("This task already waits on the current one");
+                if button.clicked() {
+                    commands.push(Command::AddDependency {
+                        task,
+                        blocker: candidate.id,
+                    });
+                    ui.close_menu();
+                }
+            }
+        });
+}
+
+// A grab handle that starts dragging the referenced task.
+fn drag_handle(ui: &mut egui::Ui, task: TaskRef) {
+    let id = egui::Id::new(("todo_drag", task.list, task.index));
+    ui.dnd_drag_source(id, task, |ui| {
+        ui.label("☰");
+    })
+    .response
+    .on_hover_text("Drag to reorder, or drop on a calendar day to reschedule");
+}
@@ -0,0 +1,100 @@
+use crate::webdav::{SyncStatus, WebDavConfig};
+use eframe::egui;
+
+/// What the user asked for in the WebDAV window.
+pub enum WebDavAction {
+    /// Save the form and start syncing with it.
+    Enable,
+    Disable,
+    SyncNow,
+}
+
+/// Draws the WebDAV settings. `status` is `None` while WebDAV sync is off.
+pub fn draw_webdav_window(
+    ctx: &egui::Context,
+    is_open: &mut bool,
+    form: &mut WebDavConfig,
+    status: Option<&SyncStatus>,
+) -> Option<WebDavAction> {
+    let mut action = None;
+    egui::Window::new("WebDAV Sync")
+        .open(is_open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label("Syncs with your other devices through a WebDAV server, such as Nextcloud.");
+            ui.weak("The password is stored on this computer only.");
+            ui.separator();
+
+            egui::Grid::new("webdav_form")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Folder URL:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut form.url)
+                            .hint_text("https://example.com/dav/focushub/")
+                            .desired_width(280.0),
+                    );
+                    ui.end_row();
+                    ui.label("Username:");
+                    ui.text_edit_singleline(&mut form.username);
+                    ui.end_row();
+                    ui.label("Password:");
+                    ui.add(egui::TextEdit::singleline(&mut form.password).password(true));
+                    ui.end_row();
+                    ui.label("Sync every:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut form.interval_minutes).range(0..=1440));
+                        ui.label("minutes");
+                    });
+                    ui.end_row();
+                });
+            ui.weak("Set 0 minutes to only sync on demand and when closing.");
+
+            if let Some(status) = status {
+                ui.separator();
+                match status {
+                    SyncStatus::NotSynced => ui.label("Not synced yet."),
+                    SyncStatus::Syncing => ui.label("Syncing…"),
+                    SyncStatus::Synced(time) => {
+                        ui.label(format!("Last synced {}.", time.format("%Y-%m-%d %H:%M")))
+                    }
+                    SyncStatus::Failed(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, format!("Sync failed: {e}"))
+                    }
+                };
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                let valid = form.url.starts_with("http://") || form.url.starts_with("https://");
+                let save = if status.is_some() { "Save" } else { "Turn On" };
+                if ui.add_enabled(valid, egui::Button::new(save)).clicked() {
+                    action = Some(WebDavAction::Enable);
+                }
+                if status.is_some() {
+                    if ui.button("Sync Now").clicked() {
+                        action = Some(WebDavAction::SyncNow);
+                    }
+                    if ui.button("Turn Off").clicked() {
+                        action = Some(WebDavAction::Disable);
+                    }
+                }
+            });
+        });
+    action
+}
+
+/// A short label for the top bar, with the details on hover.
+pub fn draw_webdav_status(ui: &mut egui::Ui, status: &SyncStatus) -> egui::Response {
+    match status {
+        SyncStatus::NotSynced => ui.weak("☁ Not synced"),
+        SyncStatus::Syncing => ui.weak("☁ Syncing…"),
+        SyncStatus::Synced(time) => ui
+            .weak(format!("☁ Synced {}", time.format("%H:%M")))
+            .on_hover_text(format!("Last synced {}", time.format("%Y-%m-%d %H:%M"))),
+        SyncStatus::Failed(e) => ui
+            .colored_label(ui.visuals().warn_fg_color, "☁ Sync failed")
+            .on_hover_text(e),
+    }
+}
@@ -0,0 +1,453 @@
+//! Syncing through a WebDAV server, such as Nextcloud or a NAS.
+//!
+//! The server stands in for the shared folder of [`crate::sync`]: a
+//! background job downloads the other devices' files into a local mirror
+//! folder, where the folder sync merges them as usual, and uploads this
+//! device's file from it. The job runs every few minutes or on demand.
+//!
+//! The server details, password included, are kept in `focushub_webdav.json`
+//! next to the data file, so they never travel with the data to other devices.
+//!
+//! `examples/webdav_server.rs` is a minimal stand-in server for trying this
+//! out locally.
+
+use crate::app_data;
+use crate::sync;
+use base64::Engine;
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CONFIG_FILE: &str = "focushub_webdav.json";
+const MIRROR_DIR: &str = "focushub_webdav";
+// Inside the mirror, the server it mirrors.
+const SERVER_FILE: &str = "server.txt";
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Where the server is and how to log in.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebDavConfig {
+    pub enabled: bool,
+    /// The folder on the server, e.g. `https://cloud.example.com/remote.php/dav/files/me/focushub/`.
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    /// Minutes between automatic syncs; 0 to only sync on demand.
+    pub interval_minutes: u32,
+}
+
+impl Default for WebDavConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            username: String::new(),
+            password: String::new(),
+            interval_minutes: 5,
+        }
+    }
+}
+
+pub fn load_config() -> WebDavConfig {
+    app_data::local_path(CONFIG_FILE)
+        .and_then(fs::read_to_string)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_config(config: &WebDavConfig) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(
+        app_data::local_path(CONFIG_FILE)?,
+        serde_json::to_string_pretty(config)?,
+    )?;
+    Ok(())
+}
+
+/// Deletes the settings and the mirror, which hold the password and
+/// plaintext copies of every device's data.
+pub fn remove_local_files() -> Result<(), std::io::Error> {
+    let config = app_data::local_path(CONFIG_FILE)?;
+    if config.exists() {
+        fs::remove_file(config)?;
+    }
+    let mirror = app_data::local_path(MIRROR_DIR)?;
+    if mirror.exists() {
+        fs::remove_dir_all(mirror)?;
+    }
+    Ok(())
+}
+
+/// How the last sync with the server went, for the top bar.
+#[derive(Clone, Debug)]
+pub enum SyncStatus {
+    NotSynced,
+    Syncing,
+    Synced(NaiveDateTime),
+    Failed(String),
+}
+
+// The few WebDAV requests the sync needs, against one collection.
+struct Client {
+    agent: ureq::Agent,
+    url: String,
+    auth: Option<String>,
+}
+
+impl Client {
+    fn new(config: &WebDavConfig) -> Self {
+        let mut url = config.url.trim().to_owned();
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+        let auth = (!config.username.is_empty()).then(|| {
+            let credentials = format!("{}:{}", config.username, config.password);
+            let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+            format!("Basic {encoded}")
+        });
+        Self {
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            url,
+            auth,
+        }
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = self.agent.request(method, url);
+        match &self.auth {
+            Some(auth) => request.set("Authorization", auth),
+            None => request,
+        }
+    }
+
+    /// The files in the collection with their ETags, creating it if it is missing.
+    fn list(&self) -> Result<Vec<(String, String)>, Error> {
+        const BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?><propfind xmlns="DAV:"><prop><getetag/><getlastmodified/></prop></propfind>"#;
+        let response = match self
+            .request("PROPFIND", &self.url)
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml")
+            .send_string(BODY)
+        {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => {
+                self.request("MKCOL", &self.url).call()?;
+                return Ok(Vec::new());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        parse_listing(&response.into_string()?)
+    }
+
+    fn get(&self, name: &str) -> Result<String, Error> {
+        let url = format!("{}{name}", self.url);
+        Ok(self.request("GET", &url).call()?.into_string()?)
+    }
+
+    fn put(&self, name: &str, text: &str) -> Result<(), Error> {
+        let url = format!("{}{name}", self.url);
+        self.request("PUT", &url)
+            .set("Content-Type", "application/json")
+            .send_string(text)?;
+        Ok(())
+    }
+}
+
+// Reads file names and version tags out of a PROPFIND multistatus response.
+// Servers without ETags get the modification time as the tag instead.
+fn parse_listing(xml: &str) -> Result<Vec<(String, String)>, Error> {
+    let document = roxmltree::Document::parse(xml)?;
+    let mut files = Vec::new();
+    for response in document
+        .descendants()
+        .filter(|n| n.tag_name().name() == "response")
+    {
+        let text = |name: &str| {
+            response
+                .descendants()
+                .find(|n| n.tag_name().name() == name)
+                .and_then(|n| n.text())
+                .unwrap_or_default()
+        };
+        let href = text("href");
+        // The collection itself is listed too, with a trailing slash.
+        let Some(name) = href.rsplit('/').next().filter(|n| !n.is_empty()) else {
+            continue;
+        };
+        let tag = match text("getetag") {
+            "" => text("getlastmodified"),
+            etag => etag,
+        };
+        files.push((name.to_owned(), tag.to_owned()));
+    }
+    Ok(files)
+}
+
+// What a finished job hands back to the app.
+struct JobResult {
+    etags: HashMap<String, String>,
+    uploaded: Option<String>,
+    outcome: Result<(), String>,
+}
+
+// Downloads every other device's file that changed on the server, then
+// uploads this device's file if it changed since the last upload.
+fn run_job(
+    client: &Client,
+    folder: &Path,
+    own_file: &str,
+    etags: &mut HashMap<String, String>,
+    uploaded: &mut Option<String>,
+) -> Result<(), Error> {
+    fs::create_dir_all(folder)?;
+    for (name, etag) in client.list()? {
+        if name == own_file || !name.ends_with(".json") || etags.get(&name) == Some(&etag) {
+            continue;
+        }
+        let text = client.get(&name)?;
+        let partial = folder.join(format!("{name}.partial"));
+        fs::write(&partial, text)?;
+        fs::rename(&partial, folder.join(&name))?;
+        etags.insert(name, etag);
+    }
+    if let Ok(text) = fs::read_to_string(folder.join(own_file))
+        && uploaded.as_ref() != Some(&text)
+    {
+        client.put(own_file, &text)?;
+        *uploaded = Some(text);
+    }
+    Ok(())
+}
+
+/// Keeps the local mirror folder and the server in step.
+pub struct WebDavSync {
+    pub config: WebDavConfig,
+    mirror: PathBuf,
+    /// ETags of the other devices' files as last downloaded.
+    etags: HashMap<String, String>,
+    /// This device's file as last uploaded.
+    uploaded: Option<String>,
+    job: Option<Receiver<JobResult>>,
+    last_start: Option<Instant>,
+    pub status: SyncStatus,
+}
+
+impl WebDavSync {
+    /// Sets up the mirror for `config`, emptying it first if it held
+    /// another server's files.
+    pub fn new(config: WebDavConfig) -> Result<Self, std::io::Error> {
+        let mirror = app_data::local_path(MIRROR_DIR)?;
+        let server = mirror.join(SERVER_FILE);
+        if fs::read_to_string(&server).ok().as_deref() != Some(config.url.trim()) {
+            if mirror.exists() {
+                fs::remove_dir_all(&mirror)?;
+            }
+            // The mirror stays in one place, so the base must start over too.
+            sync::remove_base()?;
+            fs::create_dir_all(&mirror)?;
+            fs::write(&server, config.url.trim())?;
+        }
+        Ok(Self {
+            config,
+            mirror,
+            etags: HashMap::new(),
+            uploaded: None,
+            job: None,
+            last_start: None,
+            status: SyncStatus::NotSynced,
+        })
+    }
+
+    /// The local folder the folder sync runs against.
+    pub fn mirror(&self) -> &Path {
+        &self.mirror
+    }
+
+    /// Whether the next automatic sync is due.
+    pub fn due(&self) -> bool {
+        let interval = Duration::from_secs(self.config.interval_minutes as u64 * 60);
+        self.job.is_none()
+            && (self.last_start.is_none()
+                || self.config.interval_minutes > 0
+                    && self.last_start.is_some_and(|t| t.elapsed() >= interval))
+    }
+
+    /// Starts a sync in the background unless one is already running.
+    pub fn start(&mut self, device: &str) {
+        if self.job.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let client = Client::new(&self.config);
+        let folder = sync::device_folder(&self.mirror);
+        let own_file = format!("{device}.json");
+        let mut etags = self.etags.clone();
+        let mut uploaded = self.uploaded.clone();
+        thread::spawn(move || {
+            let outcome = run_job(&client, &folder, &own_file, &mut etags, &mut uploaded)
+                .map_err(|e| e.to_string());
+            tx.send(JobResult {
+                etags,
+                uploaded,
+                outcome,
+            })
+            .ok();
+        });
+        self.job = Some(rx);
+        self.last_start = Some(Instant::now());
+        self.status = SyncStatus::Syncing;
+    }
+
+    /// Picks up a finished sync. Returns `Some(true)` once new files may
+    /// have arrived, `Some(false)` if the sync failed.
+    pub fn poll(&mut self) -> Option<bool> {
+        let result = match self.job.as_ref()?.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            // The job died without answering; the next sync starts afresh.
+            Err(TryRecvError::Disconnected) => {
+                self.job = None;
+                self.status = SyncStatus::Failed("the sync stopped unexpectedly".to_owned());
+                return Some(false);
+            }
+        };
+        self.job = None;
+        self.etags = result.etags;
+        self.uploaded = result.uploaded;
+        match result.outcome {
+            Ok(()) => {
+                self.status = SyncStatus::Synced(Local::now().naive_local());
+                Some(true)
+            }
+            Err(e) => {
+                self.status = SyncStatus::Failed(e);
+                Some(false)
+            }
+        }
+    }
+
+    /// Uploads this device's file before the app closes, waiting for it.
+    pub fn upload_now(&mut self, device: &str) -> Result<(), String> {
+        let folder = sync::device_folder(&self.mirror);
+        let own_file = format!("{device}.json");
+        let Ok(text) = fs::read_to_string(folder.join(&own_file)) else {
+            return Ok(());
+        };
+        if self.uploaded.as_ref() == Some(&text) {
+            return Ok(());
+        }
+        Client::new(&self.config)
+            .put(&own_file, &text)
+            .map_err(|e| e.to_string())?;
+        self.uploaded = Some(text);
+        Ok(())
+    }
+}
+
+// The example server, for the tests to sync through.
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "../examples/webdav_server.rs"]
+mod stand_in;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Nextcloud answer, trimmed: the collection, then its files, one
+    // without an ETag.
+    const NEXTCLOUD: &str = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:s="http://sabredav.org/ns" xmlns:oc="http://owncloud.org/ns">
+ <d:response>
+  <d:href>/remote.php/dav/files/me/focushub/</d:href>
+  <d:propstat><d:prop><d:getetag>&quot;65f1c0a2b3d4e&quot;</d:getetag></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>
+ </d:response>
+ <d:response>
+  <d:href>/remote.php/dav/files/me/focushub/laptop.json</d:href>
+  <d:propstat><d:prop><d:getetag>&quot;a1b2c3&quot;</d:getetag><d:getlastmodified>Sat, 01 Mar 2025 10:00:00 GMT</d:getlastmodified></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>
+ </d:response>
+ <d:response>
+  <d:href>/remote.php/dav/files/me/focushub/phone.json</d:href>
+  <d:propstat><d:prop><d:getlastmodified>Sat, 01 Mar 2025 11:00:00 GMT</d:getlastmodified></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>
+  <d:propstat><d:prop><d:getetag/></d:prop><d:status>HTTP/1.1 404 Not Found</d:status></d:propstat>
+ </d:response>
+</d:multistatus>"#;
+
+    #[test]
+    fn listings_give_files_and_their_tags() {
+        assert_eq!(
+            parse_listing(NEXTCLOUD).unwrap(),
+            [
+                ("laptop.json".to_owned(), "\"a1b2c3\"".to_owned()),
+                (
+                    "phone.json".to_owned(),
+                    "Sat, 01 Mar 2025 11:00:00 GMT".to_owned()
+                ),
+            ]
+        );
+        assert!(parse_listing("<d:multistatus").is_err());
+    }
+
+    #[test]
+    fn two_mirrors_sync_through_the_stand_in() {
+        let root = std::env::temp_dir().join("focushub-test-webdav");
+        fs::remove_dir_all(&root).ok();
+        let served = root.join("server");
+        fs::create_dir_all(&served).unwrap();
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        thread::spawn(move || stand_in::serve(&server, &served, Some("Basic bWU6c2VjcmV0")));
+
+        let config = WebDavConfig {
+            url: format!("http://127.0.0.1:{port}/focushub"),
+            username: "me".to_owned(),
+            password: "secret".to_owned(),
+            ..WebDavConfig::default()
+        };
+        let client = Client::new(&config);
+        let (a, b) = (root.join("a"), root.join("b"));
+        let (mut a_etags, mut a_uploaded) = (HashMap::new(), None);
+        let (mut b_etags, mut b_uploaded) = (HashMap::new(), None);
+        let run = |folder: &Path, own: &str, etags: &mut _, uploaded: &mut _| {
+            run_job(&client, folder, own, etags, uploaded).unwrap()
+        };
+
+        // The first job creates the collection; A's file goes up, B gets it.
+        fs::create_dir_all(&a).unwrap();
+        fs::write(a.join("a.json"), "a 1").unwrap();
+        run(&a, "a.json", &mut a_etags, &mut a_uploaded);
+        assert_eq!(a_uploaded.as_deref(), Some("a 1"));
+        run(&b, "b.json", &mut b_etags, &mut b_uploaded);
+        assert_eq!(fs::read_to_string(b.join("a.json")).unwrap(), "a 1");
+
+        fs::write(b.join("b.json"), "b 1").unwrap();
+        run(&b, "b.json", &mut b_etags, &mut b_uploaded);
+        run(&a, "a.json", &mut a_etags, &mut a_uploaded);
+        assert_eq!(fs::read_to_string(a.join("b.json")).unwrap(), "b 1");
+
+        // Unchanged files aren't downloaded again...
+        fs::write(a.join("b.json"), "kept").unwrap();
+        run(&a, "a.json", &mut a_etags, &mut a_uploaded);
+        assert_eq!(fs::read_to_string(a.join("b.json")).unwrap(), "kept");
+        // ...but changed ones are.
+        fs::write(b.join("b.json"), "b 2, longer").unwrap();
+        run(&b, "b.json", &mut b_etags, &mut b_uploaded);
+        run(&a, "a.json", &mut a_etags, &mut a_uploaded);
+        assert_eq!(fs::read_to_string(a.join("b.json")).unwrap(), "b 2, longer");
+
+        let wrong = Client::new(&WebDavConfig {
+            password: "guess".to_owned(),
+            ..config
+        });
+        let error = run_job(&wrong, &a, "a.json", &mut a_etags, &mut a_uploaded).unwrap_err();
+        assert!(error.to_string().contains("401"), "{error}");
+    }
+}
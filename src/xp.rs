@@ -0,0 +1,70 @@
+use crate::app_data::{AppData, LevelCurve, Stats};
+
+pub const XP_PER_MINUTE: u64 = 1;
+pub const XP_PER_TASK: u64 = 10;
+// Far beyond any real history; only stops a degenerate curve from looping forever.
+const MAX_LEVEL: u32 = 10_000;
+
+pub struct LevelInfo {
+    pub level: u32,
+    pub xp: u64,
+    /// XP earned since reaching the current level.
+    pub into_level: u64,
+    /// XP between the current level and the next.
+    pub level_span: u64,
+}
+
+impl LevelInfo {
+    pub fn fraction(&self) -> f32 {
+        self.into_level as f32 / self.level_span.max(1) as f32
+    }
+}
+
+/// Total XP, always derived from the study log and task lists so that
+/// editing history never leaves a stale total behind.
+pub fn total(data: &AppData, stats: &Stats) -> u64 {
+    let minutes = stats.daily_study_seconds.values().sum::<u64>() / 60;
+    let tasks = data.all_tasks().filter(|t| t.is_done()).count() as u64;
+    minutes * XP_PER_MINUTE + tasks * XP_PER_TASK
+}
+
+/// Total XP needed to reach `level`; level 1 needs none.
+pub fn xp_for_level(curve: &LevelCurve, level: u32) -> u64 {
+    (curve.base as f64 * ((level - 1) as f64).powf(curve.exponent as f64)).round() as u64
+}
+
+pub fn level_info(xp: u64, curve: &LevelCurve) -> LevelInfo {
+    let mut level = 1;
+    while level < MAX_LEVEL && xp >= xp_for_level(curve, level + 1) {
+        level += 1;
+    }
+    let floor = xp_for_level(curve, level);
+    LevelInfo {
+        level,
+        xp,
+        into_level: xp.saturating_sub(floor),
+        level_span: xp_for_level(curve, level + 1).saturating_sub(floor),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degenerate_curves_still_give_a_level() {
+        let flat = LevelCurve {
+            base: 0,
+            exponent: 0.0,
+        };
+        assert_eq!(level_info(500, &flat).level, MAX_LEVEL);
+    }
+
+    #[test]
+    fn curves_are_clamped_when_read() {
+        let settings: crate::app_data::Settings =
+            serde_json::from_str(r#"{"level_curve": {"base": 0, "exponent": -2.0}}"#).unwrap();
+        assert_eq!(settings.level_curve.base, 1);
+        assert_eq!(settings.level_curve.exponent, 1.0);
+    }
+}